@@ -0,0 +1,268 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::state::with_arg_buf;
+use crate::{ModuleId, RawQuery, RawResult, RawTransaction, MODULE_ID_BYTES};
+
+/// A type the arguments of methods can have, described by the structure of
+/// its archived form rather than by its name.
+///
+/// Two types with the same description archive to the same layout, so the
+/// host and the module agree on the arguments of a method regardless of
+/// where the types are defined or how they are named. Integers of the same
+/// width are described the same regardless of their sign.
+///
+/// Structs are described by their fields, in order, using [`abi_type!`].
+pub trait AbiType {
+    /// Feed the description of the type to the hasher.
+    fn describe(hasher: &mut TypeHasher);
+}
+
+/// Hashes the descriptions of types using 64 bit FNV-1a.
+#[derive(Debug)]
+pub struct TypeHasher(u64);
+
+impl TypeHasher {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    /// Feed a part of a description to the hasher.
+    pub fn write(&mut self, part: &str) {
+        for byte in part.bytes().chain([b';']) {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+}
+
+/// Return the hash of the description of the given type.
+pub fn type_hash<T: AbiType + ?Sized>() -> u64 {
+    let mut hasher = TypeHasher(TypeHasher::OFFSET);
+    T::describe(&mut hasher);
+    hasher.0
+}
+
+/// Implement [`AbiType`] for a struct, given the types of its fields in the
+/// order they are declared.
+///
+/// ```ignore
+/// struct Transfer {
+///     to: ModuleId,
+///     amount: u64,
+/// }
+///
+/// dallo::abi_type!(Transfer: ModuleId, u64);
+/// ```
+#[macro_export]
+macro_rules! abi_type {
+    ($ty:ty: $($field:ty),* $(,)?) => {
+        impl $crate::AbiType for $ty {
+            fn describe(hasher: &mut $crate::TypeHasher) {
+                hasher.write("struct");
+                $(<$field as $crate::AbiType>::describe(hasher);)*
+                hasher.write("end");
+            }
+        }
+    };
+}
+
+macro_rules! describe_as {
+    ($($ty:ty => $desc:literal),* $(,)?) => {
+        $(
+            impl AbiType for $ty {
+                fn describe(hasher: &mut TypeHasher) {
+                    hasher.write($desc);
+                }
+            }
+        )*
+    };
+}
+
+describe_as!(
+    bool => "bool",
+    char => "char",
+    u8 => "int8",
+    i8 => "int8",
+    u16 => "int16",
+    i16 => "int16",
+    u32 => "int32",
+    i32 => "int32",
+    u64 => "int64",
+    i64 => "int64",
+    u128 => "int128",
+    i128 => "int128",
+    usize => "int32",
+    isize => "int32",
+    f32 => "float32",
+    f64 => "float64",
+    String => "string",
+);
+
+impl<T: AbiType> AbiType for Vec<T> {
+    fn describe(hasher: &mut TypeHasher) {
+        hasher.write("vec");
+        T::describe(hasher);
+    }
+}
+
+impl<T: AbiType> AbiType for Option<T> {
+    fn describe(hasher: &mut TypeHasher) {
+        hasher.write("option");
+        T::describe(hasher);
+    }
+}
+
+impl<T: AbiType> AbiType for Box<T> {
+    fn describe(hasher: &mut TypeHasher) {
+        hasher.write("box");
+        T::describe(hasher);
+    }
+}
+
+impl<T: AbiType, const N: usize> AbiType for [T; N] {
+    fn describe(hasher: &mut TypeHasher) {
+        let mut digits = [0u8; 20];
+        hasher.write("array");
+        hasher.write(decimal(N, &mut digits));
+        T::describe(hasher);
+    }
+}
+
+// Tuples archive like structs with the same fields.
+macro_rules! describe_tuples {
+    ($(($($name:ident),*)),* $(,)?) => {
+        $(
+            impl<$($name: AbiType),*> AbiType for ($($name,)*) {
+                fn describe(hasher: &mut TypeHasher) {
+                    hasher.write("struct");
+                    $($name::describe(hasher);)*
+                    hasher.write("end");
+                }
+            }
+        )*
+    };
+}
+
+impl AbiType for () {
+    fn describe(hasher: &mut TypeHasher) {
+        hasher.write("unit");
+    }
+}
+
+describe_tuples!(
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F),
+    (A, B, C, D, E, F, G),
+    (A, B, C, D, E, F, G, H),
+);
+
+crate::abi_type!(ModuleId: [u8; MODULE_ID_BYTES]);
+crate::abi_type!(RawQuery: u32, Vec<u8>);
+crate::abi_type!(RawTransaction: u32, Vec<u8>);
+crate::abi_type!(RawResult: Vec<u8>);
+
+/// Write the given number in decimal to the buffer, returning the digits.
+fn decimal(mut n: usize, buf: &mut [u8; 20]) -> &str {
+    let mut start = buf.len();
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    core::str::from_utf8(&buf[start..]).expect("digits are valid UTF-8")
+}
+
+/// Declare the methods of the module together with the types of their
+/// arguments, and optionally the type of the errors they revert with.
+///
+/// ```ignore
+/// dallo::abi!(
+///     "push": i16,
+///     "pop": () => alloc::string::String,
+/// );
+/// ```
+///
+/// The host checks calls against the declaration before running them,
+/// failing calls to undeclared methods, or with arguments of a different
+/// type. Argument types are compared by the hash of their structure, as
+/// given by [`AbiType`], so the host and the module agree on them as long as
+/// they archive the same way. Error types are used by the host to render the
+/// errors methods revert with, and are given by name.
+#[macro_export]
+macro_rules! abi {
+    ($($name:literal: $arg:ty $(=> $err:ty)?),* $(,)?) => {
+        #[no_mangle]
+        unsafe fn __abi(_arg_len: u32) -> u32 {
+            $crate::write_abi(&[
+                $((
+                    $name,
+                    $crate::type_hash::<$arg>(),
+                    $crate::__abi_err!($($err)?),
+                )),*
+            ])
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __abi_err {
+    () => {
+        None
+    };
+    ($err:ty) => {
+        Some(::core::any::type_name::<$err>())
+    };
+}
+
+/// Write the names of the methods, the hashes of the types of their
+/// arguments in hexadecimal, and the types of their errors if declared to the
+/// argument buffer, one method per line with the fields separated by tabs,
+/// returning the number of bytes written.
+#[doc(hidden)]
+pub fn write_abi(methods: &[(&str, u64, Option<&str>)]) -> u32 {
+    with_arg_buf(|buf| {
+        let mut len = 0;
+        let mut write = |part: &[u8]| {
+            buf[len..len + part.len()].copy_from_slice(part);
+            len += part.len();
+        };
+
+        for (name, arg, err) in methods {
+            write(name.as_bytes());
+            write(b"\t");
+            write(&hex(*arg));
+            if let Some(err) = err {
+                write(b"\t");
+                write(err.as_bytes());
+            }
+            write(b"\n");
+        }
+        len as u32
+    })
+}
+
+/// Return the given hash as 16 lowercase hexadecimal digits.
+fn hex(hash: u64) -> [u8; 16] {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+    let mut hex = [0u8; 16];
+    for (i, digit) in hex.iter_mut().enumerate() {
+        *digit = DIGITS[(hash >> (60 - 4 * i)) as usize & 0xf];
+    }
+    hex
+}
@@ -0,0 +1,245 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Collections allocated in a scratch arena instead of the heap.
+//!
+//! The heap of a module only ever grows, so memory allocated for values that
+//! don't outlive a call is never reclaimed. Values allocated in the arena
+//! using [`scope`] are freed all at once when the scope ends, typically at
+//! the end of the call.
+//!
+//! ```ignore
+//! let sum = dallo::arena::scope(|arena| {
+//!     let mut squares = arena.vec();
+//!     squares.extend((0..n).map(|i| i * i));
+//!     squares.iter().sum::<u64>()
+//! });
+//! ```
+
+use core::alloc::{AllocError, Allocator, Layout};
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::ptr::NonNull;
+
+/// The size of the arena in bytes.
+pub const ARENA_BYTES: usize = 64 * 1024;
+
+#[repr(C, align(8))]
+struct ArenaBuf {
+    bytes: UnsafeCell<[u8; ARENA_BYTES]>,
+    ofs: UnsafeCell<usize>,
+    depth: UnsafeCell<usize>,
+}
+
+// modules are single threaded
+unsafe impl Sync for ArenaBuf {}
+
+static ARENA: ArenaBuf = ArenaBuf {
+    bytes: UnsafeCell::new([0; ARENA_BYTES]),
+    ofs: UnsafeCell::new(0),
+    depth: UnsafeCell::new(0),
+};
+
+/// A handle to the arena, valid for the duration of a [`scope`].
+///
+/// Allocation fails once the arena is full. Freeing only reclaims memory
+/// when done in reverse order of allocation, the rest being reclaimed at the
+/// end of the scope.
+#[derive(Debug, Clone, Copy)]
+pub struct Arena<'a> {
+    _scope: PhantomData<&'a mut ()>,
+}
+
+impl<'a> Arena<'a> {
+    /// Create an empty vector allocated in the arena.
+    pub fn vec<T>(self) -> Vec<'a, T> {
+        Vec::new_in(self)
+    }
+
+    /// Create an empty string allocated in the arena.
+    pub fn string(self) -> String<'a> {
+        String::new_in(self)
+    }
+}
+
+/// A vector allocated in the arena.
+pub type Vec<'a, T> = alloc::vec::Vec<T, Arena<'a>>;
+
+/// Run the given closure with a handle to the arena, freeing everything
+/// allocated in it during the closure once it returns.
+///
+/// Scopes can be nested, but only the outermost one frees memory, since an
+/// enclosing scope's handle may be used to allocate within an inner one.
+pub fn scope<F, R>(f: F) -> R
+where
+    F: for<'a> FnOnce(Arena<'a>) -> R,
+{
+    let depth = unsafe { &mut *ARENA.depth.get() };
+    *depth += 1;
+
+    let ret = f(Arena {
+        _scope: PhantomData,
+    });
+
+    let depth = unsafe { &mut *ARENA.depth.get() };
+    *depth -= 1;
+    if *depth == 0 {
+        unsafe { *ARENA.ofs.get() = 0 };
+    }
+
+    ret
+}
+
+/// Return the number of bytes currently allocated in the arena.
+pub fn used() -> usize {
+    unsafe { *ARENA.ofs.get() }
+}
+
+fn base() -> usize {
+    ARENA.bytes.get() as usize
+}
+
+unsafe impl Allocator for Arena<'_> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let ofs = unsafe { &mut *ARENA.ofs.get() };
+
+        let start = (base() + *ofs)
+            .checked_next_multiple_of(layout.align())
+            .ok_or(AllocError)?;
+        let end = start.checked_add(layout.size()).ok_or(AllocError)?;
+        if end > base() + ARENA_BYTES {
+            return Err(AllocError);
+        }
+        *ofs = end - base();
+
+        let ptr = NonNull::new(start as *mut u8).ok_or(AllocError)?;
+        Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        let ofs = &mut *ARENA.ofs.get();
+
+        // only the last allocation can be given back before the scope ends
+        if ptr.as_ptr() as usize + layout.size() == base() + *ofs {
+            *ofs = ptr.as_ptr() as usize - base();
+        }
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let ofs = &mut *ARENA.ofs.get();
+        let start = ptr.as_ptr() as usize;
+
+        // the last allocation can grow in place
+        if start + old_layout.size() == base() + *ofs
+            && start & (new_layout.align() - 1) == 0
+            && start + new_layout.size() <= base() + ARENA_BYTES
+        {
+            *ofs = start + new_layout.size() - base();
+            return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+        }
+
+        let new = self.allocate(new_layout)?;
+        core::ptr::copy_nonoverlapping(
+            ptr.as_ptr(),
+            new.as_ptr() as *mut u8,
+            old_layout.size(),
+        );
+        Ok(new)
+    }
+}
+
+/// A string allocated in the arena.
+pub struct String<'a> {
+    bytes: Vec<'a, u8>,
+}
+
+impl<'a> String<'a> {
+    pub fn new_in(arena: Arena<'a>) -> Self {
+        String {
+            bytes: Vec::new_in(arena),
+        }
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    pub fn as_str(&self) -> &str {
+        // only ever extended with valid strings
+        unsafe { core::str::from_utf8_unchecked(&self.bytes) }
+    }
+}
+
+impl Deref for String<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Write for String<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl fmt::Display for String<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Debug for String<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::fmt::Write;
+
+    // the arena is shared, so everything is tested in a single test to avoid
+    // running concurrently
+    #[test]
+    fn arena() {
+        scope(|arena| {
+            let mut v = Vec::new_in(arena);
+            v.extend(0..100u64);
+            assert!(used() >= 800);
+
+            scope(|inner| {
+                let mut s = String::new_in(inner);
+                write!(s, "{} items", v.len()).unwrap();
+                assert_eq!(s.as_str(), "100 items");
+
+                // allocating with the outer handle within the inner scope
+                v.extend(100..200u64);
+            });
+
+            assert_eq!(v.iter().sum::<u64>(), 19900);
+
+            let mut big: Vec<u8> = Vec::new_in(arena);
+            assert!(big.try_reserve_exact(ARENA_BYTES).is_err());
+        });
+
+        assert_eq!(used(), 0);
+    }
+}
@@ -4,6 +4,11 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use core::fmt::{self, Write};
+use core::ptr::addr_of_mut;
+
+use crate::bufwriter::BufWriter;
+
 extern "C" {
     pub fn host_debug(ofs: i32, len: u32);
 }
@@ -11,23 +16,46 @@ extern "C" {
 pub const DEBUG_BUFFER_SIZE: usize = 64 * 1024;
 pub static mut DEBUG_BUFFER: [u8; DEBUG_BUFFER_SIZE] = [0u8; DEBUG_BUFFER_SIZE];
 
-/// Macro to format and send debug output to the host
-#[macro_export]
-macro_rules! debug {
-	($($tt:tt)*) => {
-        #[allow(unused)]
-        use core::fmt::Write as _;
+/// Format the arguments into the debug buffer and send them to the host,
+/// dropping any output that does not fit in the buffer.
+pub fn write(args: fmt::Arguments) {
+    let buf = unsafe { &mut *addr_of_mut!(DEBUG_BUFFER) };
 
-        let buf = unsafe {&mut $crate::debug::DEBUG_BUFFER };
+    let len = {
+        let mut w = BufWriter::new(buf);
+        let _ = w.write_fmt(args);
+        w.ofs() as u32
+    };
+    let ptr = buf.as_ptr() as i32;
 
-        let len = {
-		    let mut w = $crate::bufwriter::BufWriter::new(buf);
-		    write!(&mut w, $($tt)*).unwrap();
-            w.ofs() as u32
-        };
-        let ptr = buf.as_ptr() as i32;
+    unsafe { host_debug(ptr, len) }
+}
 
-        unsafe { $crate::debug::host_debug(ptr, len) }
-	};
+/// Like [`write`], but only when the `debug` feature is enabled, doing
+/// nothing otherwise.
+pub fn write_debug(args: fmt::Arguments) {
+    #[cfg(feature = "debug")]
+    write(args);
+    #[cfg(not(feature = "debug"))]
+    let _ = args;
+}
 
+/// Macro to format and send debug output to the host.
+///
+/// The output is only sent when `dallo` is built with the `debug` feature,
+/// so it can be left in modules built for release.
+#[macro_export]
+macro_rules! debug {
+    ($($tt:tt)*) => {
+        $crate::debug::write_debug(format_args!($($tt)*))
+    };
+}
+
+/// Macro to format and send output to the host, regardless of the `debug`
+/// feature.
+#[macro_export]
+macro_rules! info {
+    ($($tt:tt)*) => {
+        $crate::debug::write(format_args!($($tt)*))
+    };
 }
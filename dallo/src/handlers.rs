@@ -8,33 +8,37 @@ use core::panic::PanicInfo;
 
 #[alloc_error_handler]
 #[allow(clippy::empty_loop)]
-fn foo(_: core::alloc::Layout) -> ! {
+fn foo(layout: core::alloc::Layout) -> ! {
+    extern "C" {
+        fn host_oom(size: u32);
+    }
+
+    // the host aborts the call, so this never returns
+    unsafe { host_oom(layout.size() as u32) }
     loop {}
 }
 
 #[panic_handler]
+#[allow(clippy::empty_loop)]
 fn panic(panic_info: &PanicInfo) -> ! {
-    #[cfg(debug)]
-    {
-        extern "C" {
-            pub(crate) fn host_panic(len: u32);
-        }
-
-        use dallo::bufwriter::BufWriter;
-
-        if let Some(msg) = panic_info.message() {
-            let len = crate::state::with_debug_buf(|b| {
-                let mut w = BufWriter::new(b);
-                core::fmt::write(&mut w, *msg).unwrap();
-                w.ofs() as u32
-            });
-            unsafe { host_panic(len) }
-        } else {
-            unsafe { host_panic(0) }
-        }
+    extern "C" {
+        fn host_panic(len: u32);
     }
-    let _ = panic_info;
-    unreachable!()
+
+    use core::fmt::Write;
+
+    use crate::bufwriter::BufWriter;
+
+    // a message too long for the buffer is reported truncated
+    let len = crate::state::with_arg_buf(|buf| {
+        let mut w = BufWriter::new(buf);
+        let _ = write!(w, "{}", panic_info);
+        w.ofs() as u32
+    });
+
+    // the host aborts the call, so this never returns
+    unsafe { host_panic(len) }
+    loop {}
 }
 
 #[lang = "eh_personality"]
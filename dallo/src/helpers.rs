@@ -8,12 +8,16 @@ use crate::state::with_arg_buf;
 use crate::SCRATCH_BUF_BYTES;
 
 use rkyv::ser::serializers::{
-    BufferScratch, BufferSerializer, CompositeSerializer,
+    BufferSerializer, CompositeSerializer, CompositeSerializerError,
 };
 use rkyv::ser::Serializer;
 use rkyv::{archived_root, Archive, Deserialize, Infallible, Serialize};
 
-use crate::types::StandardBufSerializer;
+use crate::types::{standard_scratch, StandardBufSerializer};
+
+/// Length reported to the host when the return of a call does not fit in the
+/// argument buffer.
+pub const RETURN_TOO_LARGE: u32 = u32::MAX;
 
 /// Wrap a query with its respective (de)serializers.
 ///
@@ -31,13 +35,7 @@ where
         let a: A = aa.deserialize(&mut rkyv::Infallible).unwrap();
         let ret = f(a);
 
-        let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
-        let scratch = BufferScratch::new(&mut sbuf);
-        let ser = BufferSerializer::new(buf);
-        let mut composite =
-            CompositeSerializer::new(ser, scratch, rkyv::Infallible);
-        composite.serialize_value(&ret).expect("infallible");
-        composite.pos() as u32
+        write_return(buf, &ret)
     })
 }
 
@@ -57,12 +55,24 @@ where
         let a: A = aa.deserialize(&mut rkyv::Infallible).unwrap();
         let ret = f(a);
 
-        let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
-        let scratch = BufferScratch::new(&mut sbuf);
-        let ser = BufferSerializer::new(buf);
-        let mut composite =
-            CompositeSerializer::new(ser, scratch, rkyv::Infallible);
-        composite.serialize_value(&ret).expect("infallible");
-        composite.pos() as u32
+        write_return(buf, &ret)
     })
 }
+
+/// Serialize the return of a call into the argument buffer, returning its
+/// length, or [`RETURN_TOO_LARGE`] if it does not fit.
+fn write_return<R>(buf: &mut [u8], ret: &R) -> u32
+where
+    R: for<'a> Serialize<StandardBufSerializer<'a>>,
+{
+    let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
+    let scratch = standard_scratch(&mut sbuf);
+    let ser = BufferSerializer::new(buf);
+    let mut composite =
+        CompositeSerializer::new(ser, scratch, rkyv::Infallible);
+    match composite.serialize_value(ret) {
+        Ok(_) => composite.pos() as u32,
+        Err(CompositeSerializerError::SerializerError(_)) => RETURN_TOO_LARGE,
+        Err(err) => panic!("return serialization failed: {:?}", err),
+    }
+}
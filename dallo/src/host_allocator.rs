@@ -6,6 +6,11 @@
 
 use alloc::alloc::{GlobalAlloc, Layout};
 
+/// Allocator using the heap managed by the host.
+///
+/// Allocations return a null pointer once the memory is exhausted, which
+/// fallible allocation APIs report as an error, and infallible ones route to
+/// the allocation error handler.
 pub struct HostAlloc;
 
 extern "C" {
@@ -4,7 +4,7 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-#![feature(alloc_error_handler, lang_items)]
+#![feature(alloc_error_handler, allocator_api, lang_items)]
 #![no_std]
 
 extern crate alloc;
@@ -13,9 +13,16 @@ mod snap;
 
 pub use snap::snap;
 
+mod abi;
+pub use abi::{type_hash, write_abi, AbiType, TypeHasher};
+
 mod state;
 pub use state::{
-    caller, emit, height, limit, native_query, query, query_raw, spent, State,
+    caller, code_hash_self, commit_id, defer, emit, entry_module, frame_limit,
+    frame_spent, heap_stats, height, limit, memory_limit, memory_pages,
+    memory_pressure, native_query, query, query_raw, query_selector, random,
+    random_bytes, revert, spent, subscribe, timestamp, try_query, tx_limit,
+    tx_meta, tx_spent, unsubscribe, State,
 };
 
 mod helpers;
@@ -24,11 +31,17 @@ pub use helpers::*;
 mod ops;
 pub use ops::*;
 
+mod selector;
+pub use selector::selector;
+
 mod types;
 pub use types::*;
 
+pub mod arena;
 pub mod bufwriter;
 pub mod debug;
+pub mod proxy;
+mod pure;
 
 /// How many bytes to use for scratch space when serializing
 pub const SCRATCH_BUF_BYTES: usize = 64;
@@ -36,6 +49,16 @@ pub const SCRATCH_BUF_BYTES: usize = 64;
 /// The size of the argument buffer in bytes
 pub const ARGBUF_LEN: usize = 64 * 1024;
 
+/// The alignment of the argument buffer, the same as the one of the buffers
+/// rkyv serializes to, so that archived arguments can be accessed in place.
+pub const ARGBUF_ALIGN: usize = 16;
+
+/// Return the number of bytes available in the argument buffer, which the
+/// serialized argument of a call - and its return - must fit in.
+pub const fn arg_capacity() -> usize {
+    ARGBUF_LEN
+}
+
 #[cfg(not(feature = "std"))]
 mod handlers;
 #[cfg(not(feature = "std"))]
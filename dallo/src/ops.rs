@@ -14,6 +14,15 @@ mod ext {
     }
 }
 
+/// Return the id of the module, as written by the host when deploying it.
+///
+/// # Panics
+/// If the host did not write the id, since using the zero id instead would
+/// go unnoticed.
 pub fn self_id() -> ModuleId {
-    unsafe { ext::SELF_ID }
+    let id = unsafe { ext::SELF_ID };
+    if id.is_uninitialized() {
+        panic!("SELF_ID was not written by the host");
+    }
+    id
 }
@@ -0,0 +1,74 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Forwarding calls received as a [`RawQuery`] or [`RawTransaction`] to
+//! other modules.
+//!
+//! Raw calls passed in arguments are not built by the module forwarding them,
+//! so their name and argument can't be trusted to be well formed. The
+//! functions here check them before making the call, instead of trapping
+//! halfway through copying them.
+//!
+//! ```ignore
+//! pub fn delegate(&self, module_id: ModuleId, raw: RawQuery) -> RawResult {
+//!     dallo::proxy::forward_query(module_id, &raw).unwrap_or_else(|err| {
+//!         panic!("failed forwarding query: {:?}", err)
+//!     })
+//! }
+//! ```
+
+use crate::state::{extern_query, extern_transaction, with_arg_buf};
+use crate::{
+    ArgumentTooLarge, ModuleId, RawQuery, RawResult, RawTransaction, ARGBUF_LEN,
+};
+
+/// Why a call could not be forwarded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForwardError {
+    /// The argument is longer than the data of the call, or the name is not
+    /// valid UTF-8.
+    Malformed,
+    /// The argument does not fit in the argument buffer.
+    ArgumentTooLarge(ArgumentTooLarge),
+}
+
+/// Forward the given query to a module, returning its result.
+pub fn forward_query(
+    module_id: ModuleId,
+    raw: &RawQuery,
+) -> Result<RawResult, ForwardError> {
+    let (name, arg) = raw.checked_parts().ok_or(ForwardError::Malformed)?;
+    let arg_len = write_arg(arg)?;
+
+    let ret_len = extern_query(module_id, name, arg_len);
+    Ok(with_arg_buf(|buf| RawResult::new(&buf[..ret_len as usize])))
+}
+
+/// Forward the given transaction to a module, returning its result.
+pub fn forward_transaction(
+    module_id: ModuleId,
+    raw: &RawTransaction,
+) -> Result<RawResult, ForwardError> {
+    let (name, arg) = raw.checked_parts().ok_or(ForwardError::Malformed)?;
+    let arg_len = write_arg(arg)?;
+
+    let ret_len = extern_transaction(module_id, name, arg_len);
+    Ok(with_arg_buf(|buf| RawResult::new(&buf[..ret_len as usize])))
+}
+
+/// Copy the argument of a call into the argument buffer, returning its
+/// length.
+fn write_arg(arg: &[u8]) -> Result<u32, ForwardError> {
+    if arg.len() > ARGBUF_LEN {
+        return Err(ForwardError::ArgumentTooLarge(ArgumentTooLarge {
+            required: arg.len(),
+            available: ARGBUF_LEN,
+        }));
+    }
+
+    with_arg_buf(|buf| buf[..arg.len()].copy_from_slice(arg));
+    Ok(arg.len() as u32)
+}
@@ -0,0 +1,20 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+/// Declare methods of the module as pure.
+///
+/// Pure methods are not allowed to change state. The host fails any
+/// transaction they attempt, and fails the call if they write to the state of
+/// a module, including to statics such as the arena and the debug buffer.
+/// Writing to the stack and the argument buffer is allowed, and memory they
+/// allocate on the heap is freed once they return.
+#[macro_export]
+macro_rules! pure {
+    ($($name:literal),* $(,)?) => {
+        #[no_mangle]
+        static PURE_METHODS: &str = concat!($($name, "\n"),*);
+    };
+}
@@ -0,0 +1,32 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+const FNV_OFFSET: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// Return the selector of the method with the given name, used to call it
+/// without passing its name, for instance using
+/// [`query_selector`](crate::query_selector).
+///
+/// The selector is the 32 bit FNV-1a hash of the name. Since this is a
+/// `const fn`, selectors can be computed at compile time:
+///
+/// ```
+/// const READ_VALUE: u32 = dallo::selector("read_value");
+/// ```
+pub const fn selector(name: &str) -> u32 {
+    let bytes = name.as_bytes();
+
+    let mut hash = FNV_OFFSET;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+
+    hash
+}
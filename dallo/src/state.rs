@@ -6,34 +6,36 @@
 
 use rkyv::{
     archived_root,
-    ser::serializers::{BufferScratch, BufferSerializer, CompositeSerializer},
+    ser::serializers::{BufferSerializer, CompositeSerializer},
     ser::Serializer,
     Archive, Deserialize, Infallible, Serialize,
 };
 
+use alloc::vec;
+use alloc::vec::Vec;
+
 use crate::{
-    RawQuery, RawResult, RawTransaction, StandardBufSerializer,
-    SCRATCH_BUF_BYTES,
+    standard_scratch, CallError, ErrorEnvelope, RawQuery, RawResult,
+    RawTransaction, StandardBufSerializer, ARGBUF_LEN, CALL_FAILED,
+    REVERT_CODE, SCRATCH_BUF_BYTES,
 };
 
 mod arg_buf {
     use crate::ARGBUF_LEN;
 
+    /// The argument buffer, aligned to [`ARGBUF_ALIGN`](crate::ARGBUF_ALIGN).
+    #[repr(C, align(16))]
+    pub struct ArgBuf([u8; ARGBUF_LEN]);
+
     #[no_mangle]
-    static mut A: [u64; ARGBUF_LEN / 8] = [0; ARGBUF_LEN / 8];
+    static mut A: ArgBuf = ArgBuf([0; ARGBUF_LEN]);
 
     pub fn with_arg_buf<F, R>(f: F) -> R
     where
         F: FnOnce(&mut [u8]) -> R,
     {
         let buf = unsafe { &mut A };
-        let first = &mut buf[0];
-        let slice = unsafe {
-            let first_byte: &mut u8 = core::mem::transmute(first);
-            core::slice::from_raw_parts_mut(first_byte, ARGBUF_LEN)
-        };
-
-        f(slice)
+        f(&mut buf.0)
     }
 }
 
@@ -54,29 +56,90 @@ mod ext {
             name_len: u32,
             arg_len: u32,
         ) -> u32;
+        pub(crate) fn tq(
+            mod_id: *const u8,
+            name: *const u8,
+            name_len: u32,
+            arg_len: u32,
+        ) -> u32;
+        pub(crate) fn tt(
+            mod_id: *const u8,
+            name: *const u8,
+            name_len: u32,
+            arg_len: u32,
+        ) -> u32;
+        pub(crate) fn revert(arg_len: u32);
+        pub(crate) fn qs(mod_id: *const u8, selector: u32, arg_len: u32)
+            -> u32;
+        pub(crate) fn ts(mod_id: *const u8, selector: u32, arg_len: u32)
+            -> u32;
 
         pub(crate) fn height() -> u32;
+        pub(crate) fn timestamp() -> u32;
+        pub(crate) fn heap_stats() -> u32;
+        pub(crate) fn memory_pages() -> u32;
+        pub(crate) fn memory_pressure() -> u32;
+        pub(crate) fn code_hash() -> u32;
+        pub(crate) fn commit_id() -> u32;
+        pub(crate) fn random(
+            domain: *const u8,
+            domain_len: u32,
+            len: u32,
+        ) -> u32;
         pub(crate) fn caller() -> u32;
+        pub(crate) fn entry_module() -> u32;
+        pub(crate) fn tx_meta() -> u32;
         pub(crate) fn emit(arg_len: u32);
+        pub(crate) fn defer(arg_len: u32);
+        pub(crate) fn subscribe(arg_len: u32);
+        pub(crate) fn unsubscribe(arg_len: u32);
         pub(crate) fn limit() -> u32;
         pub(crate) fn spent() -> u32;
+        pub(crate) fn tx_limit() -> u32;
+        pub(crate) fn tx_spent() -> u32;
     }
 }
 
-fn extern_query(module_id: ModuleId, name: &str, arg_len: u32) -> u32 {
+pub(crate) fn extern_query(
+    module_id: ModuleId,
+    name: &str,
+    arg_len: u32,
+) -> u32 {
     let mod_ptr = module_id.as_ptr();
     let name_ptr = name.as_ptr();
     let name_len = name.as_bytes().len() as u32;
     unsafe { ext::q(mod_ptr, name_ptr, name_len, arg_len) }
 }
 
-fn extern_transaction(module_id: ModuleId, name: &str, arg_len: u32) -> u32 {
+pub(crate) fn extern_transaction(
+    module_id: ModuleId,
+    name: &str,
+    arg_len: u32,
+) -> u32 {
     let mod_ptr = module_id.as_ptr();
     let name_ptr = name.as_ptr();
     let name_len = name.as_bytes().len() as u32;
     unsafe { ext::t(mod_ptr, name_ptr, name_len, arg_len) }
 }
 
+fn extern_try_query(module_id: ModuleId, name: &str, arg_len: u32) -> u32 {
+    let mod_ptr = module_id.as_ptr();
+    let name_ptr = name.as_ptr();
+    let name_len = name.len() as u32;
+    unsafe { ext::tq(mod_ptr, name_ptr, name_len, arg_len) }
+}
+
+fn extern_try_transaction(
+    module_id: ModuleId,
+    name: &str,
+    arg_len: u32,
+) -> u32 {
+    let mod_ptr = module_id.as_ptr();
+    let name_ptr = name.as_ptr();
+    let name_len = name.len() as u32;
+    unsafe { ext::tt(mod_ptr, name_ptr, name_len, arg_len) }
+}
+
 fn extern_native_query(name: &str, arg_len: u32) -> u32 {
     let name_ptr = name.as_ptr();
     let name_len = name.bytes().len() as u32;
@@ -118,7 +181,7 @@ where
 {
     let arg_len = with_arg_buf(|buf| {
         let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
-        let scratch = BufferScratch::new(&mut sbuf);
+        let scratch = standard_scratch(&mut sbuf);
         let ser = BufferSerializer::new(buf);
         let mut composite =
             CompositeSerializer::new(ser, scratch, rkyv::Infallible);
@@ -136,6 +199,128 @@ where
     })
 }
 
+/// Like [`query`], but calling the method with the given
+/// [`selector`](crate::selector) instead of passing its name.
+pub fn query_selector<Arg, Ret>(
+    mod_id: ModuleId,
+    selector: u32,
+    arg: Arg,
+) -> Ret
+where
+    Arg: for<'a> Serialize<StandardBufSerializer<'a>>,
+    Ret: Archive,
+    Ret::Archived: Deserialize<Ret, Infallible>,
+{
+    let arg_len = with_arg_buf(|buf| {
+        let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
+        let scratch = standard_scratch(&mut sbuf);
+        let ser = BufferSerializer::new(buf);
+        let mut composite =
+            CompositeSerializer::new(ser, scratch, rkyv::Infallible);
+
+        composite.serialize_value(&arg).expect("infallible");
+        composite.pos() as u32
+    });
+
+    let ret_len = unsafe { ext::qs(mod_id.as_ptr(), selector, arg_len) };
+
+    with_arg_buf(|buf| {
+        let slice = &buf[..ret_len as usize];
+        let ret = unsafe { archived_root::<Ret>(slice) };
+        ret.deserialize(&mut Infallible).expect("Infallible")
+    })
+}
+
+/// Like [`query`], but returning the failure of the call instead of aborting
+/// the calling module.
+///
+/// An error the callee reverted with using [`revert`] is returned as
+/// [`CallError::Reverted`], any other failure as [`CallError::Failed`] with
+/// the code of the error.
+pub fn try_query<Arg, Ret, Err>(
+    mod_id: ModuleId,
+    name: &str,
+    arg: Arg,
+) -> Result<Ret, CallError<Err>>
+where
+    Arg: for<'a> Serialize<StandardBufSerializer<'a>>,
+    Ret: Archive,
+    Ret::Archived: Deserialize<Ret, Infallible>,
+    Err: Archive,
+    Err::Archived: Deserialize<Err, Infallible>,
+{
+    let arg_len = with_arg_buf(|buf| {
+        let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
+        let scratch = standard_scratch(&mut sbuf);
+        let ser = BufferSerializer::new(buf);
+        let mut composite =
+            CompositeSerializer::new(ser, scratch, rkyv::Infallible);
+
+        composite.serialize_value(&arg).expect("infallible");
+        composite.pos() as u32
+    });
+
+    let ret_len = extern_try_query(mod_id, name, arg_len);
+    read_call_result(ret_len)
+}
+
+/// Read the return or the failure of a call made using [`try_query`] or
+/// [`State::try_transact`] from the argument buffer.
+fn read_call_result<Ret, Err>(ret_len: u32) -> Result<Ret, CallError<Err>>
+where
+    Ret: Archive,
+    Ret::Archived: Deserialize<Ret, Infallible>,
+    Err: Archive,
+    Err::Archived: Deserialize<Err, Infallible>,
+{
+    with_arg_buf(|buf| {
+        if ret_len & CALL_FAILED == 0 {
+            let slice = &buf[..ret_len as usize];
+            let ret = unsafe { archived_root::<Ret>(slice) };
+            return Ok(ret.deserialize(&mut Infallible).expect("Infallible"));
+        }
+
+        let len = (ret_len & !CALL_FAILED) as usize;
+        let envelope = unsafe { archived_root::<ErrorEnvelope>(&buf[..len]) };
+        if envelope.code != REVERT_CODE {
+            return Err(CallError::Failed(envelope.code));
+        }
+
+        // the payload is archived aligned, so it can be accessed in place
+        let err = unsafe { archived_root::<Err>(envelope.payload.as_slice()) };
+        Err(CallError::Reverted(
+            err.deserialize(&mut Infallible).expect("Infallible"),
+        ))
+    })
+}
+
+/// Abort the current call with the given error, which a caller using
+/// [`try_query`] or [`State::try_transact`] receives as
+/// [`CallError::Reverted`].
+///
+/// Callers using [`query`] or [`State::transact`] are aborted as well, as
+/// with a panic.
+#[allow(clippy::empty_loop)]
+pub fn revert<E>(err: E) -> !
+where
+    for<'a> E: Serialize<StandardBufSerializer<'a>>,
+{
+    let arg_len = with_arg_buf(|buf| {
+        let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
+        let scratch = standard_scratch(&mut sbuf);
+        let ser = BufferSerializer::new(buf);
+        let mut composite =
+            CompositeSerializer::new(ser, scratch, rkyv::Infallible);
+
+        composite.serialize_value(&err).unwrap();
+        composite.pos() as u32
+    });
+
+    // the host aborts the call, so this never returns
+    unsafe { ext::revert(arg_len) }
+    loop {}
+}
+
 pub fn query_raw(mod_id: ModuleId, raw: RawQuery) -> RawResult {
     with_arg_buf(|buf| {
         let bytes = raw.arg_bytes();
@@ -157,7 +342,7 @@ where
 {
     let arg_len = with_arg_buf(|buf| {
         let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
-        let scratch = BufferScratch::new(&mut sbuf);
+        let scratch = standard_scratch(&mut sbuf);
         let ser = BufferSerializer::new(buf);
         let mut composite =
             CompositeSerializer::new(ser, scratch, rkyv::Infallible);
@@ -185,6 +370,111 @@ pub fn height() -> u64 {
     })
 }
 
+/// Return the current timestamp, as set by the host.
+pub fn timestamp() -> u64 {
+    with_arg_buf(|buf| {
+        let ret_len = unsafe { ext::timestamp() };
+
+        let ret = unsafe { archived_root::<u64>(&buf[..ret_len as usize]) };
+        ret.deserialize(&mut Infallible).expect("Infallible")
+    })
+}
+
+/// Return the number of bytes allocated on the heap, and the number of bytes
+/// the heap can hold.
+pub fn heap_stats() -> (u64, u64) {
+    with_arg_buf(|buf| {
+        let ret_len = unsafe { ext::heap_stats() };
+
+        let ret =
+            unsafe { archived_root::<(u64, u64)>(&buf[..ret_len as usize]) };
+        ret.deserialize(&mut Infallible).expect("Infallible")
+    })
+}
+
+fn pages_and_limit() -> (u32, u32) {
+    with_arg_buf(|buf| {
+        let ret_len = unsafe { ext::memory_pages() };
+
+        let ret =
+            unsafe { archived_root::<(u32, u32)>(&buf[..ret_len as usize]) };
+        ret.deserialize(&mut Infallible).expect("Infallible")
+    })
+}
+
+/// Return the number of 64KiB pages of linear memory the module currently
+/// uses.
+pub fn memory_pages() -> u32 {
+    pages_and_limit().0
+}
+
+/// Return the maximum number of 64KiB pages the linear memory of the module
+/// can grow to.
+pub fn memory_limit() -> u32 {
+    pages_and_limit().1
+}
+
+/// Return true if the heap of the module is filled past the soft memory limit
+/// set by the host, in which case the module should free what memory it can,
+/// such as caches, before its allocations start failing.
+pub fn memory_pressure() -> bool {
+    unsafe { ext::memory_pressure() != 0 }
+}
+
+/// Return the hash of the bytecode the module was deployed from, as recorded
+/// by the host when deploying it.
+pub fn code_hash_self() -> [u8; 32] {
+    with_arg_buf(|buf| {
+        let ret_len = unsafe { ext::code_hash() };
+
+        let ret =
+            unsafe { archived_root::<[u8; 32]>(&buf[..ret_len as usize]) };
+        ret.deserialize(&mut Infallible).expect("Infallible")
+    })
+}
+
+/// Return the id of the commit the state was last persisted as, or zeroes
+/// if it was never persisted.
+///
+/// The id changes whenever the state is persisted with changes, so it can be
+/// used to detect the state being reset, or to bind signatures to a specific
+/// state.
+pub fn commit_id() -> [u8; 32] {
+    with_arg_buf(|buf| {
+        let ret_len = unsafe { ext::commit_id() };
+
+        let ret =
+            unsafe { archived_root::<[u8; 32]>(&buf[..ret_len as usize]) };
+        ret.deserialize(&mut Infallible).expect("Infallible")
+    })
+}
+
+/// Fill `buf` with random bytes, separated from other uses of randomness by
+/// `domain`.
+///
+/// The bytes are derived from the state root, the current call and the number
+/// of times randomness was drawn during it, so the same call on the same state
+/// always produces the same bytes.
+pub fn random(domain: &[u8], buf: &mut [u8]) {
+    let domain_ptr = domain.as_ptr();
+    let domain_len = domain.len() as u32;
+
+    for chunk in buf.chunks_mut(ARGBUF_LEN) {
+        with_arg_buf(|arg_buf| {
+            let len = chunk.len() as u32;
+            let ret_len = unsafe { ext::random(domain_ptr, domain_len, len) };
+            chunk.copy_from_slice(&arg_buf[..ret_len as usize]);
+        });
+    }
+}
+
+/// Return `n` random bytes. See [`random`].
+pub fn random_bytes(n: usize) -> Vec<u8> {
+    let mut bytes = vec![0; n];
+    random(&[], &mut bytes);
+    bytes
+}
+
 /// Return the ID of the calling module. The returned id will be
 /// uninitialized if there is no caller - meaning this is the first module
 /// to be called.
@@ -197,6 +487,30 @@ pub fn caller() -> ModuleId {
     })
 }
 
+/// Return the ID of the module the outermost call of the transaction was made
+/// to. Unlike [`caller`], this is the same for every module taking part in
+/// the transaction.
+///
+/// This is a module, not the external identity that made the transaction,
+/// which is not known to modules.
+pub fn entry_module() -> ModuleId {
+    with_arg_buf(|buf| {
+        let ret_len = unsafe { ext::entry_module() };
+        let ret =
+            unsafe { archived_root::<ModuleId>(&buf[..ret_len as usize]) };
+        ret.deserialize(&mut Infallible).expect("Infallible")
+    })
+}
+
+/// Return the metadata the host attached to the current transaction, which
+/// is empty if there is none or outside of a transaction.
+pub fn tx_meta() -> Vec<u8> {
+    with_arg_buf(|buf| {
+        let len = unsafe { ext::tx_meta() };
+        buf[..len as usize].to_vec()
+    })
+}
+
 /// Emits an event with the given data.
 pub fn emit<D>(data: D)
 where
@@ -204,7 +518,7 @@ where
 {
     with_arg_buf(|buf| {
         let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
-        let scratch = BufferScratch::new(&mut sbuf);
+        let scratch = standard_scratch(&mut sbuf);
         let ser = BufferSerializer::new(buf);
         let mut composite =
             CompositeSerializer::new(ser, scratch, rkyv::Infallible);
@@ -216,17 +530,106 @@ where
     });
 }
 
-pub fn limit() -> u64 {
+/// Defer a transaction to be performed by the host once the current
+/// transaction succeeds.
+///
+/// Deferred transactions are performed in the order they are deferred, and
+/// their receipts are attached to the receipt of the outer transaction. A
+/// deferred transaction that fails is rolled back on its own, and its failure
+/// is recorded in the receipt of the outer transaction instead.
+///
+/// Deferring a transaction during a query fails the query, since there is no
+/// transaction to perform it after.
+pub fn defer(module_id: ModuleId, raw: RawTransaction) {
     with_arg_buf(|buf| {
-        let ret_len = unsafe { ext::limit() };
-        let ret = unsafe { archived_root::<u64>(&buf[..ret_len as usize]) };
-        ret.deserialize(&mut Infallible).expect("Infallible")
-    })
+        let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
+        let scratch = standard_scratch(&mut sbuf);
+        let ser = BufferSerializer::new(buf);
+        let mut composite =
+            CompositeSerializer::new(ser, scratch, rkyv::Infallible);
+
+        composite.serialize_value(&(module_id, raw)).unwrap();
+        let arg_len = composite.pos() as u32;
+
+        unsafe { ext::defer(arg_len) }
+    });
+}
+
+/// Subscribe the calling module to the events of the given module.
+///
+/// Once a transaction emitting events from the given module completes, the
+/// `on_event` method of the calling module is called with each event, as a
+/// transaction taking the id of the emitting module and the event data as a
+/// `(ModuleId, Vec<u8>)`. These calls are deferred, spending the points left
+/// by the transaction, so a call to `on_event` that fails is rolled back
+/// without failing the transaction that emitted the event.
+///
+/// The subscription only takes effect once the transaction making it
+/// succeeds.
+pub fn subscribe(module_id: ModuleId) {
+    with_arg_buf(|buf| {
+        let arg_len = write_module_id(buf, module_id);
+        unsafe { ext::subscribe(arg_len) }
+    });
+}
+
+/// Unsubscribe the calling module from the events of the given module.
+pub fn unsubscribe(module_id: ModuleId) {
+    with_arg_buf(|buf| {
+        let arg_len = write_module_id(buf, module_id);
+        unsafe { ext::unsubscribe(arg_len) }
+    });
+}
+
+fn write_module_id(buf: &mut [u8], module_id: ModuleId) -> u32 {
+    let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
+    let scratch = standard_scratch(&mut sbuf);
+    let ser = BufferSerializer::new(buf);
+    let mut composite =
+        CompositeSerializer::new(ser, scratch, rkyv::Infallible);
+
+    composite.serialize_value(&module_id).unwrap();
+    composite.pos() as u32
 }
 
+/// Return the points given to the current call frame. Same as
+/// [`frame_limit`].
+pub fn limit() -> u64 {
+    frame_limit()
+}
+
+/// Return the points spent by the current call frame. Same as
+/// [`frame_spent`].
 pub fn spent() -> u64 {
+    frame_spent()
+}
+
+/// Return the points given to the current call frame, which is a share of
+/// the points left to its caller.
+pub fn frame_limit() -> u64 {
+    read_u64(|| unsafe { ext::limit() })
+}
+
+/// Return the points spent by the current call frame, including the ones
+/// spent by the calls it made.
+pub fn frame_spent() -> u64 {
+    read_u64(|| unsafe { ext::spent() })
+}
+
+/// Return the points given to the whole transaction.
+pub fn tx_limit() -> u64 {
+    read_u64(|| unsafe { ext::tx_limit() })
+}
+
+/// Return the points spent by the whole transaction so far, across all call
+/// frames.
+pub fn tx_spent() -> u64 {
+    read_u64(|| unsafe { ext::tx_spent() })
+}
+
+fn read_u64<F: FnOnce() -> u32>(host_call: F) -> u64 {
     with_arg_buf(|buf| {
-        let ret_len = unsafe { ext::spent() };
+        let ret_len = host_call();
         let ret = unsafe { archived_root::<u64>(&buf[..ret_len as usize]) };
         ret.deserialize(&mut Infallible).expect("Infallible")
     })
@@ -263,7 +666,7 @@ impl<S> State<S> {
     {
         let arg_len = with_arg_buf(|buf| {
             let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
-            let scratch = BufferScratch::new(&mut sbuf);
+            let scratch = standard_scratch(&mut sbuf);
             let ser = BufferSerializer::new(buf);
             let mut composite =
                 CompositeSerializer::new(ser, scratch, rkyv::Infallible);
@@ -280,4 +683,69 @@ impl<S> State<S> {
             ret.deserialize(&mut Infallible).expect("Infallible")
         })
     }
+
+    /// Like [`transact`](Self::transact), but calling the method with the
+    /// given [`selector`](crate::selector) instead of passing its name.
+    pub fn transact_selector<Arg, Ret>(
+        &mut self,
+        mod_id: ModuleId,
+        selector: u32,
+        arg: Arg,
+    ) -> Ret
+    where
+        Arg: for<'a> Serialize<StandardBufSerializer<'a>>,
+        Ret: Archive,
+        Ret::Archived: Deserialize<Ret, Infallible>,
+    {
+        let arg_len = with_arg_buf(|buf| {
+            let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
+            let scratch = standard_scratch(&mut sbuf);
+            let ser = BufferSerializer::new(buf);
+            let mut composite =
+                CompositeSerializer::new(ser, scratch, rkyv::Infallible);
+
+            composite.serialize_value(&arg).unwrap();
+            composite.pos() as u32
+        });
+
+        let ret_len = unsafe { ext::ts(mod_id.as_ptr(), selector, arg_len) };
+
+        with_arg_buf(|buf| {
+            let slice = &buf[..ret_len as usize];
+            let ret = unsafe { archived_root::<Ret>(slice) };
+            ret.deserialize(&mut Infallible).expect("Infallible")
+        })
+    }
+
+    /// Like [`transact`](Self::transact), but returning the failure of the
+    /// call instead of aborting the calling module, as with [`try_query`].
+    ///
+    /// Changes the callee made to the state before failing are rolled back.
+    pub fn try_transact<Arg, Ret, Err>(
+        &mut self,
+        mod_id: ModuleId,
+        name: &str,
+        arg: Arg,
+    ) -> Result<Ret, CallError<Err>>
+    where
+        Arg: for<'a> Serialize<StandardBufSerializer<'a>>,
+        Ret: Archive,
+        Ret::Archived: Deserialize<Ret, Infallible>,
+        Err: Archive,
+        Err::Archived: Deserialize<Err, Infallible>,
+    {
+        let arg_len = with_arg_buf(|buf| {
+            let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
+            let scratch = standard_scratch(&mut sbuf);
+            let ser = BufferSerializer::new(buf);
+            let mut composite =
+                CompositeSerializer::new(ser, scratch, rkyv::Infallible);
+
+            composite.serialize_value(&arg).unwrap();
+            composite.pos() as u32
+        });
+
+        let ret_len = extern_try_transaction(mod_id, name, arg_len);
+        read_call_result(ret_len)
+    }
 }
@@ -4,25 +4,48 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
 use rkyv::{
     ser::serializers::{
-        AllocSerializer, BufferScratch, BufferSerializer, CompositeSerializer,
+        AllocScratch, AllocSerializer, BufferScratch, BufferSerializer,
+        CompositeSerializer, FallbackScratch,
     },
-    ser::Serializer,
-    Archive, Deserialize, Infallible, Serialize,
+    ser::{ScratchSpace, Serializer},
+    vec::{ArchivedVec, VecResolver},
+    AlignedVec, Archive, Deserialize, Fallible, Infallible, Serialize,
 };
 
 use bytecheck::CheckBytes;
 
-use crate::SCRATCH_BUF_BYTES;
+use crate::{ARGBUF_LEN, SCRATCH_BUF_BYTES};
 
-pub type StandardBufSerializer<'a> = CompositeSerializer<
-    BufferSerializer<&'a mut [u8]>,
+/// Scratch space backed by a fixed size buffer, falling back to the heap
+/// when serializing a value needs more space than it has.
+pub type StandardScratch<'a> = FallbackScratch<
     BufferScratch<&'a mut [u8; SCRATCH_BUF_BYTES]>,
+    AllocScratch,
 >;
 
+pub type StandardBufSerializer<'a> =
+    CompositeSerializer<BufferSerializer<&'a mut [u8]>, StandardScratch<'a>>;
+
+/// Create the scratch space used by a [`StandardBufSerializer`].
+pub fn standard_scratch(
+    sbuf: &mut [u8; SCRATCH_BUF_BYTES],
+) -> StandardScratch<'_> {
+    FallbackScratch::new(BufferScratch::new(sbuf), AllocScratch::new())
+}
+
 pub const MODULE_ID_BYTES: usize = 32;
 
+/// Length of the prefix marking the ids reserved for system modules.
+const RESERVED_PREFIX_LEN: usize = MODULE_ID_BYTES - 8;
+/// Byte the prefix of the ids reserved for system modules is filled with.
+const RESERVED_BYTE: u8 = 0xff;
+
 #[derive(
     PartialEq,
     Eq,
@@ -59,6 +82,28 @@ impl ModuleId {
     pub fn is_uninitialized(&self) -> bool {
         self == &Self::uninitialized()
     }
+
+    /// Return the id with the given index in the namespace reserved for
+    /// system modules, implemented by the host instead of being deployed.
+    pub const fn reserved(index: u64) -> Self {
+        let mut bytes = [RESERVED_BYTE; MODULE_ID_BYTES];
+        let index = index.to_le_bytes();
+
+        let mut i = 0;
+        while i < index.len() {
+            bytes[RESERVED_PREFIX_LEN + i] = index[i];
+            i += 1;
+        }
+
+        ModuleId(bytes)
+    }
+
+    /// Return true if the id is in the namespace reserved for system modules.
+    pub fn is_reserved(&self) -> bool {
+        self.0[..RESERVED_PREFIX_LEN]
+            .iter()
+            .all(|byte| *byte == RESERVED_BYTE)
+    }
 }
 
 impl From<[u8; 32]> for ModuleId {
@@ -79,11 +124,162 @@ impl core::fmt::Debug for ModuleId {
     }
 }
 
+/// Bytes aligned for archived values to be accessed in place, archived as a
+/// vector of bytes with the same alignment.
+#[derive(Debug, Clone, Default)]
+pub struct AlignedBytes(AlignedVec);
+
+impl AlignedBytes {
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        let mut vec = AlignedVec::with_capacity(bytes.len());
+        vec.extend_from_slice(bytes);
+        AlignedBytes(vec)
+    }
+}
+
+impl From<AlignedVec> for AlignedBytes {
+    fn from(vec: AlignedVec) -> Self {
+        AlignedBytes(vec)
+    }
+}
+
+impl Deref for AlignedBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+impl PartialEq for AlignedBytes {
+    fn eq(&self, other: &Self) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl Eq for AlignedBytes {}
+
+impl PartialOrd for AlignedBytes {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AlignedBytes {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self[..].cmp(&other[..])
+    }
+}
+
+impl Hash for AlignedBytes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self[..].hash(state)
+    }
+}
+
+impl Archive for AlignedBytes {
+    type Archived = ArchivedVec<u8>;
+    type Resolver = VecResolver;
+
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        self.0.resolve(pos, resolver, out)
+    }
+}
+
+impl<S> Serialize<S> for AlignedBytes
+where
+    S: ScratchSpace + Serializer + ?Sized,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<VecResolver, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<D> Deserialize<AlignedBytes, D> for ArchivedVec<u8>
+where
+    D: Fallible + ?Sized,
+{
+    fn deserialize(&self, _: &mut D) -> Result<AlignedBytes, D::Error> {
+        Ok(AlignedBytes::from_slice(self.as_slice()))
+    }
+}
+
+/// Set in the length the host returns for a call made using
+/// [`try_query`](crate::try_query) or
+/// [`State::try_transact`](crate::State::try_transact) when the call failed,
+/// in which case the argument buffer holds an [`ErrorEnvelope`].
+pub const CALL_FAILED: u32 = 1 << 31;
+
+/// The code of the failure of a call whose callee used
+/// [`revert`](crate::revert), the same as the one of the host error.
+pub const REVERT_CODE: u16 = 34;
+
+/// The failure of a call, as written by the host in the argument buffer of
+/// the caller.
+#[derive(Archive, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ErrorEnvelope {
+    /// The numeric code of the error the call failed with.
+    pub code: u16,
+    /// The archived error the callee reverted with, empty for other
+    /// failures.
+    pub payload: AlignedBytes,
+}
+
+/// The failure of a call made using [`try_query`](crate::try_query) or
+/// [`State::try_transact`](crate::State::try_transact).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CallError<E> {
+    /// The callee reverted with the given error.
+    Reverted(E),
+    /// The call failed otherwise, with the given error code.
+    Failed(u16),
+}
+
+/// The serialized argument of a call does not fit in the argument buffer.
+#[derive(
+    Archive, Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ArgumentTooLarge {
+    pub required: usize,
+    pub available: usize,
+}
+
+/// Check an argument of the given length fits in the argument buffer.
+fn check_arg_len(arg_len: usize) -> Result<(), ArgumentTooLarge> {
+    match arg_len <= ARGBUF_LEN {
+        true => Ok(()),
+        false => Err(ArgumentTooLarge {
+            required: arg_len,
+            available: ARGBUF_LEN,
+        }),
+    }
+}
+
+/// Split the data of a raw call into its name and argument, checking the
+/// length of the argument and the encoding of the name.
+fn checked_parts(arg_len: u32, data: &[u8]) -> Option<(&str, &[u8])> {
+    let arg_len = arg_len as usize;
+    if arg_len > data.len() {
+        return None;
+    }
+
+    let (arg, name) = data.split_at(arg_len);
+    let name = core::str::from_utf8(name).ok()?;
+    Some((name, arg))
+}
+
 #[derive(Archive, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 #[archive_attr(derive(CheckBytes))]
 pub struct RawQuery {
     arg_len: u32,
-    data: alloc::vec::Vec<u8>,
+    data: AlignedBytes,
 }
 
 impl RawQuery {
@@ -98,13 +294,27 @@ impl RawQuery {
 
         let arg_len = ser.pos() as u32;
 
-        let mut data = ser.into_serializer().into_inner().to_vec();
+        let mut data = ser.into_serializer().into_inner();
 
         let name_as_bytes = name.as_bytes();
 
         data.extend_from_slice(name_as_bytes);
 
-        RawQuery { arg_len, data }
+        RawQuery {
+            arg_len,
+            data: data.into(),
+        }
+    }
+
+    /// Like [`new`](Self::new), but failing if the argument would not fit in
+    /// the argument buffer of the module called.
+    pub fn try_new<A>(name: &str, arg: A) -> Result<Self, ArgumentTooLarge>
+    where
+        A: Serialize<AllocSerializer<64>>,
+    {
+        let raw = Self::new(name, arg);
+        check_arg_len(raw.arg_len as usize)?;
+        Ok(raw)
     }
 
     pub fn name(&self) -> &str {
@@ -115,14 +325,20 @@ impl RawQuery {
     pub fn arg_bytes(&self) -> &[u8] {
         &self.data[..self.arg_len as usize]
     }
+
+    /// Return the name and the argument of the call, or `None` if they are
+    /// malformed, as they may be when the call was received from elsewhere
+    /// instead of being built using [`new`](Self::new).
+    pub fn checked_parts(&self) -> Option<(&str, &[u8])> {
+        checked_parts(self.arg_len, &self.data)
+    }
 }
 
 #[derive(Archive, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 #[archive_attr(derive(CheckBytes))]
 pub struct RawTransaction {
     arg_len: u32,
-    // TODO: use AlignedVec
-    data: alloc::vec::Vec<u8>,
+    data: AlignedBytes,
 }
 
 impl RawTransaction {
@@ -137,11 +353,25 @@ impl RawTransaction {
 
         let arg_len = ser.pos() as u32;
 
-        let mut data = ser.into_serializer().into_inner().to_vec();
+        let mut data = ser.into_serializer().into_inner();
 
         data.extend_from_slice(name.as_bytes());
 
-        RawTransaction { arg_len, data }
+        RawTransaction {
+            arg_len,
+            data: data.into(),
+        }
+    }
+
+    /// Like [`new`](Self::new), but failing if the argument would not fit in
+    /// the argument buffer of the module called.
+    pub fn try_new<A>(name: &str, arg: A) -> Result<Self, ArgumentTooLarge>
+    where
+        A: Serialize<AllocSerializer<64>>,
+    {
+        let raw = Self::new(name, arg);
+        check_arg_len(raw.arg_len as usize)?;
+        Ok(raw)
     }
 
     pub fn name(&self) -> &str {
@@ -152,21 +382,44 @@ impl RawTransaction {
     pub fn arg_bytes(&self) -> &[u8] {
         &self.data[..self.arg_len as usize]
     }
+
+    /// Return the name and the argument of the call, or `None` if they are
+    /// malformed, as they may be when the call was received from elsewhere
+    /// instead of being built using [`new`](Self::new).
+    pub fn checked_parts(&self) -> Option<(&str, &[u8])> {
+        checked_parts(self.arg_len, &self.data)
+    }
 }
 
-#[derive(Archive, Serialize, Deserialize)]
+#[derive(
+    Archive,
+    Serialize,
+    Deserialize,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Clone,
+)]
 #[archive_attr(derive(CheckBytes))]
 pub struct RawResult {
-    data: alloc::vec::Vec<u8>,
+    data: AlignedBytes,
 }
 
 impl RawResult {
     pub fn new(bytes: &[u8]) -> Self {
         RawResult {
-            data: alloc::vec::Vec::from(bytes),
+            data: AlignedBytes::from_slice(bytes),
         }
     }
 
+    /// Return the serialized return.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
     pub fn cast<D>(&self) -> D
     where
         D: Archive,
@@ -196,6 +449,60 @@ mod test {
         );
     }
 
+    #[test]
+    fn raw_arg_capacity() {
+        let fits = alloc::vec![0u8; crate::arg_capacity() - 8];
+        assert!(RawQuery::try_new("fits", fits).is_ok());
+
+        let too_large = alloc::vec![0u8; crate::arg_capacity()];
+        let err = RawTransaction::try_new("too_large", too_large)
+            .expect_err("the argument should not fit");
+        assert!(err.required > err.available);
+        assert_eq!(err.available, crate::arg_capacity());
+    }
+
+    #[test]
+    fn raw_checked_parts() {
+        let q = RawQuery::new("checked", 7u32);
+        assert_eq!(q.checked_parts(), Some(("checked", q.arg_bytes())));
+
+        let long = RawQuery {
+            arg_len: 64,
+            data: AlignedBytes::from_slice(&[0u8; 8]),
+        };
+        assert_eq!(long.checked_parts(), None);
+
+        let invalid = RawTransaction {
+            arg_len: 0,
+            data: AlignedBytes::from_slice(&[0xff, 0xfe]),
+        };
+        assert_eq!(invalid.checked_parts(), None);
+    }
+
+    #[test]
+    fn raw_data_aligned() {
+        let q = RawQuery::new("aligned", 0xdeadbeefu64);
+        let t = RawTransaction::new("aligned", [1u128, 2u128]);
+
+        for bytes in [q.arg_bytes(), t.arg_bytes()] {
+            let addr = bytes.as_ptr() as usize;
+            assert_eq!(addr % crate::ARGBUF_ALIGN, 0);
+        }
+    }
+
+    #[test]
+    fn reserved_module_ids() {
+        assert!(ModuleId::reserved(0).is_reserved());
+        assert!(ModuleId::reserved(u64::MAX).is_reserved());
+        assert_ne!(ModuleId::reserved(1), ModuleId::reserved(2));
+
+        assert!(!ModuleId::uninitialized().is_reserved());
+
+        let mut id = ModuleId::reserved(3);
+        id.as_bytes_mut()[0] = 0;
+        assert!(!id.is_reserved());
+    }
+
     #[test]
     fn raw_transaction() {
         let q = RawQuery::new("world", 666u128);
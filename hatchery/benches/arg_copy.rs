@@ -0,0 +1,59 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Measures deep chains of inter-module queries passing payloads of
+//! different sizes, whose cost per call should follow the size of the
+//! payload rather than the size of the argument buffer.
+
+use std::time::Instant;
+
+use dallo::{ModuleId, RawQuery, RawResult};
+use hatchery::{module_bytecode, Error, World};
+
+const DEPTH: usize = 16;
+const ROUNDS: u32 = 1000;
+const PAYLOADS: [usize; 4] = [0, 256, 4096, 16384];
+
+/// Build a query that goes through the callcenter `depth` times, the last
+/// one returning a payload of the given size, which is passed along in both
+/// directions at every step.
+fn chain(center_id: ModuleId, depth: usize, payload: usize) -> RawQuery {
+    let payload = RawQuery::new("payload", vec![0u8; payload]);
+    let mut rq = RawQuery::new("query_passthrough", payload);
+    for _ in 1..depth {
+        rq = RawQuery::new("delegate_query", (center_id, rq));
+    }
+    rq
+}
+
+fn main() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    world.set_point_limit(1 << 32);
+
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    for payload in PAYLOADS {
+        let rq = chain(center_id, DEPTH, payload);
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            world.query::<_, RawResult>(
+                center_id,
+                "delegate_query",
+                (center_id, rq.clone()),
+            )?;
+        }
+        let elapsed = start.elapsed();
+
+        println!(
+            "call chain of depth {DEPTH} passing {payload} bytes: {:?} per query, {:?} per call",
+            elapsed / ROUNDS,
+            elapsed / (ROUNDS * DEPTH as u32),
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Measures deep chains of inter-module queries, dominated by the cost of
+//! resolving the method called at each step.
+
+use std::time::Instant;
+
+use dallo::{ModuleId, RawQuery, RawResult};
+use hatchery::{module_bytecode, Error, World};
+
+const DEPTH: usize = 16;
+const ROUNDS: u32 = 1000;
+
+/// Build a query that goes through the callcenter `depth` times before
+/// reading the counter.
+fn chain(center_id: ModuleId, counter_id: ModuleId, depth: usize) -> RawQuery {
+    let mut rq = RawQuery::new("read_value", ());
+    let mut target = counter_id;
+    for _ in 1..depth {
+        rq = RawQuery::new("delegate_query", (target, rq));
+        target = center_id;
+    }
+    rq
+}
+
+fn main() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    world.set_point_limit(1 << 32);
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    let rq = chain(center_id, counter_id, DEPTH);
+    let target = if DEPTH > 1 { center_id } else { counter_id };
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        world.query::<_, RawResult>(
+            center_id,
+            "delegate_query",
+            (target, rq.clone()),
+        )?;
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "call chain of depth {DEPTH}: {:?} per query, {:?} per call",
+        elapsed / ROUNDS,
+        elapsed / (ROUNDS * DEPTH as u32),
+    );
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Measures the first call made to a module after the world it lives in is
+//! opened, with and without warming the module up ahead of the call.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use hatchery::{module_bytecode, Error, World};
+
+const ROUNDS: u32 = 100;
+
+/// Open the world at the given path, returning how long the module took to
+/// deploy, to warm up if asked to, and to answer its first query.
+fn first_call(
+    storage_path: &Path,
+    warming: bool,
+) -> Result<(Duration, Duration, Duration), Error> {
+    let mut world = World::new(storage_path)?;
+
+    let start = Instant::now();
+    let id = world.deploy(module_bytecode!("counter"))?;
+    let deploy = start.elapsed();
+
+    let start = Instant::now();
+    if warming {
+        world.warm_up(&[id])?;
+    }
+    let warm_up = start.elapsed();
+
+    let start = Instant::now();
+    world.query::<_, i64>(id, "read_value", ())?;
+    let query = start.elapsed();
+
+    Ok((deploy, warm_up, query))
+}
+
+fn main() -> Result<(), Error> {
+    let storage_path = {
+        let mut world = World::ephemeral()?;
+        let id = world.deploy(module_bytecode!("counter"))?;
+        world.transact::<_, ()>(id, "increment", ())?;
+        world.persist()?;
+        world.storage_path().to_path_buf()
+    };
+
+    for warming in [false, true] {
+        let mut total = (Duration::ZERO, Duration::ZERO, Duration::ZERO);
+        for _ in 0..ROUNDS {
+            let (deploy, warm_up, query) = first_call(&storage_path, warming)?;
+            total.0 += deploy;
+            total.1 += warm_up;
+            total.2 += query;
+        }
+
+        println!(
+            "cold start {}: {:?} to deploy, {:?} to warm up, {:?} to query",
+            if warming {
+                "warmed up"
+            } else {
+                "not warmed up"
+            },
+            total.0 / ROUNDS,
+            total.1 / ROUNDS,
+            total.2 / ROUNDS,
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Compares queries validating their return with queries trusting it, on
+//! returns large enough for validation to matter.
+
+use std::time::Instant;
+
+use hatchery::{module_bytecode, Error, Receipt, World};
+
+const ELEMENTS: i16 = 1024;
+const TIMES: u32 = 16;
+const ROUNDS: u32 = 1000;
+
+fn main() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    world.set_point_limit(1 << 32);
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+    world.transact::<_, Vec<Vec<i16>>>(
+        id,
+        "push_chunks",
+        vec![(0..ELEMENTS).collect::<Vec<_>>()],
+    )?;
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let _: Receipt<Vec<i16>> = world.query(id, "replicate", TIMES)?;
+    }
+    let checked = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        // the vector module is trusted to return a valid archived vector
+        let _: Receipt<Vec<i16>> =
+            unsafe { world.query_unchecked(id, "replicate", TIMES)? };
+    }
+    let unchecked = start.elapsed();
+
+    println!(
+        "return of {} elements: {:?} per query, {:?} per unchecked query",
+        ELEMENTS as u32 * TIMES,
+        checked / ROUNDS,
+        unchecked / ROUNDS,
+    );
+
+    Ok(())
+}
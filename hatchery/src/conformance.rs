@@ -0,0 +1,161 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Test vectors for the points spent by calls.
+//!
+//! Each [`Vector`] is a sequence of calls to a small module, with the points
+//! each of them is expected to spend when every instruction costs one point.
+//! Running them using [`run`] on a world configured like the ones of a node
+//! catches metering diverging between nodes, for instance after a change to
+//! the runtime or to the target compiled for.
+
+use dallo::ModuleId;
+
+use crate::error::Error;
+use crate::world::{Receipt, World};
+
+/// The module the vectors call, assembled by hand so that the vectors don't
+/// depend on the compiler of modules. In text format:
+///
+/// ```text
+/// (module
+///   (memory (export "memory") 2)
+///   (global (export "A") i32 (i32.const 64))
+///   (global (export "SELF_ID") i32 (i32.const 0))
+///   (global (export "__heap_base") i32 (i32.const 65664))
+///   (func $noop (export "noop") (param i32) (result i32)
+///     i32.const 0)
+///   (func (export "count") (param i32) (result i32) (local $n i32)
+///     i32.const 64
+///     i32.load
+///     local.set $n
+///     block
+///       loop
+///         local.get $n
+///         i32.eqz
+///         br_if 1
+///         local.get $n
+///         i32.const 1
+///         i32.sub
+///         local.set $n
+///         br 0
+///       end
+///     end
+///     i32.const 0)
+///   (func (export "nested") (param i32) (result i32)
+///     local.get 0
+///     call $noop))
+/// ```
+pub const BYTECODE: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60,
+    0x01, 0x7f, 0x01, 0x7f, 0x03, 0x04, 0x03, 0x00, 0x00, 0x00, 0x05, 0x03,
+    0x01, 0x00, 0x02, 0x06, 0x13, 0x03, 0x7f, 0x00, 0x41, 0xc0, 0x00, 0x0b,
+    0x7f, 0x00, 0x41, 0x00, 0x0b, 0x7f, 0x00, 0x41, 0x80, 0x81, 0x04, 0x0b,
+    0x07, 0x3e, 0x07, 0x06, 0x6d, 0x65, 0x6d, 0x6f, 0x72, 0x79, 0x02, 0x00,
+    0x01, 0x41, 0x03, 0x00, 0x07, 0x53, 0x45, 0x4c, 0x46, 0x5f, 0x49, 0x44,
+    0x03, 0x01, 0x0b, 0x5f, 0x5f, 0x68, 0x65, 0x61, 0x70, 0x5f, 0x62, 0x61,
+    0x73, 0x65, 0x03, 0x02, 0x04, 0x6e, 0x6f, 0x6f, 0x70, 0x00, 0x00, 0x05,
+    0x63, 0x6f, 0x75, 0x6e, 0x74, 0x00, 0x01, 0x06, 0x6e, 0x65, 0x73, 0x74,
+    0x65, 0x64, 0x00, 0x02, 0x0a, 0x30, 0x03, 0x04, 0x00, 0x41, 0x00, 0x0b,
+    0x22, 0x01, 0x01, 0x7f, 0x41, 0xc0, 0x00, 0x28, 0x02, 0x00, 0x21, 0x01,
+    0x02, 0x40, 0x03, 0x40, 0x20, 0x01, 0x45, 0x0d, 0x01, 0x20, 0x01, 0x41,
+    0x01, 0x6b, 0x21, 0x01, 0x0c, 0x00, 0x0b, 0x0b, 0x41, 0x00, 0x0b, 0x06,
+    0x00, 0x20, 0x00, 0x10, 0x00, 0x0b,
+];
+
+/// A call to the module of the vectors, with the points it is expected to
+/// spend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Call {
+    pub method: &'static str,
+    pub arg: u32,
+    pub spent: u64,
+}
+
+/// A named sequence of calls, made as transactions in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Vector {
+    pub name: &'static str,
+    pub calls: &'static [Call],
+}
+
+const fn call(method: &'static str, arg: u32, spent: u64) -> Call {
+    Call { method, arg, spent }
+}
+
+/// The canonical vectors.
+///
+/// Points are charged per basic block, so a call spends the instructions of
+/// the blocks it enters, including the ones it leaves by branching.
+pub const VECTORS: &[Vector] = &[
+    Vector {
+        name: "noop",
+        calls: &[call("noop", 0, 2)],
+    },
+    Vector {
+        name: "count",
+        calls: &[
+            call("count", 0, 10),
+            call("count", 1, 18),
+            call("count", 100, 810),
+        ],
+    },
+    Vector {
+        name: "nested",
+        calls: &[call("nested", 0, 5), call("noop", 0, 2)],
+    },
+];
+
+/// A call of a vector that spent other points than expected.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Mismatch {
+    pub vector: &'static str,
+    /// The index of the call in the vector.
+    pub call: usize,
+    pub expected: u64,
+    pub spent: u64,
+}
+
+/// Run the given vectors on the world, returning the calls that spent other
+/// points than expected.
+///
+/// The module of the vectors is deployed for the run and released after, so
+/// the world should be a scratch one. Calls failing - for instance because
+/// the point limit of the world is too low - fail the run.
+pub fn run(
+    world: &mut World,
+    vectors: &[Vector],
+) -> Result<Vec<Mismatch>, Error> {
+    let module_id = world.deploy(BYTECODE)?;
+    let mismatches = run_on(world, module_id, vectors);
+    world.release(module_id)?;
+    mismatches
+}
+
+fn run_on(
+    world: &mut World,
+    module_id: ModuleId,
+    vectors: &[Vector],
+) -> Result<Vec<Mismatch>, Error> {
+    let mut mismatches = Vec::new();
+
+    for vector in vectors {
+        for (index, call) in vector.calls.iter().enumerate() {
+            let receipt: Receipt<()> =
+                world.transact(module_id, call.method, call.arg)?;
+            if receipt.spent() != call.spent {
+                mismatches.push(Mismatch {
+                    vector: vector.name,
+                    call: index,
+                    expected: call.spent,
+                    spent: receipt.spent(),
+                });
+            }
+        }
+    }
+
+    Ok(mismatches)
+}
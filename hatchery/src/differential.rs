@@ -0,0 +1,165 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Running every call on two differently configured worlds, and comparing
+//! their results.
+
+use bytecheck::CheckBytes;
+use dallo::{AbiType, ModuleId, StandardBufSerializer};
+use rkyv::{
+    validation::validators::DefaultValidator, Archive, Deserialize, Infallible,
+    Serialize,
+};
+
+use crate::error::Error;
+use crate::world::{Receipt, World};
+
+/// What differed between the two worlds of a [`Differential`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DivergenceKind {
+    /// The call succeeded in one world and failed in the other.
+    Outcome,
+    /// The call returned different values.
+    Return,
+    /// The call emitted different events.
+    Events,
+    /// The state root differs after the call.
+    State,
+}
+
+/// A difference between the two worlds of a [`Differential`], observed
+/// during a call.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Divergence {
+    pub module_id: ModuleId,
+    pub method: String,
+    pub kind: DivergenceKind,
+}
+
+/// Two worlds performing the same calls, such as worlds with different
+/// configurations or built with different versions of the runtime.
+///
+/// Each call is performed on the primary world first, and then on the
+/// secondary. The outcome, return, and events of the calls are compared, as
+/// well as the state roots after each transaction. The points spent are not
+/// compared, since they are expected to differ between cost schedules.
+///
+/// Divergences are recorded rather than returned as errors, and the result
+/// of the primary world is always the one returned.
+#[derive(Debug)]
+pub struct Differential {
+    primary: World,
+    secondary: World,
+    divergences: Vec<Divergence>,
+}
+
+impl Differential {
+    pub fn new(primary: World, secondary: World) -> Self {
+        Self {
+            primary,
+            secondary,
+            divergences: vec![],
+        }
+    }
+
+    /// Return the world whose results are returned.
+    pub fn primary(&self) -> &World {
+        &self.primary
+    }
+
+    /// Return the world the results are compared against.
+    pub fn secondary(&self) -> &World {
+        &self.secondary
+    }
+
+    /// Return the divergences observed so far, in the order they happened.
+    pub fn divergences(&self) -> &[Divergence] {
+        &self.divergences
+    }
+
+    /// Deploy the module in both worlds.
+    pub fn deploy(&mut self, bytecode: &[u8]) -> Result<ModuleId, Error> {
+        let id = self.primary.deploy(bytecode)?;
+        self.secondary.deploy(bytecode)?;
+        Ok(id)
+    }
+
+    pub fn query<Arg, Ret>(
+        &mut self,
+        m_id: ModuleId,
+        name: &str,
+        arg: Arg,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        Arg: AbiType + for<'a> Serialize<StandardBufSerializer<'a>> + Clone,
+        Ret: Archive + PartialEq,
+        Ret::Archived: Deserialize<Ret, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        let primary = self.primary.query(m_id, name, arg.clone());
+        let secondary = self.secondary.query(m_id, name, arg);
+
+        self.compare(m_id, name, &primary, &secondary);
+
+        primary
+    }
+
+    pub fn transact<Arg, Ret>(
+        &mut self,
+        m_id: ModuleId,
+        name: &str,
+        arg: Arg,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        Arg: AbiType
+            + for<'a> Serialize<StandardBufSerializer<'a>>
+            + core::fmt::Debug
+            + Clone,
+        Ret: Archive + PartialEq,
+        Ret::Archived: Deserialize<Ret, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        let primary = self.primary.transact(m_id, name, arg.clone());
+        let secondary = self.secondary.transact(m_id, name, arg);
+
+        self.compare(m_id, name, &primary, &secondary);
+
+        if self.primary.root() != self.secondary.root() {
+            self.diverged(m_id, name, DivergenceKind::State);
+        }
+
+        primary
+    }
+
+    fn compare<Ret: PartialEq>(
+        &mut self,
+        m_id: ModuleId,
+        name: &str,
+        primary: &Result<Receipt<Ret>, Error>,
+        secondary: &Result<Receipt<Ret>, Error>,
+    ) {
+        match (primary, secondary) {
+            (Ok(primary), Ok(secondary)) => {
+                if primary.ret() != secondary.ret() {
+                    self.diverged(m_id, name, DivergenceKind::Return);
+                }
+                if primary.events() != secondary.events() {
+                    self.diverged(m_id, name, DivergenceKind::Events);
+                }
+            }
+            (Err(_), Err(_)) => {}
+            _ => self.diverged(m_id, name, DivergenceKind::Outcome),
+        }
+    }
+
+    fn diverged(&mut self, m_id: ModuleId, name: &str, kind: DivergenceKind) {
+        self.divergences.push(Divergence {
+            module_id: m_id,
+            method: name.to_string(),
+            kind,
+        });
+    }
+}
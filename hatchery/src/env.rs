@@ -31,6 +31,16 @@ impl Env {
         }
     }
 
+    /// Drop the instance, unmapping its memory and closing its files.
+    ///
+    /// The functions imported by the instance hold clones of the env, so the
+    /// instance is never dropped unless it's closed.
+    pub(crate) fn close(&self) {
+        unsafe {
+            *self.0.get() = EnvInner::Uninitialized;
+        }
+    }
+
     pub(crate) fn uninitialized() -> Self {
         Env(Arc::new(UnsafeCell::new(EnvInner::Uninitialized)))
     }
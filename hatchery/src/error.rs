@@ -4,14 +4,19 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+
 use dallo::ModuleId;
 use rkyv::ser::serializers::{
-    BufferSerializerError, CompositeSerializerError, FixedSizeScratchError,
+    AllocScratchError, BufferSerializerError, CompositeSerializerError,
 };
 
+use crate::world::{CommitId, ModuleError};
+
 pub type Compo = CompositeSerializerError<
     BufferSerializerError,
-    FixedSizeScratchError,
+    AllocScratchError,
     std::convert::Infallible,
 >;
 
@@ -27,8 +32,384 @@ pub enum Error {
     OutOfPoints(ModuleId),
     PersistenceError(std::io::Error),
     ValidationError,
+    PureViolation(ModuleId),
+    EmitLimit(ModuleId),
+    DebugLimit(ModuleId),
+    WriteLimit(ModuleId),
+    CommitNotFound(CommitId),
+    /// The snapshot of a module in a commit is missing or was altered.
+    CorruptSnapshot {
+        module: ModuleId,
+        commit: CommitId,
+    },
+    /// The snapshots of a commit no longer add up to its id.
+    CorruptCommit(CommitId),
+    ModuleNotFound(ModuleId),
+    /// A serialized argument does not fit in the argument buffer. Since
+    /// serialization stops at the first write that does not fit, `required`
+    /// is a lower bound.
+    ArgumentTooLarge {
+        required: usize,
+        available: usize,
+    },
+    InvalidReturnData {
+        module: ModuleId,
+        method: String,
+    },
+    /// The method is not declared in the ABI of the module.
+    MethodNotFound {
+        module: ModuleId,
+        method: String,
+    },
+    /// The type of the argument differs from the one declared in the ABI of
+    /// the module. The types are given by the hashes of their structure, in
+    /// hexadecimal.
+    ArgumentMismatch {
+        module: ModuleId,
+        method: String,
+        expected: String,
+        found: String,
+    },
+    /// The return of a call does not fit in the argument buffer.
+    ReturnTooLarge {
+        module: ModuleId,
+        method: String,
+    },
+    GuestOutOfMemory(ModuleId),
+    /// The argument buffer of a module is not aligned to
+    /// [`dallo::ARGBUF_ALIGN`].
+    MisalignedArgBuffer(ModuleId),
+    /// The id of a module could not be written to its `SELF_ID`.
+    SelfIdNotWritten(ModuleId),
+    /// A memory image does not match its expected hash.
+    MemoryMismatch(ModuleId),
+    /// A module panicked, with the message and location of the panic.
+    Panic {
+        module: ModuleId,
+        message: String,
+    },
+    DeployRejected(ModuleId, String),
+    StorageLocked(PathBuf),
+    /// A system module was given an id outside the reserved namespace.
+    UnreservedModuleId(ModuleId),
+    #[cfg(feature = "tx")]
+    InvalidSignature,
+    #[cfg(feature = "tx")]
+    InvalidNonce(u64),
+    /// A module reverted its call with the given error, using
+    /// `dallo::revert`.
+    Revert(ModuleError),
+    /// No method of the module has the selector, or more than one does.
+    SelectorNotFound {
+        module: ModuleId,
+        selector: u32,
+    },
+    /// The state of a module left out of a partial restore differs from the
+    /// one it has in the commit restored.
+    StateMismatch {
+        module: ModuleId,
+        commit: CommitId,
+    },
+    /// A value was encoded using a version of the wire encoding this crate
+    /// does not know.
+    UnsupportedWireVersion(u8),
+    /// A module deferred a transaction during a query, which has no
+    /// transaction to perform it after.
+    DeferredInQuery(ModuleId),
+    /// The accesses to the memory of a module could not be tracked to
+    /// record a witness, because too many memories are tracked at once or
+    /// its memory could not be protected.
+    AccessTracking(ModuleId),
+}
+
+/// The stable numeric code of each kind of [`Error`], for transmitting
+/// failures compactly.
+///
+/// Codes are never reused or reassigned. New kinds of errors get the next
+/// free code, and codes of removed kinds stay reserved, so codes can be
+/// matched on across versions. Codes exist independently of the features
+/// enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u16)]
+pub enum ErrorCode {
+    InstantiationError = 1,
+    CompileError = 2,
+    ExportError = 3,
+    RuntimeError = 4,
+    Trap = 5,
+    MissingModuleExport = 6,
+    CompositeSerializerError = 7,
+    OutOfPoints = 8,
+    PersistenceError = 9,
+    ValidationError = 10,
+    PureViolation = 11,
+    EmitLimit = 12,
+    DebugLimit = 13,
+    WriteLimit = 14,
+    CommitNotFound = 15,
+    CorruptSnapshot = 16,
+    CorruptCommit = 17,
+    ModuleNotFound = 18,
+    ArgumentTooLarge = 19,
+    InvalidReturnData = 20,
+    MethodNotFound = 21,
+    ArgumentMismatch = 22,
+    ReturnTooLarge = 23,
+    GuestOutOfMemory = 24,
+    MisalignedArgBuffer = 25,
+    SelfIdNotWritten = 26,
+    MemoryMismatch = 27,
+    Panic = 28,
+    DeployRejected = 29,
+    StorageLocked = 30,
+    UnreservedModuleId = 31,
+    InvalidSignature = 32,
+    InvalidNonce = 33,
+    Revert = 34,
+    SelectorNotFound = 35,
+    StateMismatch = 36,
+    UnsupportedWireVersion = 37,
+    DeferredInQuery = 38,
+    AccessTracking = 39,
+}
+
+// guests tell reverts apart from other failures by their code
+const _: () = assert!(ErrorCode::Revert as u16 == dallo::REVERT_CODE);
+
+impl ErrorCode {
+    /// Return the numeric value of the code.
+    pub fn as_u16(self) -> u16 {
+        self as u16
+    }
+
+    /// Return the kind of error with the given numeric code, if any.
+    pub fn from_code(code: u16) -> Option<Self> {
+        use ErrorCode::*;
+
+        Some(match code {
+            1 => InstantiationError,
+            2 => CompileError,
+            3 => ExportError,
+            4 => RuntimeError,
+            5 => Trap,
+            6 => MissingModuleExport,
+            7 => CompositeSerializerError,
+            8 => OutOfPoints,
+            9 => PersistenceError,
+            10 => ValidationError,
+            11 => PureViolation,
+            12 => EmitLimit,
+            13 => DebugLimit,
+            14 => WriteLimit,
+            15 => CommitNotFound,
+            16 => CorruptSnapshot,
+            17 => CorruptCommit,
+            18 => ModuleNotFound,
+            19 => ArgumentTooLarge,
+            20 => InvalidReturnData,
+            21 => MethodNotFound,
+            22 => ArgumentMismatch,
+            23 => ReturnTooLarge,
+            24 => GuestOutOfMemory,
+            25 => MisalignedArgBuffer,
+            26 => SelfIdNotWritten,
+            27 => MemoryMismatch,
+            28 => Panic,
+            29 => DeployRejected,
+            30 => StorageLocked,
+            31 => UnreservedModuleId,
+            32 => InvalidSignature,
+            33 => InvalidNonce,
+            34 => Revert,
+            35 => SelectorNotFound,
+            36 => StateMismatch,
+            37 => UnsupportedWireVersion,
+            38 => DeferredInQuery,
+            39 => AccessTracking,
+            _ => return None,
+        })
+    }
+}
+
+impl Error {
+    /// Return the stable numeric code of the kind of the error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::InstantiationError(_) => ErrorCode::InstantiationError,
+            Error::CompileError(_) => ErrorCode::CompileError,
+            Error::ExportError(_) => ErrorCode::ExportError,
+            Error::RuntimeError(_) => ErrorCode::RuntimeError,
+            Error::Trap(_) => ErrorCode::Trap,
+            Error::MissingModuleExport => ErrorCode::MissingModuleExport,
+            Error::CompositeSerializerError(_) => {
+                ErrorCode::CompositeSerializerError
+            }
+            Error::OutOfPoints(_) => ErrorCode::OutOfPoints,
+            Error::PersistenceError(_) => ErrorCode::PersistenceError,
+            Error::ValidationError => ErrorCode::ValidationError,
+            Error::PureViolation(_) => ErrorCode::PureViolation,
+            Error::EmitLimit(_) => ErrorCode::EmitLimit,
+            Error::DebugLimit(_) => ErrorCode::DebugLimit,
+            Error::WriteLimit(_) => ErrorCode::WriteLimit,
+            Error::CommitNotFound(_) => ErrorCode::CommitNotFound,
+            Error::CorruptSnapshot { .. } => ErrorCode::CorruptSnapshot,
+            Error::CorruptCommit(_) => ErrorCode::CorruptCommit,
+            Error::ModuleNotFound(_) => ErrorCode::ModuleNotFound,
+            Error::ArgumentTooLarge { .. } => ErrorCode::ArgumentTooLarge,
+            Error::InvalidReturnData { .. } => ErrorCode::InvalidReturnData,
+            Error::MethodNotFound { .. } => ErrorCode::MethodNotFound,
+            Error::ArgumentMismatch { .. } => ErrorCode::ArgumentMismatch,
+            Error::ReturnTooLarge { .. } => ErrorCode::ReturnTooLarge,
+            Error::GuestOutOfMemory(_) => ErrorCode::GuestOutOfMemory,
+            Error::MisalignedArgBuffer(_) => ErrorCode::MisalignedArgBuffer,
+            Error::SelfIdNotWritten(_) => ErrorCode::SelfIdNotWritten,
+            Error::MemoryMismatch(_) => ErrorCode::MemoryMismatch,
+            Error::Panic { .. } => ErrorCode::Panic,
+            Error::DeployRejected(..) => ErrorCode::DeployRejected,
+            Error::StorageLocked(_) => ErrorCode::StorageLocked,
+            Error::UnreservedModuleId(_) => ErrorCode::UnreservedModuleId,
+            #[cfg(feature = "tx")]
+            Error::InvalidSignature => ErrorCode::InvalidSignature,
+            #[cfg(feature = "tx")]
+            Error::InvalidNonce(_) => ErrorCode::InvalidNonce,
+            Error::Revert(_) => ErrorCode::Revert,
+            Error::SelectorNotFound { .. } => ErrorCode::SelectorNotFound,
+            Error::StateMismatch { .. } => ErrorCode::StateMismatch,
+            Error::UnsupportedWireVersion(_) => {
+                ErrorCode::UnsupportedWireVersion
+            }
+            Error::DeferredInQuery(_) => ErrorCode::DeferredInQuery,
+            Error::AccessTracking(_) => ErrorCode::AccessTracking,
+        }
+    }
 }
 
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InstantiationError(e) => write!(f, "instantiation: {}", e),
+            Error::CompileError(e) => write!(f, "compile: {}", e),
+            Error::ExportError(e) => write!(f, "export: {}", e),
+            Error::RuntimeError(e) => write!(f, "runtime: {}", e),
+            Error::Trap(e) => write!(f, "trap: {:?}", e),
+            Error::MissingModuleExport => write!(f, "missing module export"),
+            Error::CompositeSerializerError(e) => {
+                write!(f, "serialization: {}", e)
+            }
+            Error::OutOfPoints(id) => write!(f, "out of points: {:?}", id),
+            Error::PersistenceError(e) => write!(f, "persistence: {}", e),
+            Error::ValidationError => write!(f, "validation failed"),
+            Error::PureViolation(id) => {
+                write!(f, "pure method attempted to change state: {:?}", id)
+            }
+            Error::EmitLimit(id) => write!(f, "emit limit exceeded: {:?}", id),
+            Error::DebugLimit(id) => {
+                write!(f, "debug limit exceeded: {:?}", id)
+            }
+            Error::WriteLimit(id) => {
+                write!(f, "write limit exceeded: {:?}", id)
+            }
+            Error::CommitNotFound(id) => {
+                write!(f, "commit not found: {:?}", id)
+            }
+            Error::CorruptSnapshot { module, commit } => write!(
+                f,
+                "corrupt snapshot of {:?} in commit {:?}",
+                module, commit
+            ),
+            Error::CorruptCommit(id) => {
+                write!(f, "corrupt commit: {:?}", id)
+            }
+            Error::ModuleNotFound(id) => {
+                write!(f, "module not found: {:?}", id)
+            }
+            Error::ArgumentTooLarge {
+                required,
+                available,
+            } => write!(
+                f,
+                "argument too large: requires at least {} bytes, {} available",
+                required, available
+            ),
+            Error::InvalidReturnData { module, method } => write!(
+                f,
+                "invalid return data from {:?} calling {}",
+                module, method
+            ),
+            Error::MethodNotFound { module, method } => {
+                write!(f, "method {} not found in {:?}", method, module)
+            }
+            Error::ArgumentMismatch {
+                module,
+                method,
+                expected,
+                found,
+            } => write!(
+                f,
+                "argument mismatch calling {} on {:?}: expected {}, found {}",
+                method, module, expected, found
+            ),
+            Error::ReturnTooLarge { module, method } => write!(
+                f,
+                "return too large from {:?} calling {}",
+                module, method
+            ),
+            Error::GuestOutOfMemory(id) => {
+                write!(f, "module ran out of memory: {:?}", id)
+            }
+            Error::MisalignedArgBuffer(id) => {
+                write!(f, "argument buffer misaligned: {:?}", id)
+            }
+            Error::SelfIdNotWritten(id) => {
+                write!(f, "self id could not be written: {:?}", id)
+            }
+            Error::MemoryMismatch(id) => {
+                write!(f, "memory image mismatch: {:?}", id)
+            }
+            Error::Panic { module, message } => {
+                write!(f, "module {:?} {}", module, message)
+            }
+            Error::DeployRejected(id, reason) => {
+                write!(f, "deploy of {:?} rejected: {}", id, reason)
+            }
+            Error::StorageLocked(path) => {
+                write!(f, "storage locked: {:?}", path)
+            }
+            Error::UnreservedModuleId(id) => {
+                write!(f, "module id not reserved: {:?}", id)
+            }
+            #[cfg(feature = "tx")]
+            Error::InvalidSignature => write!(f, "invalid signature"),
+            #[cfg(feature = "tx")]
+            Error::InvalidNonce(expected) => {
+                write!(f, "invalid nonce, expected: {}", expected)
+            }
+            Error::Revert(err) => write!(f, "{}", err),
+            Error::SelectorNotFound { module, selector } => write!(
+                f,
+                "method with selector {:#010x} not found in {:?}",
+                selector, module
+            ),
+            Error::StateMismatch { module, commit } => write!(
+                f,
+                "state of {:?} does not match commit {:?}",
+                module, commit
+            ),
+            Error::UnsupportedWireVersion(version) => {
+                write!(f, "unsupported wire version: {}", version)
+            }
+            Error::DeferredInQuery(id) => {
+                write!(f, "transaction deferred in a query: {:?}", id)
+            }
+            Error::AccessTracking(id) => {
+                write!(f, "memory accesses could not be tracked: {:?}", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 impl From<wasmer::InstantiationError> for Error {
     fn from(e: wasmer::InstantiationError) -> Self {
         Error::InstantiationError(e)
@@ -4,15 +4,24 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::Range;
+
 use colored::*;
 
 use bytecheck::CheckBytes;
 use dallo::{
-    ModuleId, StandardBufSerializer, MODULE_ID_BYTES, SCRATCH_BUF_BYTES,
+    standard_scratch, AbiType, ModuleId, RawResult, StandardBufSerializer,
+    MODULE_ID_BYTES, SCRATCH_BUF_BYTES,
 };
 use rkyv::{
-    check_archived_root,
-    ser::serializers::{BufferScratch, BufferSerializer, CompositeSerializer},
+    archived_root, check_archived_root,
+    ser::serializers::{
+        BufferSerializer, BufferSerializerError, CompositeSerializer,
+        CompositeSerializerError,
+    },
     ser::Serializer,
     validation::validators::DefaultValidator,
     Archive, Deserialize, Infallible, Serialize,
@@ -25,11 +34,33 @@ use wasmer_middlewares::metering::{
 use crate::error::*;
 use crate::memory::MemHandler;
 use crate::snapshot::SnapshotId;
-use crate::world::World;
+use crate::world::{
+    advice, coverage, instructions, regions, FunctionHits, MemoryAdvice,
+    MemoryLayout, SnapshotPolicy, World,
+};
+
+/// The function a module exports when declaring its ABI.
+const ABI_EXPORT: &str = "__abi";
+
+/// Method names up to this length are copied out of guest memory onto the
+/// stack, without touching the name buffer of the instance.
+const SMALL_NAME_LEN: usize = 32;
+
+/// The exported functions called so far, keyed by their names.
+#[derive(Default)]
+struct Functions(RefCell<BTreeMap<String, NativeFunc<u32, u32>>>);
+
+impl fmt::Debug for Functions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.borrow().keys()).finish()
+    }
+}
 
 #[derive(Debug)]
 pub struct Instance {
     id: ModuleId,
+    /// The hash of the bytecode the module was deployed from.
+    code_hash: [u8; 32],
     instance: wasmer::Instance,
     world: World,
     mem_handler: MemHandler,
@@ -37,21 +68,72 @@ pub struct Instance {
     heap_base: i32,
     self_id_ofs: i32,
     snapshot_id: Option<SnapshotId>,
+    pure_methods: BTreeSet<String>,
+    /// The names of the exported functions, keyed by their selector.
+    selectors: BTreeMap<u32, String>,
+    /// The hashes of the types of the arguments of the methods, if the
+    /// module declared them.
+    abi: Option<BTreeMap<String, u64>>,
+    /// The types of the errors the methods revert with, for the methods the
+    /// module declared them for.
+    error_types: BTreeMap<String, String>,
+    /// Percentage of the points spent by the instance that are charged.
+    gas_multiplier: u64,
+    /// The hundredths of a point left over when the points given to the
+    /// instance were scaled by the gas multiplier, given back when they are
+    /// read so that no points are lost to rounding.
+    points_remainder: Cell<u64>,
+    /// Which regions of the memory make up the state of the module.
+    snapshot_policy: SnapshotPolicy,
+    /// The advice last applied to the memory of the module.
+    memory_advice: MemoryAdvice,
+    /// Reused to hold the names of the methods the module calls on other
+    /// modules, taken out while a call is running.
+    name_buf: Cell<String>,
+    functions: Functions,
+}
+
+/// The memory of an instance saved before a call, restored if the call is
+/// rolled back.
+#[derive(Debug)]
+pub struct SavedMemory {
+    memory: Vec<u8>,
+    mem_handler: MemHandler,
+}
+
+impl SavedMemory {
+    /// Return the memory as it was saved.
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+}
+
+/// The state of an instance as it entered a pure frame, checked once the frame
+/// returns to make sure it was not written to.
+#[derive(Debug)]
+pub struct PureState {
+    hash: [u8; 32],
+    len: usize,
+    mem_handler: MemHandler,
 }
 
 impl Instance {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         id: ModuleId,
+        code_hash: [u8; 32],
         instance: wasmer::Instance,
         world: World,
         mem_handler: MemHandler,
         arg_buf_ofs: i32,
         heap_base: i32,
         self_id_ofs: i32,
+        pure_methods: BTreeSet<String>,
+        selectors: BTreeMap<u32, String>,
     ) -> Self {
         Instance {
             id,
+            code_hash,
             instance,
             world,
             mem_handler,
@@ -59,27 +141,177 @@ impl Instance {
             heap_base,
             self_id_ofs,
             snapshot_id: None,
+            pure_methods,
+            selectors,
+            abi: None,
+            error_types: BTreeMap::new(),
+            gas_multiplier: 100,
+            points_remainder: Cell::new(0),
+            snapshot_policy: SnapshotPolicy::default(),
+            memory_advice: MemoryAdvice::default(),
+            name_buf: Cell::new(String::new()),
+            functions: Functions::default(),
         }
     }
 
-    pub(crate) fn query<Arg, Ret>(
+    /// Returns true if the module declared the method as pure.
+    pub(crate) fn is_pure(&self, name: &str) -> bool {
+        self.pure_methods.contains(name)
+    }
+
+    /// Return the name of the function exported under the given selector.
+    pub(crate) fn selector_method(&self, selector: u32) -> Option<&str> {
+        self.selectors.get(&selector).map(String::as_str)
+    }
+
+    /// Load the methods the module declared using `dallo::abi!`, if any,
+    /// running the declaration with the given points.
+    pub(crate) fn load_abi(&mut self, limit: u64) -> Result<(), Error> {
+        if self.instance.exports.get_function(ABI_EXPORT).is_err() {
+            return Ok(());
+        }
+
+        self.set_remaining_points(limit);
+        let len = self.perform_query(ABI_EXPORT, 0)?;
+        self.check_return_len(ABI_EXPORT, len)?;
+
+        let mut abi = BTreeMap::new();
+        let mut error_types = BTreeMap::new();
+
+        self.with_arg_buffer(|buf| {
+            let lines = core::str::from_utf8(&buf[..len as usize])
+                .map_err(|_| Error::ValidationError)?;
+
+            for line in lines.lines() {
+                let mut fields = line.split('\t');
+                let (name, arg) = fields
+                    .next()
+                    .zip(fields.next())
+                    .ok_or(Error::ValidationError)?;
+
+                if let Some(err) = fields.next() {
+                    error_types.insert(name.into(), err.into());
+                }
+                if fields.next().is_some() {
+                    return Err(Error::ValidationError);
+                }
+
+                let arg = u64::from_str_radix(arg, 16)
+                    .map_err(|_| Error::ValidationError)?;
+                abi.insert(name.into(), arg);
+            }
+
+            Ok(())
+        })?;
+
+        self.abi = Some(abi);
+        self.error_types = error_types;
+        Ok(())
+    }
+
+    /// Return the name of the type of the errors the given method reverts
+    /// with, if the module declared it.
+    pub(crate) fn error_type(&self, method: &str) -> Option<&str> {
+        self.error_types.get(method).map(String::as_str)
+    }
+
+    /// Check a call to the method with an argument of type `Arg` matches the
+    /// ABI declared by the module, if any.
+    ///
+    /// Types are compared by the hash of their structure, so the argument
+    /// only has to archive the same way as the declared one.
+    pub(crate) fn check_argument<Arg: AbiType>(
         &self,
-        name: &str,
-        arg: Arg,
-    ) -> Result<Ret, Error>
-    where
-        Arg: for<'a> Serialize<StandardBufSerializer<'a>>,
-        Ret: Archive,
-        Ret::Archived: Deserialize<Ret, Infallible>
-            + for<'a> CheckBytes<DefaultValidator<'a>>,
-    {
-        let ret_len = {
-            let arg_len = self.write_to_arg_buffer(arg)?;
-            self.perform_query(name, arg_len)
-                .map_err(|e| map_call_err(self, e))?
+        method: &str,
+    ) -> Result<(), Error> {
+        let abi = match &self.abi {
+            Some(abi) => abi,
+            None => return Ok(()),
         };
 
-        self.read_from_arg_buffer(ret_len)
+        let expected =
+            abi.get(method).ok_or_else(|| Error::MethodNotFound {
+                module: self.id,
+                method: String::from(method),
+            })?;
+
+        let found = dallo::type_hash::<Arg>();
+        if *expected != found {
+            return Err(Error::ArgumentMismatch {
+                module: self.id,
+                method: String::from(method),
+                expected: format!("{:016x}", expected),
+                found: format!("{:016x}", found),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn save_memory(&self) -> SavedMemory {
+        SavedMemory {
+            memory: self.with_memory(|m| m.to_vec()),
+            mem_handler: self.mem_handler.clone(),
+        }
+    }
+
+    /// Record the state of the instance as it enters a pure frame.
+    pub(crate) fn enter_pure(&self) -> PureState {
+        let len = self.with_memory(|m| m.len());
+
+        PureState {
+            hash: self.hash_pure_state(len),
+            len,
+            mem_handler: self.mem_handler.clone(),
+        }
+    }
+
+    /// Free what was allocated on the heap since the instance entered a pure
+    /// frame, returning false if the state was written to in the meantime.
+    pub(crate) fn leave_pure(&mut self, entered: PureState) -> bool {
+        let a = entered.mem_handler.heap_top();
+        let b = self.heap_top();
+
+        self.with_memory_mut(|m| {
+            let b = b.min(m.len());
+            if a < b {
+                m[a..b].fill(0);
+            }
+        });
+        self.mem_handler = entered.mem_handler;
+
+        self.hash_pure_state(entered.len) == entered.hash
+    }
+
+    /// Hash the state in the first `len` bytes of the memory, leaving out the
+    /// stack and the argument buffer, which pure methods are free to write.
+    fn hash_pure_state(&self, len: usize) -> [u8; 32] {
+        let layout = self.layout();
+        let regions = regions::without(
+            &self.state_regions(len),
+            &[layout.stack_region, layout.arg_buf],
+        );
+
+        self.with_memory(|m| {
+            let mut hasher = blake3::Hasher::new();
+            for region in regions {
+                hasher.update(&m[region]);
+            }
+            hasher.finalize().into()
+        })
+    }
+
+    /// Restore previously saved memory, including the argument buffer.
+    ///
+    /// Memory can't shrink, so any pages grown since it was saved are zeroed
+    /// instead, as they were before growing.
+    pub(crate) fn restore_all_memory(&mut self, saved: SavedMemory) {
+        self.with_memory_mut(|m| {
+            let len = saved.memory.len();
+            m[..len].copy_from_slice(&saved.memory);
+            m[len..].fill(0);
+        });
+        self.mem_handler = saved.mem_handler;
     }
 
     pub(crate) fn perform_query(
@@ -87,50 +319,111 @@ impl Instance {
         name: &str,
         arg_len: u32,
     ) -> Result<u32, Error> {
-        let fun: NativeFunc<u32, u32> =
-            self.instance.exports.get_native_function(name)?;
+        let fun = self.function(name)?;
         Ok(fun.call(arg_len)?)
     }
 
-    pub(crate) fn transact<Arg, Ret>(
-        &mut self,
-        name: &str,
-        arg: Arg,
-    ) -> Result<Ret, Error>
-    where
-        Arg: for<'a> Serialize<StandardBufSerializer<'a>> + core::fmt::Debug,
-        Ret: Archive,
-        Ret::Archived: Deserialize<Ret, Infallible>
-            + for<'a> CheckBytes<DefaultValidator<'a>>,
-    {
-        let ret_len = {
-            let arg_len = self.write_to_arg_buffer(arg)?;
-            self.perform_transaction(name, arg_len)
-                .map_err(|e| map_call_err(self, e))?
-        };
-
-        self.read_from_arg_buffer(ret_len)
-    }
-
     pub(crate) fn perform_transaction(
         &self,
         name: &str,
         arg_len: u32,
     ) -> Result<u32, Error> {
+        let fun = self.function(name)?;
+        Ok(fun.call(arg_len)?)
+    }
+
+    /// Return the function exported under the given name, looking it up
+    /// only the first time it's called.
+    pub(crate) fn function(
+        &self,
+        name: &str,
+    ) -> Result<NativeFunc<u32, u32>, Error> {
+        if let Some(fun) = self.functions.0.borrow().get(name) {
+            return Ok(fun.clone());
+        }
+
         let fun: NativeFunc<u32, u32> =
             self.instance.exports.get_native_function(name)?;
-        Ok(fun.call(arg_len)?)
+        self.functions
+            .0
+            .borrow_mut()
+            .insert(String::from(name), fun.clone());
+
+        Ok(fun)
     }
 
+    /// Return the points remaining to the instance, which are the points
+    /// left to the metering middleware scaled by the gas multiplier.
     pub(crate) fn remaining_points(&self) -> u64 {
-        match get_remaining_points(&self.instance) {
+        let remaining = match get_remaining_points(&self.instance) {
             MeteringPoints::Remaining(r) => r,
-            MeteringPoints::Exhausted => 0,
-        }
+            MeteringPoints::Exhausted => return 0,
+        };
+        let hundredths = remaining as u128 * self.gas_multiplier as u128
+            + self.points_remainder.get() as u128;
+        (hundredths / 100).try_into().unwrap_or(u64::MAX)
     }
 
     pub(crate) fn set_remaining_points(&self, points: u64) {
-        set_remaining_points(&self.instance, points)
+        let hundredths = points as u128 * 100;
+        let multiplier = self.gas_multiplier as u128;
+
+        let remaining =
+            (hundredths / multiplier).try_into().unwrap_or(u64::MAX);
+        self.points_remainder.set((hundredths % multiplier) as u64);
+        set_remaining_points(&self.instance, remaining)
+    }
+
+    pub(crate) fn set_gas_multiplier(&mut self, percent: u64) {
+        self.gas_multiplier = percent;
+    }
+
+    pub(crate) fn set_snapshot_policy(&mut self, policy: SnapshotPolicy) {
+        self.snapshot_policy = policy;
+    }
+
+    /// Apply the given advice to the memory of the module, replacing the
+    /// previous one.
+    pub(crate) fn set_memory_advice(
+        &mut self,
+        advice: MemoryAdvice,
+    ) -> Result<(), Error> {
+        let previous = self.memory_advice;
+        self.with_memory(|memory| advice::apply(memory, previous, advice))
+            .map_err(Error::PersistenceError)?;
+        self.memory_advice = advice;
+        Ok(())
+    }
+
+    /// Return the regions making up the state of the module in a memory of
+    /// `len` bytes, according to its snapshot policy.
+    pub(crate) fn state_regions(&self, len: usize) -> Vec<Range<usize>> {
+        self.snapshot_policy
+            .regions(&self.layout().stack_region, len)
+    }
+
+    /// Hash the state of the module in the given memory, which need not be
+    /// its current one.
+    pub(crate) fn hash_state(&self, memory: &[u8]) -> SnapshotId {
+        regions::hash(&self.state_regions(memory.len()), memory)
+    }
+
+    /// Zero the parts of the given memory that are not part of the state of
+    /// the module.
+    pub(crate) fn zero_transient(&self, memory: &mut [u8]) {
+        regions::zero_outside(&self.state_regions(memory.len()), memory)
+    }
+
+    /// Zero the parts of the current memory that are not part of the state,
+    /// as done after restoring the module.
+    pub(crate) fn clear_transient(&self) {
+        if self.snapshot_policy != SnapshotPolicy::Full {
+            let len = self.with_memory(|memory| memory.len());
+            let regions = self.state_regions(len);
+            self.with_memory_mut(|memory| {
+                regions::zero_outside(&regions, memory)
+            });
+        }
     }
 
     pub(crate) fn with_memory<F, R>(&self, f: F) -> R
@@ -159,18 +452,47 @@ impl Instance {
         f(memory_bytes)
     }
 
-    pub(crate) fn write_self_id(&self, module_id: ModuleId) {
-        let mem =
-            self.instance.exports.get_memory("memory").expect(
-                "memory export should be checked at module creation time",
-            );
+    /// Replace the memory of the module with the given image, growing it if
+    /// the image is larger, and zeroing any memory past its end.
+    pub(crate) fn load_memory(&self, image: &[u8]) -> Result<(), Error> {
+        let mem = self
+            .instance
+            .exports
+            .get_memory("memory")
+            .expect("memory export is checked at module creation time");
+
+        let len = mem.data_size() as usize;
+        if image.len() > len {
+            let page_size = wasmer::WASM_PAGE_SIZE;
+            let pages = (image.len() - len).div_ceil(page_size);
+            mem.grow(wasmer::Pages(pages as u32))
+                .map_err(|_| Error::GuestOutOfMemory(self.id))?;
+        }
+
+        self.with_memory_mut(|memory| {
+            memory[..image.len()].copy_from_slice(image);
+            memory[image.len()..].fill(0);
+        });
+        self.write_self_id(self.id)
+    }
 
+    /// Write the given id to the `SELF_ID` of the module, failing if it
+    /// doesn't lie within the memory.
+    pub(crate) fn write_self_id(
+        &self,
+        module_id: ModuleId,
+    ) -> Result<(), Error> {
         let ofs = self.self_id_ofs as usize;
 
-        let memory = unsafe { mem.data_unchecked_mut() };
-        let self_id_buf = &mut memory[ofs..][..MODULE_ID_BYTES];
+        self.with_memory_mut(|memory| {
+            let self_id_buf = memory
+                .get_mut(ofs..)
+                .and_then(|buf| buf.get_mut(..MODULE_ID_BYTES))
+                .ok_or(Error::SelfIdNotWritten(module_id))?;
 
-        self_id_buf.copy_from_slice(module_id.as_bytes());
+            self_id_buf.copy_from_slice(module_id.as_bytes());
+            Ok(())
+        })
     }
 
     pub(crate) fn write_to_arg_buffer<T>(&self, value: T) -> Result<u32, Error>
@@ -179,32 +501,142 @@ impl Instance {
     {
         self.with_arg_buffer(|abuf| {
             let mut sbuf = [0u8; SCRATCH_BUF_BYTES];
-            let scratch = BufferScratch::new(&mut sbuf);
+            let scratch = standard_scratch(&mut sbuf);
             let ser = BufferSerializer::new(abuf);
             let mut ser =
                 CompositeSerializer::new(ser, scratch, rkyv::Infallible);
 
-            ser.serialize_value(&value)?;
+            ser.serialize_value(&value).map_err(|err| match err {
+                CompositeSerializerError::SerializerError(
+                    BufferSerializerError::Overflow {
+                        pos, bytes_needed, ..
+                    },
+                ) => Error::ArgumentTooLarge {
+                    required: pos + bytes_needed,
+                    available: dallo::ARGBUF_LEN,
+                },
+                err => Error::CompositeSerializerError(err),
+            })?;
 
             Ok(ser.pos() as u32)
         })
     }
 
-    fn read_from_arg_buffer<T>(&self, arg_len: u32) -> Result<T, Error>
+    pub(crate) fn read_from_arg_buffer<T>(
+        &self,
+        arg_len: u32,
+    ) -> Result<T, Error>
     where
         T: Archive,
         T::Archived: Deserialize<T, Infallible>
             + for<'a> CheckBytes<DefaultValidator<'a>>,
     {
-        // TODO use bytecheck here
         self.with_arg_buffer(|abuf| {
-            let slice = &abuf[..arg_len as usize];
+            let slice =
+                abuf.get(..arg_len as usize).ok_or(Error::ValidationError)?;
             let ta: &T::Archived = check_archived_root::<T>(slice)?;
             let t = ta.deserialize(&mut Infallible).expect("Infallible");
             Ok(t)
         })
     }
 
+    /// Read the return of a call to `method` from the argument buffer,
+    /// reporting data that fails validation as [`Error::InvalidReturnData`].
+    pub(crate) fn read_return<T>(
+        &self,
+        method: &str,
+        ret_len: u32,
+    ) -> Result<T, Error>
+    where
+        T: Archive,
+        T::Archived: Deserialize<T, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        self.check_return_len(method, ret_len)?;
+        self.read_from_arg_buffer(ret_len).map_err(|err| match err {
+            Error::ValidationError => Error::InvalidReturnData {
+                module: self.id,
+                method: String::from(method),
+            },
+            err => err,
+        })
+    }
+
+    /// Read the return of a call to `method` from the argument buffer,
+    /// without validating it.
+    ///
+    /// # Safety
+    /// The buffer must contain a valid archived `T`.
+    pub(crate) unsafe fn read_return_unchecked<T>(
+        &self,
+        method: &str,
+        ret_len: u32,
+    ) -> Result<T, Error>
+    where
+        T: Archive,
+        T::Archived: Deserialize<T, Infallible>,
+    {
+        self.check_return_len(method, ret_len)?;
+        self.with_arg_buffer(|abuf| {
+            let slice = abuf.get(..ret_len as usize).ok_or_else(|| {
+                Error::InvalidReturnData {
+                    module: self.id,
+                    method: String::from(method),
+                }
+            })?;
+            let ta: &T::Archived = archived_root::<T>(slice);
+            let t = ta.deserialize(&mut Infallible).expect("Infallible");
+            Ok(t)
+        })
+    }
+
+    /// Check the return of a call to `method` fits in the argument buffer,
+    /// failing with [`Error::ReturnTooLarge`] otherwise.
+    pub(crate) fn check_return_len(
+        &self,
+        method: &str,
+        ret_len: u32,
+    ) -> Result<(), Error> {
+        if ret_len as usize > dallo::ARGBUF_LEN {
+            return Err(Error::ReturnTooLarge {
+                module: self.id,
+                method: String::from(method),
+            });
+        }
+        Ok(())
+    }
+
+    /// Copy the given serialized argument to the argument buffer, returning
+    /// its length.
+    pub(crate) fn write_raw_argument(&self, arg: &[u8]) -> Result<u32, Error> {
+        if arg.len() > dallo::ARGBUF_LEN {
+            return Err(Error::ArgumentTooLarge {
+                required: arg.len(),
+                available: dallo::ARGBUF_LEN,
+            });
+        }
+        self.with_arg_buffer(|buf| buf[..arg.len()].copy_from_slice(arg));
+
+        Ok(arg.len() as u32)
+    }
+
+    /// Read the serialized return of the given method from the argument
+    /// buffer.
+    pub(crate) fn read_raw_return(
+        &self,
+        method: &str,
+        ret_len: u32,
+    ) -> Result<RawResult, Error> {
+        self.check_return_len(method, ret_len)?;
+        self.with_arg_buffer(|buf| {
+            buf.get(..ret_len as usize).map(RawResult::new)
+        })
+        .ok_or_else(|| Error::InvalidReturnData {
+            module: self.id,
+            method: String::from(method),
+        })
+    }
+
     pub(crate) fn with_arg_buffer<F, R>(&self, f: F) -> R
     where
         F: FnOnce(&mut [u8]) -> R,
@@ -218,8 +650,129 @@ impl Instance {
         })
     }
 
-    pub(crate) fn alloc(&mut self, amount: usize, align: usize) -> usize {
-        self.mem_handler.alloc(amount, align)
+    /// Call the given closure with the method name at the given offset in
+    /// memory, without allocating in the common case.
+    ///
+    /// Short names are copied onto the stack, and longer ones into the name
+    /// buffer of the instance. The buffer is taken out for the duration of
+    /// the closure, so a call re-entering the module allocates a new one.
+    pub(crate) fn with_method_name<F, R>(
+        &self,
+        ofs: usize,
+        len: usize,
+        f: F,
+    ) -> R
+    where
+        F: FnOnce(&str) -> R,
+    {
+        if len <= SMALL_NAME_LEN {
+            let mut bytes = [0u8; SMALL_NAME_LEN];
+            self.with_memory(|buf| {
+                bytes[..len].copy_from_slice(&buf[ofs..][..len])
+            });
+            let name = core::str::from_utf8(&bytes[..len])
+                .expect("TODO, error out cleaner");
+            return f(name);
+        }
+
+        let mut name = self.name_buf.take();
+        name.clear();
+        self.with_memory(|buf| {
+            name.push_str(
+                core::str::from_utf8(&buf[ofs..][..len])
+                    .expect("TODO, error out cleaner"),
+            )
+        });
+
+        let ret = f(&name);
+        self.name_buf.set(name);
+        ret
+    }
+
+    pub(crate) fn heap_top(&self) -> usize {
+        self.mem_handler.heap_top()
+    }
+
+    /// Return the number of bytes allocated on the heap, and the number of
+    /// bytes the heap can hold.
+    pub(crate) fn heap_stats(&self) -> (u64, u64) {
+        let heap_base = self.heap_base as usize;
+        let mem_len = self.with_memory(|mem| mem.len());
+
+        let used = self.heap_top() - heap_base;
+        let limit = mem_len.saturating_sub(heap_base);
+
+        (used as u64, limit as u64)
+    }
+
+    /// Return how the memory of the module is laid out.
+    ///
+    /// Modules not exporting the end of their static data are taken to have
+    /// no stack region.
+    pub(crate) fn layout(&self) -> MemoryLayout {
+        let heap_base = self.heap_base as usize;
+        let data_end = match self.instance.exports.get_global("__data_end") {
+            Ok(global) => match global.get() {
+                wasmer::Val::I32(data_end) => data_end as usize,
+                _ => heap_base,
+            },
+            Err(_) => heap_base,
+        };
+
+        let arg_buf_ofs = self.arg_buf_ofs as usize;
+
+        MemoryLayout {
+            data_end,
+            heap_base,
+            arg_buf: arg_buf_ofs..arg_buf_ofs + dallo::ARGBUF_LEN,
+            stack_region: data_end..heap_base,
+        }
+    }
+
+    /// Touch every page of the memory of the module, so that later calls
+    /// don't pay for faulting them in.
+    pub(crate) fn warm_up(&self) {
+        const OS_PAGE_SIZE: usize = 4096;
+
+        self.with_memory(|memory| {
+            for page in memory.chunks(OS_PAGE_SIZE) {
+                std::hint::black_box(page[0]);
+            }
+        });
+    }
+
+    /// Return the number of pages of linear memory currently in use, and the
+    /// maximum number of pages the memory can grow to.
+    pub(crate) fn memory_pages(&self) -> (u32, u32) {
+        let mem = self
+            .instance
+            .exports
+            .get_memory("memory")
+            .expect("memory export is checked at module creation time");
+
+        let pages = mem.size().0;
+        let limit = mem.ty().maximum.unwrap_or(wasmer::WASM_MAX_PAGES.into());
+
+        (pages, limit.0)
+    }
+
+    /// Allocate on the heap of the module, returning `None` if the memory is
+    /// exhausted.
+    pub(crate) fn alloc(
+        &mut self,
+        amount: usize,
+        align: usize,
+    ) -> Option<usize> {
+        let limit = self.with_memory(|mem| mem.len());
+        self.mem_handler.alloc(amount, align, limit)
+    }
+
+    pub(crate) fn instructions(&self) -> u64 {
+        instructions::instructions(&self.instance)
+    }
+
+    pub(crate) fn function_hits(&self) -> Option<Vec<FunctionHits>> {
+        coverage::function_hits(&self.instance)
     }
 
     pub(crate) fn dealloc(&mut self, _addr: usize) {}
@@ -228,6 +781,11 @@ impl Instance {
         self.id
     }
 
+    /// Return the hash of the bytecode the module was deployed from.
+    pub fn code_hash(&self) -> [u8; 32] {
+        self.code_hash
+    }
+
     pub(crate) fn set_snapshot_id(&mut self, snapshot_id: SnapshotId) {
         self.snapshot_id = Some(snapshot_id);
     }
@@ -284,7 +842,7 @@ impl Instance {
         }
     }
 
-    pub fn debug(&self, ofs: i32, len: u32) {
+    pub fn debug(&self, ofs: i32, len: u32) -> Result<(), Error> {
         let string = self.with_memory(|m| {
             String::from(
                 core::str::from_utf8(&m[ofs as usize..][..len as usize])
@@ -293,18 +851,29 @@ impl Instance {
         });
 
         println!("CONTRACT DEBUG: {}", &string);
-        self.world.debug(string)
+        self.world.debug(self.id, string)
     }
 }
 
-fn map_call_err(instance: &Instance, err: Error) -> Error {
+/// Turn an error raised by a call to the given instance into the one it
+/// stands for, which is running out of points if the instance has none left.
+pub(crate) fn map_call_err(instance: &Instance, err: Error) -> Error {
     match err {
-        e @ Error::RuntimeError(_) => {
+        Error::RuntimeError(e) => {
             match get_remaining_points(&instance.instance) {
-                MeteringPoints::Remaining(_) => e,
+                MeteringPoints::Remaining(_) => host_error(e),
                 MeteringPoints::Exhausted => Error::OutOfPoints(instance.id),
             }
         }
         e => e,
     }
 }
+
+/// Recover the error a host function trapped with, if any.
+fn host_error(err: wasmer::RuntimeError) -> Error {
+    match err.downcast::<Error>() {
+        Ok(Error::RuntimeError(e)) => host_error(e),
+        Ok(e) => e,
+        Err(e) => Error::RuntimeError(e),
+    }
+}
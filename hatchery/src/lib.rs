@@ -4,16 +4,32 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+pub mod conformance;
+mod differential;
 mod env;
 mod error;
 mod instance;
 mod memory;
 mod snapshot;
 mod storage_helpers;
+#[cfg(feature = "tx")]
+mod tx;
+pub mod wire;
 mod world;
 
-pub use error::Error;
-pub use world::{Event, NativeQuery, Receipt, World};
+pub use differential::{Differential, Divergence, DivergenceKind};
+pub use error::{Error, ErrorCode};
+#[cfg(feature = "tx")]
+pub use tx::{SignatureVerifier, SignedTransaction, TxPayload};
+pub use world::{
+    CallFailure, CallRecord, CommitHook, CommitId, CommitInfo, ContractRef,
+    CoverageReport, DeployHook, DeployReceipt, Event, FloatPolicy,
+    FunctionHits, HeapGrowth, HeapReport, MemoryAdvice, MemoryLayout,
+    MemoryWitness, ModuleError, ModuleSnapshotId, ModuleState,
+    ModuleStateBuilder, NativeQuery, ReadOnlyWorld, Receipt, SnapshotPolicy,
+    Sponsor, SponsorMode, StateProof, SystemModule, WasmFeatures, Witness,
+    World, WorldStats, WITNESS_PAGE_SIZE,
+};
 
 #[macro_export]
 macro_rules! module_bytecode {
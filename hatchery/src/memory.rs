@@ -4,7 +4,7 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MemHandler {
     heap_base: usize,
 }
@@ -18,10 +18,26 @@ impl MemHandler {
         self.heap_base += self.heap_base % n;
     }
 
-    pub fn alloc(&mut self, size: usize, align: usize) -> usize {
-        self.align_to(align);
-        let ofs = self.heap_base;
-        self.heap_base += size;
-        ofs
+    /// Return the offset past the last allocated byte.
+    pub fn heap_top(&self) -> usize {
+        self.heap_base
+    }
+
+    /// Allocate `size` bytes, returning `None` if they would extend past
+    /// `limit`.
+    pub fn alloc(
+        &mut self,
+        size: usize,
+        align: usize,
+        limit: usize,
+    ) -> Option<usize> {
+        let mut handler = self.clone();
+        handler.align_to(align);
+
+        let ofs = handler.heap_base;
+        let end = ofs.checked_add(size).filter(|end| *end <= limit)?;
+
+        self.heap_base = end;
+        Some(ofs)
     }
 }
@@ -38,6 +38,11 @@ impl From<[u8; 32]> for SnapshotId {
         SnapshotId(array)
     }
 }
+impl From<SnapshotId> for [u8; 32] {
+    fn from(snapshot_id: SnapshotId) -> Self {
+        snapshot_id.0
+    }
+}
 
 pub trait SnapshotLike {
     fn path(&self) -> &PathBuf;
@@ -77,13 +82,6 @@ pub struct Snapshot {
 }
 
 impl Snapshot {
-    pub fn new(memory_path: &MemoryPath) -> Result<Self, Error> {
-        let snapshot_id: SnapshotId = SnapshotId::from(
-            *blake3::hash(memory_path.read()?.as_slice()).as_bytes(),
-        );
-        Snapshot::from_id(snapshot_id, memory_path)
-    }
-
     pub fn from_id(
         snapshot_id: SnapshotId,
         memory_path: &MemoryPath,
@@ -0,0 +1,166 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Signed transactions, verified by the host before being performed.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+use std::path::Path;
+
+use dallo::{ModuleId, RawTransaction};
+use rkyv::{AlignedVec, Deserialize, Infallible};
+
+use crate::error::Error;
+use crate::Error::PersistenceError;
+
+/// The signed part of a [`SignedTransaction`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxPayload {
+    pub module_id: ModuleId,
+    pub raw: RawTransaction,
+    pub nonce: u64,
+    pub limit: u64,
+}
+
+impl TxPayload {
+    /// Return the canonical encoding of the payload, which is the message
+    /// to be signed.
+    ///
+    /// The encoding is the module id, followed by the nonce and the limit as
+    /// little endian, the length of the method name as a little endian `u32`,
+    /// the method name, and finally the serialized argument.
+    pub fn to_signable_bytes(&self) -> Vec<u8> {
+        let name = self.raw.name().as_bytes();
+        let arg = self.raw.arg_bytes();
+
+        let mut bytes = Vec::with_capacity(
+            self.module_id.as_bytes().len() + 20 + name.len() + arg.len(),
+        );
+
+        bytes.extend_from_slice(self.module_id.as_bytes());
+        bytes.extend_from_slice(&self.nonce.to_le_bytes());
+        bytes.extend_from_slice(&self.limit.to_le_bytes());
+        bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(name);
+        bytes.extend_from_slice(arg);
+
+        bytes
+    }
+}
+
+/// A transaction signed by an account, identified by its public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTransaction {
+    pub payload: TxPayload,
+    pub signer: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// A signature scheme provided by the host.
+///
+/// Called with the public key of the signer, the message, and the signature,
+/// it should return whether the signature is valid.
+pub trait SignatureVerifier: Fn(&[u8], &[u8], &[u8]) -> bool {}
+impl<F> SignatureVerifier for F where F: Fn(&[u8], &[u8], &[u8]) -> bool {}
+
+type Nonces = Vec<(Vec<u8>, u64)>;
+
+/// The verifier and the next expected nonce of each signer, or of each
+/// identity transacting using [`World::transact_with_nonce`].
+///
+/// [`World::transact_with_nonce`]: crate::World::transact_with_nonce
+#[derive(Default)]
+pub(crate) struct TxState {
+    verifier: Option<Box<dyn SignatureVerifier>>,
+    nonces: BTreeMap<Vec<u8>, u64>,
+}
+
+impl Debug for TxState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TxState")
+            .field("verifier", &self.verifier.is_some())
+            .field("nonces", &self.nonces)
+            .finish()
+    }
+}
+
+impl TxState {
+    pub fn set_verifier<V>(&mut self, verifier: V)
+    where
+        V: 'static + SignatureVerifier,
+    {
+        self.verifier = Some(Box::new(verifier));
+    }
+
+    /// Return the nonce the next transaction of the signer must have.
+    pub fn nonce(&self, signer: &[u8]) -> u64 {
+        self.nonces.get(signer).copied().unwrap_or(0)
+    }
+
+    /// Verify the signature and nonce of a transaction, consuming the nonce
+    /// if both are valid.
+    pub fn verify(&mut self, tx: &SignedTransaction) -> Result<(), Error> {
+        let verifier = self.verifier.as_ref().ok_or(Error::InvalidSignature)?;
+
+        let msg = tx.payload.to_signable_bytes();
+        if !verifier(&tx.signer, &msg, &tx.signature) {
+            return Err(Error::InvalidSignature);
+        }
+
+        self.consume_nonce(&tx.signer, tx.payload.nonce)
+    }
+
+    /// Consume the nonce of the identity, if it is the expected one.
+    pub fn consume_nonce(
+        &mut self,
+        identity: &[u8],
+        nonce: u64,
+    ) -> Result<(), Error> {
+        let expected = self.nonce(identity);
+        if nonce != expected {
+            return Err(Error::InvalidNonce(expected));
+        }
+
+        self.nonces.insert(identity.to_vec(), expected + 1);
+        Ok(())
+    }
+
+    /// Write the nonces to the given file.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let nonces: Nonces = self
+            .nonces
+            .iter()
+            .map(|(signer, nonce)| (signer.clone(), *nonce))
+            .collect();
+
+        let bytes = rkyv::to_bytes::<_, 1024>(&nonces)
+            .expect("Serializing the nonces should succeed");
+        std::fs::write(path, bytes).map_err(PersistenceError)
+    }
+
+    /// Replace the nonces with the ones in the given file, clearing them if
+    /// the file does not exist.
+    pub fn load(&mut self, path: &Path) -> Result<(), Error> {
+        self.nonces.clear();
+
+        if !path.exists() {
+            return Ok(());
+        }
+
+        let contents = std::fs::read(path).map_err(PersistenceError)?;
+
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(&contents);
+
+        let archived = rkyv::check_archived_root::<Nonces>(&bytes[..])
+            .map_err(|_| Error::ValidationError)?;
+        let nonces: Nonces =
+            archived.deserialize(&mut Infallible).expect("Infallible");
+
+        self.nonces.extend(nonces);
+        Ok(())
+    }
+}
@@ -0,0 +1,316 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! A stable encoding of receipts, events, and commit infos, for exchanging
+//! them between nodes.
+//!
+//! The in-memory types are free to change between versions of this crate, so
+//! they're never archived directly. Instead, each is converted to a wire type
+//! with a fixed layout, archived using `rkyv`, and prefixed by two bytes:
+//! [`WIRE_VERSION`] and the kind of value encoded. A change to the layout of
+//! any wire type must bump the version, so that nodes fail to decode values
+//! of a version they don't know instead of misreading them.
+//!
+//! Witnesses are not part of the encoding, so decoded receipts have none.
+
+use bytecheck::CheckBytes;
+use dallo::{ModuleId, RawResult};
+use rkyv::ser::serializers::AllocSerializer;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{AlignedVec, Archive, Deserialize, Infallible, Serialize};
+
+use crate::error::{Error, ErrorCode};
+use crate::world::{
+    CallFailure, CommitId, CommitInfo, Event, ModuleError, Receipt,
+};
+
+/// The version of the encoding, the first byte of every encoded value.
+pub const WIRE_VERSION: u8 = 1;
+
+const RECEIPT: u8 = 0;
+const EVENT: u8 = 1;
+const COMMIT_INFO: u8 = 2;
+
+const HEADER_LEN: usize = 2;
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes), repr(C))]
+struct WireEvent {
+    module_id: ModuleId,
+    callers: Vec<ModuleId>,
+    method: String,
+    data: Vec<u8>,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes), repr(C))]
+struct WireModuleError {
+    module: ModuleId,
+    method: String,
+    payload: Vec<u8>,
+    type_name: Option<String>,
+    rendering: Option<String>,
+}
+
+/// A call, as recorded in a receipt. The calls deferred by a transaction are
+/// kept next to it instead of inside it, since they defer no calls of their
+/// own.
+#[derive(Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes), repr(C))]
+struct WireCall {
+    ret: Vec<u8>,
+    events: Vec<WireEvent>,
+    debug: Vec<String>,
+    reverts: Vec<WireModuleError>,
+    sponsor: Option<Vec<u8>>,
+    spent: u64,
+    limit: u64,
+    instructions: u64,
+    ret_len: u64,
+    height: u64,
+    timestamp: u64,
+    sponsored: u64,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes), repr(C))]
+struct WireCallFailure {
+    module: ModuleId,
+    method: String,
+    message: String,
+    code: u16,
+    spent: u64,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes), repr(C))]
+struct WireReceipt {
+    call: WireCall,
+    deferred: Vec<WireCall>,
+    failures: Vec<WireCallFailure>,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes), repr(C))]
+struct WireCommitInfo {
+    id: [u8; 32],
+    height: u64,
+    timestamp: u64,
+}
+
+/// Encode a receipt of a raw call.
+pub fn encode_receipt(receipt: &Receipt<RawResult>) -> Vec<u8> {
+    let wire = WireReceipt {
+        call: WireCall::from(receipt),
+        deferred: receipt.deferred().iter().map(WireCall::from).collect(),
+        failures: receipt
+            .failures()
+            .iter()
+            .map(WireCallFailure::from)
+            .collect(),
+    };
+    encode(RECEIPT, &wire)
+}
+
+/// Decode a receipt encoded using [`encode_receipt`].
+pub fn decode_receipt(bytes: &[u8]) -> Result<Receipt<RawResult>, Error> {
+    let wire: WireReceipt = decode(RECEIPT, bytes)?;
+
+    let deferred = wire.deferred.into_iter().map(Receipt::from).collect();
+    let failures = wire
+        .failures
+        .into_iter()
+        .map(CallFailure::try_from)
+        .collect::<Result<_, _>>()?;
+
+    Ok(Receipt::from(wire.call)
+        .with_deferred(deferred)
+        .with_failures(failures))
+}
+
+/// Encode an event.
+pub fn encode_event(event: &Event) -> Vec<u8> {
+    encode(EVENT, &WireEvent::from(event))
+}
+
+/// Decode an event encoded using [`encode_event`].
+pub fn decode_event(bytes: &[u8]) -> Result<Event, Error> {
+    decode::<WireEvent>(EVENT, bytes).map(Event::from)
+}
+
+/// Encode a commit info.
+pub fn encode_commit_info(info: &CommitInfo) -> Vec<u8> {
+    let wire = WireCommitInfo {
+        id: *info.id().as_bytes(),
+        height: info.height(),
+        timestamp: info.timestamp(),
+    };
+    encode(COMMIT_INFO, &wire)
+}
+
+/// Decode a commit info encoded using [`encode_commit_info`].
+pub fn decode_commit_info(bytes: &[u8]) -> Result<CommitInfo, Error> {
+    let wire: WireCommitInfo = decode(COMMIT_INFO, bytes)?;
+    Ok(CommitInfo::new(
+        CommitId::from(wire.id),
+        wire.height,
+        wire.timestamp,
+    ))
+}
+
+fn encode<T>(kind: u8, value: &T) -> Vec<u8>
+where
+    T: Serialize<AllocSerializer<1024>>,
+{
+    let archived = rkyv::to_bytes::<_, 1024>(value)
+        .expect("Serializing to memory should succeed");
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + archived.len());
+    bytes.extend_from_slice(&[WIRE_VERSION, kind]);
+    bytes.extend_from_slice(&archived);
+    bytes
+}
+
+fn decode<T>(kind: u8, bytes: &[u8]) -> Result<T, Error>
+where
+    T: Archive,
+    T::Archived:
+        Deserialize<T, Infallible> + for<'a> CheckBytes<DefaultValidator<'a>>,
+{
+    let (header, archived) = match bytes.len() {
+        len if len >= HEADER_LEN => bytes.split_at(HEADER_LEN),
+        _ => return Err(Error::ValidationError),
+    };
+
+    if header[0] != WIRE_VERSION {
+        return Err(Error::UnsupportedWireVersion(header[0]));
+    }
+    if header[1] != kind {
+        return Err(Error::ValidationError);
+    }
+
+    let mut aligned = AlignedVec::new();
+    aligned.extend_from_slice(archived);
+
+    let archived = rkyv::check_archived_root::<T>(&aligned[..])
+        .map_err(|_| Error::ValidationError)?;
+
+    Ok(archived.deserialize(&mut Infallible).expect("Infallible"))
+}
+
+impl From<&Event> for WireEvent {
+    fn from(event: &Event) -> Self {
+        WireEvent {
+            module_id: *event.module_id(),
+            callers: event.callers().to_vec(),
+            method: String::from(event.method()),
+            data: event.data().to_vec(),
+        }
+    }
+}
+
+impl From<WireEvent> for Event {
+    fn from(wire: WireEvent) -> Self {
+        Event::new(wire.module_id, wire.callers, wire.method, wire.data)
+    }
+}
+
+impl From<&ModuleError> for WireModuleError {
+    fn from(err: &ModuleError) -> Self {
+        WireModuleError {
+            module: err.module(),
+            method: String::from(err.method()),
+            payload: err.payload().to_vec(),
+            type_name: err.type_name().map(String::from),
+            rendering: err.rendering().map(String::from),
+        }
+    }
+}
+
+impl From<WireModuleError> for ModuleError {
+    fn from(wire: WireModuleError) -> Self {
+        ModuleError::new(
+            wire.module,
+            wire.method,
+            wire.payload,
+            wire.type_name.zip(wire.rendering),
+        )
+    }
+}
+
+impl From<&CallFailure> for WireCallFailure {
+    fn from(failure: &CallFailure) -> Self {
+        WireCallFailure {
+            module: failure.module(),
+            method: String::from(failure.method()),
+            message: String::from(failure.message()),
+            code: failure.code().as_u16(),
+            spent: failure.spent(),
+        }
+    }
+}
+
+impl TryFrom<WireCallFailure> for CallFailure {
+    type Error = Error;
+
+    fn try_from(wire: WireCallFailure) -> Result<Self, Error> {
+        let code =
+            ErrorCode::from_code(wire.code).ok_or(Error::ValidationError)?;
+        Ok(CallFailure::from_parts(
+            wire.module,
+            wire.method,
+            code,
+            wire.message,
+            wire.spent,
+        ))
+    }
+}
+
+impl From<&Receipt<RawResult>> for WireCall {
+    fn from(receipt: &Receipt<RawResult>) -> Self {
+        WireCall {
+            ret: receipt.ret().as_bytes().to_vec(),
+            events: receipt.events().iter().map(WireEvent::from).collect(),
+            debug: receipt.debug().to_vec(),
+            reverts: receipt
+                .reverts()
+                .iter()
+                .map(WireModuleError::from)
+                .collect(),
+            sponsor: receipt.sponsor().map(<[u8]>::to_vec),
+            spent: receipt.spent(),
+            limit: receipt.limit(),
+            instructions: receipt.instructions(),
+            ret_len: receipt.ret_len() as u64,
+            height: receipt.height(),
+            timestamp: receipt.timestamp(),
+            sponsored: receipt.sponsored(),
+        }
+    }
+}
+
+impl From<WireCall> for Receipt<RawResult> {
+    fn from(wire: WireCall) -> Self {
+        let receipt = Receipt::new(
+            RawResult::new(&wire.ret),
+            wire.events.into_iter().map(Event::from).collect(),
+            wire.debug,
+            wire.spent,
+        )
+        .with_limit(wire.limit)
+        .with_instructions(wire.instructions)
+        .with_ret_len(wire.ret_len as u32)
+        .with_context(wire.height, wire.timestamp)
+        .with_reverts(
+            wire.reverts.into_iter().map(ModuleError::from).collect(),
+        );
+
+        match wire.sponsor {
+            Some(sponsor) => receipt.with_sponsor(sponsor, wire.sponsored),
+            None => receipt,
+        }
+    }
+}
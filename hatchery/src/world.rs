@@ -4,55 +4,196 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+mod access;
+pub(crate) mod advice;
+mod bloom;
+mod cache;
+mod commit;
+mod contract;
+pub(crate) mod coverage;
+mod deploy;
 mod event;
+mod float;
+mod heap;
+mod history;
+mod hooks;
+mod index;
+pub(crate) mod instructions;
+mod layout;
+mod lock;
+mod log;
+mod metadata;
+mod migrate;
 mod native;
+mod pins;
+mod prune;
+mod read_only;
+pub(crate) mod regions;
+mod revert;
+mod savepoint;
+mod schedule;
+mod sponsor;
 mod stack;
+mod stats;
 mod store;
-
+mod subscriptions;
+mod system;
+mod witness;
+mod writes;
+
+pub use advice::MemoryAdvice;
+pub use commit::{CommitId, CommitInfo, ModuleSnapshotId, StateProof};
+pub use contract::ContractRef;
+pub use coverage::{CoverageReport, FunctionHits};
+pub use deploy::DeployReceipt;
 pub use event::{Event, Receipt};
+pub use float::FloatPolicy;
+pub use heap::{HeapGrowth, HeapReport};
+pub use history::CallRecord;
+pub use hooks::{CommitHook, DeployHook};
+pub use layout::MemoryLayout;
+pub use migrate::{ModuleState, ModuleStateBuilder};
+
+use event::Observer;
 pub use native::NativeQuery;
+pub use read_only::ReadOnlyWorld;
+pub use regions::SnapshotPolicy;
+pub use revert::{CallFailure, ModuleError};
+pub use sponsor::{Sponsor, SponsorMode};
+pub use stats::WorldStats;
+pub use store::WasmFeatures;
+pub use system::SystemModule;
+pub use witness::{MemoryWitness, Witness, WITNESS_PAGE_SIZE};
 
 use std::cell::UnsafeCell;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::mem;
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, RangeBounds};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use access::AccessTracker;
 use bytecheck::CheckBytes;
-use dallo::{ModuleId, StandardBufSerializer, MODULE_ID_BYTES};
+use cache::{CachedQuery, QueryCache};
+use commit::Commit;
+use dallo::{
+    AbiType, AlignedBytes, ErrorEnvelope, ModuleId, RawQuery, RawResult,
+    RawTransaction, StandardBufSerializer, MODULE_ID_BYTES,
+};
+use heap::HeapTracker;
+use history::CallHistory;
+use hooks::{CommitHooks, DeployHooks};
+use instructions::InstructionTracker;
+use lock::StorageLock;
+use log::EventLog;
+use metadata::Metadata;
 use native::NativeQueries;
 use parking_lot::ReentrantMutex;
+use pins::Pins;
+use revert::ErrorTypes;
 use rkyv::{
     validation::validators::DefaultValidator, Archive, Deserialize, Infallible,
     Serialize,
 };
+use savepoint::{Journal, Savepoint};
+use schedule::Schedule;
 use stack::CallStack;
 use store::new_store;
-use tempfile::tempdir;
+use subscriptions::{SubscriptionChange, Subscriptions};
+use system::SystemModules;
+use tempfile::TempDir;
 use wasmer::{imports, Exports, Function, Val};
+use witness::WitnessRecorder;
+use writes::WriteTracker;
 
 use crate::env::Env;
 use crate::error::Error;
-use crate::instance::Instance;
+use crate::instance::{map_call_err, Instance, PureState};
 use crate::memory::MemHandler;
-use crate::snapshot::{MemoryPath, Snapshot, SnapshotLike};
-use crate::storage_helpers::module_id_to_name;
+use crate::snapshot::{MemoryPath, Snapshot, SnapshotId, SnapshotLike};
+use crate::storage_helpers::{
+    combine_module_snapshot_names, module_id_to_name, snapshot_id_to_name,
+};
+#[cfg(feature = "tx")]
+use crate::tx::{SignatureVerifier, SignedTransaction, TxState};
 use crate::Error::PersistenceError;
 
 const DEFAULT_POINT_LIMIT: u64 = 4096;
 const POINT_PASS_PERCENTAGE: u64 = 93;
 
+/// Points charged for each call to `random`, plus per byte drawn.
+const RANDOM_POINTS: u64 = 32;
+const RANDOM_BYTE_POINTS: u64 = 1;
+
 #[derive(Debug)]
 pub struct WorldInner {
     environments: BTreeMap<ModuleId, Env>,
     native_queries: NativeQueries,
+    system_modules: SystemModules,
+    query_cache: QueryCache,
+    witness: WitnessRecorder,
+    accesses: AccessTracker,
+    heap: HeapTracker,
+    instructions: InstructionTracker,
+    writes: WriteTracker,
+    pure_states: BTreeMap<ModuleId, PureState>,
+    journal: Journal,
     storage_path: PathBuf,
+    storage_lock: StorageLock,
     debug: Vec<String>,
     events: Vec<Event>,
+    /// Errors of the calls that reverted and were handled by their callers.
+    reverts: Vec<ModuleError>,
+    error_types: ErrorTypes,
+    features: WasmFeatures,
+    float_policy: FloatPolicy,
+    coverage: bool,
+    emit_limit: OutputLimit,
+    debug_limit: OutputLimit,
+    /// Share of its heap, in percent, past which a module is under memory
+    /// pressure.
+    soft_memory_limit: Option<u64>,
+    deferred: Vec<(ModuleId, RawTransaction)>,
+    schedule: Schedule,
+    subscriptions: Subscriptions,
+    /// Subscriptions changed by the current call, applied once it succeeds.
+    subscription_changes: Vec<SubscriptionChange>,
+    metadata: Metadata,
+    pins: Pins,
+    observers: Vec<Observer>,
+    event_log: EventLog,
+    history: CallHistory,
+    deploy_hooks: DeployHooks,
+    commit_hooks: CommitHooks,
+    #[cfg(feature = "tx")]
+    tx: TxState,
     call_stack: CallStack,
+    /// Whether the current call is a query, whose deferred transactions
+    /// would never be performed.
+    querying: bool,
+    /// Number of external handles to the world.
+    handles: usize,
+    /// Root of the state as of the last persist or restore.
+    root: [u8; 32],
+    commits: BTreeMap<CommitId, Commit>,
+    points_spent: u64,
+    state: Commit,
+    dirty: BTreeSet<ModuleId>,
+    /// Hash of the height and metadata of the current call, together with
+    /// its module, method and argument.
+    call_hash: [u8; 32],
+    /// Metadata of the current transaction.
+    tx_meta: Vec<u8>,
+    random_counter: u64,
     height: u64,
-    limit: u64,
+    timestamp: u64,
+    /// Point limit of calls, unless overridden.
+    default_limit: u64,
+    /// Point limit overriding the default one.
+    limit: Option<u64>,
+    /// Point limit of queries, if different from the one of transactions.
+    query_limit: Option<u64>,
 }
 
 impl Deref for WorldInner {
@@ -69,355 +210,2975 @@ impl DerefMut for WorldInner {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct World(Arc<ReentrantMutex<UnsafeCell<WorldInner>>>);
-
-impl World {
-    pub fn new<P>(path: P) -> Self
-    where
-        P: Into<PathBuf>,
-    {
-        World(Arc::new(ReentrantMutex::new(UnsafeCell::new(WorldInner {
+impl WorldInner {
+    fn new(storage_path: PathBuf, storage_lock: StorageLock) -> Self {
+        WorldInner {
             environments: BTreeMap::new(),
             native_queries: NativeQueries::new(),
-            storage_path: path.into(),
+            system_modules: SystemModules::new(),
+            query_cache: QueryCache::default(),
+            witness: WitnessRecorder::default(),
+            accesses: AccessTracker::default(),
+            heap: HeapTracker::default(),
+            instructions: InstructionTracker::default(),
+            writes: WriteTracker::default(),
+            pure_states: BTreeMap::new(),
+            journal: Journal::default(),
+            storage_path,
+            storage_lock,
             events: vec![],
             debug: vec![],
+            reverts: vec![],
+            error_types: ErrorTypes::default(),
+            features: WasmFeatures::default(),
+            float_policy: FloatPolicy::default(),
+            coverage: false,
+            emit_limit: OutputLimit::UNLIMITED,
+            debug_limit: OutputLimit::UNLIMITED,
+            soft_memory_limit: None,
+            deferred: vec![],
+            schedule: Schedule::default(),
+            subscriptions: Subscriptions::default(),
+            subscription_changes: vec![],
+            metadata: Metadata::default(),
+            pins: Pins::default(),
+            observers: vec![],
+            event_log: EventLog::default(),
+            history: CallHistory::default(),
+            deploy_hooks: DeployHooks::default(),
+            commit_hooks: CommitHooks::default(),
+            #[cfg(feature = "tx")]
+            tx: TxState::default(),
             call_stack: CallStack::default(),
+            querying: false,
+            handles: 1,
+            root: [0; 32],
+            commits: BTreeMap::new(),
+            points_spent: 0,
+            state: Commit::default(),
+            dirty: BTreeSet::new(),
+            call_hash: [0; 32],
+            tx_meta: vec![],
+            random_counter: 0,
             height: 0,
-            limit: DEFAULT_POINT_LIMIT,
-        }))))
+            timestamp: 0,
+            default_limit: DEFAULT_POINT_LIMIT,
+            limit: None,
+            query_limit: None,
+        }
     }
 
-    pub fn ephemeral() -> Result<Self, Error> {
-        Ok(World(Arc::new(ReentrantMutex::new(UnsafeCell::new(
-            WorldInner {
-                environments: BTreeMap::new(),
-                native_queries: NativeQueries::new(),
-                storage_path: tempdir()
-                    .map_err(PersistenceError)?
-                    .path()
-                    .into(),
-                events: vec![],
-                debug: vec![],
-                call_stack: CallStack::default(),
-                height: 0,
-                limit: DEFAULT_POINT_LIMIT,
-            },
-        )))))
+    /// Return the point limit given to calls.
+    fn limit(&self) -> u64 {
+        self.limit.unwrap_or(self.default_limit)
     }
 
-    pub fn persist(&self) -> Result<(), Error> {
-        let guard = self.0.lock();
-        let w = unsafe { &mut *guard.get() };
-        for (module_id, environment) in w.environments.iter() {
-            let memory_path = MemoryPath::new(self.memory_path(module_id));
-            let snapshot = Snapshot::new(&memory_path)?;
-            environment.inner_mut().set_snapshot_id(snapshot.id());
-            snapshot.save(&memory_path)?;
+    /// Return the point limit given to queries.
+    fn query_limit(&self) -> u64 {
+        self.query_limit.unwrap_or_else(|| self.limit())
+    }
+
+    /// Prepare for a call to the method `name` of the given module, whose
+    /// argument is already serialized in the module's argument buffer.
+    fn start_call(
+        &mut self,
+        module_id: ModuleId,
+        name: &str,
+        arg_len: u32,
+        limit: u64,
+        pure: bool,
+    ) -> Result<(), Error> {
+        if self.witness.is_enabled() {
+            let root = self.state_root();
+            self.witness.start(*root.as_bytes());
+        }
+
+        let instance = self.environments[&module_id].inner();
+        self.call_hash = instance.with_arg_buffer(|buf| {
+            // the same call made at another height, or by another sender,
+            // must not draw the same random bytes
+            let mut hasher = blake3::Hasher::new();
+            hasher.update(&self.height.to_le_bytes());
+            hasher.update(&(self.tx_meta.len() as u32).to_le_bytes());
+            hasher.update(&self.tx_meta);
+            hasher.update(module_id.as_bytes());
+            hasher.update(&(name.len() as u32).to_le_bytes());
+            hasher.update(name.as_bytes());
+            hasher.update(&buf[..arg_len as usize]);
+            hasher.finalize().into()
+        });
+        self.random_counter = 0;
+
+        self.heap.clear();
+        self.heap.enter(module_id, instance.heap_top());
+        self.instructions.clear();
+        self.instructions.enter(module_id, instance);
+        self.writes.clear();
+        self.subscription_changes.clear();
+        self.journal.enter(module_id, instance);
+        self.dirty.insert(module_id);
+
+        self.call_stack = CallStack::new(module_id, name, limit, pure);
+        if pure {
+            self.enter_pure(module_id);
+        }
+
+        // entered last, so that the memory copied or hashed on entering is
+        // not taken to be accessed by the call
+        self.accesses.clear();
+        self.track_accesses(module_id)
+    }
+
+    /// Start tracking the pages accessed in the memory of a module, if it
+    /// was not yet entered during the call and a witness is being recorded.
+    fn track_accesses(&mut self, module_id: ModuleId) -> Result<(), Error> {
+        if self.witness.is_enabled() && !self.accesses.is_tracking(&module_id) {
+            let instance = self.environments[&module_id].inner();
+            instance.with_memory(|mem| self.accesses.track(module_id, mem))?;
         }
         Ok(())
     }
 
-    pub fn restore(&self) -> Result<(), Error> {
-        let guard = self.0.lock();
-        let w = unsafe { &mut *guard.get() };
-        for (module_id, environment) in w.environments.iter() {
-            let memory_path = MemoryPath::new(self.memory_path(module_id));
-            if let Some(snapshot_id) = environment.inner().snapshot_id() {
-                let snapshot = Snapshot::from_id(*snapshot_id, &memory_path)?;
-                snapshot.load(&memory_path)?;
-                println!(
-                    "restored state of module: {:?} from file: {:?}",
-                    module_id_to_name(*module_id),
-                    snapshot.path()
+    /// Stop tracking the memories of the modules entered once the call
+    /// returned, handing the accesses made to them to the witness.
+    fn untrack_accesses(&mut self) {
+        for module_id in self.accesses.tracked() {
+            let instance = self.environments[&module_id].inner();
+            let accesses = instance
+                .with_memory(|mem| self.accesses.untrack(&module_id, mem));
+            if let Some(accesses) = accesses {
+                self.witness.record(module_id, accesses);
+            }
+        }
+    }
+
+    /// Perform a transaction whose argument is already serialized in the
+    /// argument buffer of the module, using at most `limit` points.
+    ///
+    /// Returns the length of the return and the points spent.
+    fn call_transaction(
+        &mut self,
+        module_id: ModuleId,
+        name: &str,
+        arg_len: u32,
+        limit: u64,
+    ) -> Result<(u32, u64), Error> {
+        let instance = self.environments[&module_id].inner();
+        instance.set_remaining_points(limit);
+
+        let arg = self.history.is_enabled().then(|| {
+            instance.with_arg_buffer(|buf| buf[..arg_len as usize].to_vec())
+        });
+
+        let pure = instance.is_pure(name);
+        self.querying = false;
+        let started = self.start_call(module_id, name, arg_len, limit, pure);
+
+        let instance = self.environments[&module_id].inner();
+        self.writes.enter(module_id, instance);
+        let ret_len = started.and_then(|_| {
+            instance
+                .perform_transaction(name, arg_len)
+                .map_err(|e| map_call_err(instance, e))
+        });
+        self.untrack_accesses();
+        let left = self.leave_pure();
+
+        let environments = &self.environments;
+        self.heap
+            .finish(name, |id| environments[&id].inner().heap_top());
+
+        let ret_len = ret_len?;
+        left?;
+        let remaining =
+            self.environments[&module_id].inner().remaining_points();
+        let spent = self.writes.finish(
+            module_id,
+            &self.environments,
+            limit - remaining,
+            limit,
+        )?;
+        self.points_spent += spent;
+        if let Some(arg) = arg {
+            self.history.record(module_id, name, &arg);
+        }
+        for change in mem::take(&mut self.subscription_changes) {
+            self.subscriptions.apply(change);
+        }
+
+        Ok((ret_len, spent))
+    }
+
+    /// Return the root of the current state of all modules, hashing again
+    /// the ones taking part in a call since it was last computed.
+    fn state_root(&mut self) -> CommitId {
+        for module_id in mem::take(&mut self.dirty) {
+            let instance = self.environments[&module_id].inner();
+            let hash =
+                instance.with_memory(|memory| instance.hash_state(memory));
+            self.state.insert(module_id, hash);
+        }
+
+        self.state.id()
+    }
+
+    /// Give the given module the points of a transaction about to be made to
+    /// it, so that [`spent_by`](Self::spent_by) is accurate even if the
+    /// transaction fails before it starts.
+    fn reset_points(&self, module_id: ModuleId, limit: u64) {
+        if let Some(env) = self.environments.get(&module_id) {
+            env.inner().set_remaining_points(limit);
+        }
+    }
+
+    /// Return the points spent by the last transaction made to the given
+    /// module with the given limit, even if it failed.
+    fn spent_by(&self, module_id: ModuleId, limit: u64) -> u64 {
+        self.environments.get(&module_id).map_or(0, |env| {
+            limit.saturating_sub(env.inner().remaining_points())
+        })
+    }
+
+    /// Record the state of a module entering a pure frame, unless it was
+    /// already recorded by an enclosing pure frame.
+    fn enter_pure(&mut self, module_id: ModuleId) {
+        if !self.pure_states.contains_key(&module_id) {
+            let instance = self.environments[&module_id].inner();
+            self.pure_states.insert(module_id, instance.enter_pure());
+        }
+    }
+
+    /// Defer a call to `on_event` on the modules subscribed to each of the
+    /// given events, in the order the events were emitted.
+    fn notify_subscribers(&mut self, events: &[Event]) {
+        for event in events {
+            for subscriber in self.subscriptions.subscribers(event.module_id())
+            {
+                let raw = RawTransaction::new(
+                    "on_event",
+                    (*event.module_id(), event.data().to_vec()),
                 );
+                self.deferred.push((*subscriber, raw));
             }
         }
-        Ok(())
     }
 
-    pub fn memory_path(&self, module_id: &ModuleId) -> PathBuf {
-        self.storage_path().join(module_id_to_name(*module_id))
+    /// Leave the outermost pure frame, failing with
+    /// [`Error::PureViolation`] if it wrote to the state of any of the modules
+    /// it entered.
+    fn leave_pure(&mut self) -> Result<(), Error> {
+        let mut violation = None;
+
+        for (module_id, entered) in mem::take(&mut self.pure_states) {
+            let instance = self.environments[&module_id].inner_mut();
+            if !instance.leave_pure(entered) && violation.is_none() {
+                violation = Some(module_id);
+            }
+        }
+
+        match violation {
+            Some(module_id) => Err(Error::PureViolation(module_id)),
+            None => Ok(()),
+        }
     }
+}
 
-    pub fn deploy(&mut self, bytecode: &[u8]) -> Result<ModuleId, Error> {
-        let id_bytes: [u8; MODULE_ID_BYTES] = blake3::hash(bytecode).into();
-        let id = ModuleId::from(id_bytes);
+/// Caps on the number of items, and on their cumulative size in bytes, a
+/// single call is allowed to output.
+#[derive(Debug, Clone, Copy)]
+struct OutputLimit {
+    count: usize,
+    bytes: usize,
+}
 
-        let store = new_store(
-            self.storage_path().join(module_id_to_name(id)).as_path(),
-        );
-        let module = wasmer::Module::new(&store, bytecode)?;
+impl OutputLimit {
+    const UNLIMITED: Self = OutputLimit {
+        count: usize::MAX,
+        bytes: usize::MAX,
+    };
 
-        let mut env = Env::uninitialized();
+    fn allows(&self, count: usize, bytes: usize) -> bool {
+        count <= self.count && bytes <= self.bytes
+    }
+}
 
-        let imports = imports! {
-            "env" => {
-                "alloc" => Function::new_native_with_env(&store, env.clone(), host_alloc),
-                "dealloc" => Function::new_native_with_env(&store, env.clone(), host_dealloc),
+#[derive(Debug)]
+pub struct World(Arc<ReentrantMutex<UnsafeCell<WorldInner>>>, Handle);
+
+/// Who holds a handle to a world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Handle {
+    /// The users of the world, who keep it alive.
+    External,
+    /// The instances of the modules in the world, which are dropped with it.
+    Internal,
+}
 
-                "snap" => Function::new_native_with_env(&store, env.clone(), host_snapshot),
+impl Clone for World {
+    fn clone(&self) -> Self {
+        if self.1 == Handle::External {
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+            w.handles += 1;
+        }
+        World(self.0.clone(), self.1)
+    }
+}
 
-                "q" => Function::new_native_with_env(&store, env.clone(), host_query),
-                "nq" => Function::new_native_with_env(&store, env.clone(), host_native_query),
-                "t" => Function::new_native_with_env(&store, env.clone(), host_transact),
+impl Drop for World {
+    fn drop(&mut self) {
+        if self.1 == Handle::Internal {
+            return;
+        }
 
-                "height" => Function::new_native_with_env(&store, env.clone(), host_height),
-                "host_debug" => Function::new_native_with_env(&store, env.clone(), host_debug),
-                "host_panic" => Function::new_native_with_env(&store, env.clone(), host_panic),
-                "emit" => Function::new_native_with_env(&store, env.clone(), host_emit),
-                "caller" => Function::new_native_with_env(&store, env.clone(), host_caller),
-                "limit" => Function::new_native_with_env(&store, env.clone(), host_limit),
-                "spent" => Function::new_native_with_env(&store, env.clone(), host_spent),
+        let environments = {
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+
+            w.handles -= 1;
+            if w.handles > 0 {
+                return;
             }
+            mem::take(&mut w.environments)
         };
 
-        let instance = wasmer::Instance::new(&module, &imports)?;
-
-        let arg_buf_ofs = global_i32(&instance.exports, "A")?;
+        // the instances hold internal handles, so closing them drops the
+        // world once the last of them is gone
+        for env in environments.values() {
+            env.close();
+        }
+    }
+}
 
-        // TODO: We should check these buffers have the correct length.
-        let self_id_ofs = global_i32(&instance.exports, "SELF_ID")?;
+/// Restores the world when dropped, unless disarmed by taking it out.
+struct RestoreGuard(Option<World>);
 
-        let heap_base = global_i32(&instance.exports, "__heap_base")?;
+impl Drop for RestoreGuard {
+    fn drop(&mut self) {
+        if let Some(world) = self.0.take() {
+            // the failure that caused the restore is the one reported
+            let _ = world.restore();
+        }
+    }
+}
 
-        // check buffer alignment
-        // debug_assert_eq!(arg_buf_ofs % 8, 0);
+impl World {
+    /// Open a world at the given storage path, locking it exclusively.
+    ///
+    /// Fails with [`Error::StorageLocked`] if another world holds a lock on
+    /// the same path.
+    pub fn new<P>(path: P) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let lock = StorageLock::exclusive(&path)?;
+        World::with_lock(path, lock)
+    }
 
-        // We need to read the actual value of AL from the offset into memory
+    /// Open a world at the given storage path, sharing the lock on it with
+    /// other read-only worlds.
+    ///
+    /// A read-only world can run calls, but fails to
+    /// [`persist`](Self::persist) with [`Error::StorageLocked`].
+    pub fn new_read_only<P>(path: P) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let lock = StorageLock::shared(&path)?;
+        World::with_lock(path, lock)
+    }
 
-        let instance = Instance::new(
-            id,
-            instance,
-            self.clone(),
-            MemHandler::new(heap_base as usize),
-            arg_buf_ofs,
-            heap_base,
-            self_id_ofs,
+    fn with_lock(path: PathBuf, lock: StorageLock) -> Result<Self, Error> {
+        let world = World(
+            Arc::new(ReentrantMutex::new(UnsafeCell::new(WorldInner::new(
+                path, lock,
+            )))),
+            Handle::External,
         );
-        instance.write_self_id(id);
 
-        env.initialize(instance);
+        {
+            let guard = world.0.lock();
+            let w = unsafe { &mut *guard.get() };
+            w.pins = Pins::load(&world.pins_path())?;
+            w.metadata = Metadata::load(&world.metadata_path())?;
+            w.commits = index::read(&world.commits_path())?;
+        }
 
-        let guard = self.0.lock();
-        let w = unsafe { &mut *guard.get() };
-        w.insert(id, env);
+        Ok(world)
+    }
 
-        Ok(id)
+    /// Open a world in a new temporary directory, created in the default
+    /// location for temporary files.
+    pub fn ephemeral() -> Result<Self, Error> {
+        World::ephemeral_in(std::env::temp_dir())
     }
 
-    /// Registers a [`NativeQuery`] with the given `name`.
-    pub fn register_native_query<Q>(&mut self, name: &'static str, query: Q)
+    /// Open a world in a new temporary directory, created in the given
+    /// scratch directory.
+    pub fn ephemeral_in<P>(scratch_dir: P) -> Result<Self, Error>
     where
-        Q: 'static + NativeQuery,
+        P: AsRef<Path>,
     {
+        let dir = TempDir::new_in(scratch_dir).map_err(PersistenceError)?;
+        World::new(dir.keep())
+    }
+
+    /// Snapshot the memories of all modules, returning the id of the
+    /// resulting commit.
+    pub fn persist(&self) -> Result<CommitId, Error> {
         let guard = self.0.lock();
         let w = unsafe { &mut *guard.get() };
 
-        w.native_queries.insert(name, query);
+        if !w.storage_lock.is_exclusive() {
+            return Err(Error::StorageLocked(w.storage_path.clone()));
+        }
+
+        let mut commit = Commit::default();
+        for (module_id, environment) in w.environments.iter() {
+            let memory_path = MemoryPath::new(self.memory_path(module_id));
+            // The world is locked, so the memory can't change until the
+            // snapshot is saved.
+            let instance = environment.inner();
+            let snapshot_id =
+                instance.with_memory(|memory| instance.hash_state(memory));
+            let snapshot = Snapshot::from_id(snapshot_id, &memory_path)?;
+            environment.inner_mut().set_snapshot_id(snapshot.id());
+            snapshot.save(&memory_path)?;
+
+            commit.insert(*module_id, snapshot.id());
+        }
+
+        let commit_id = commit.id();
+
+        // the same state can be committed more than once, with the events of
+        // each time accumulating
+        if let Some(previous) = w.commits.get(&commit_id) {
+            commit.events_mut().union(previous.events());
+        }
+        commit.events_mut().extend(w.event_log.pending());
+
+        // modules can read the id of the commit, so queries made against
+        // the previous one can't be served from the cache
+        if w.root != *commit_id.as_bytes() {
+            w.query_cache.clear();
+        }
+        w.root = *commit_id.as_bytes();
+        w.state = commit.clone();
+        w.dirty.clear();
+        index::append(&self.commits_path(), commit_id, &commit)?;
+        w.commits.insert(commit_id, commit);
+
+        w.schedule.save(&self.schedule_path())?;
+        w.subscriptions.save(&self.subscriptions_path())?;
+        w.metadata.save(&self.metadata_path())?;
+        #[cfg(feature = "tx")]
+        w.tx.save(&self.nonces_path())?;
+        w.event_log.append(&self.event_log_path(), commit_id)?;
+        w.history.append(&self.history_path(), commit_id)?;
+
+        let info = CommitInfo::new(commit_id, w.height, w.timestamp);
+        let modules: Vec<_> = w
+            .state
+            .snapshots()
+            .map(|(module_id, snapshot_id)| (*module_id, (*snapshot_id).into()))
+            .collect();
+
+        // Hooks are taken out of the world while they are called, so that
+        // they can use it.
+        let mut hooks = mem::take(&mut w.commit_hooks);
+        drop(guard);
+
+        hooks.committed(&info, &modules);
+
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+        hooks.append(&mut w.commit_hooks);
+        w.commit_hooks = hooks;
+
+        Ok(commit_id)
     }
 
-    pub fn query<Arg, Ret>(
-        &self,
-        m_id: ModuleId,
-        name: &str,
-        arg: Arg,
-    ) -> Result<Receipt<Ret>, Error>
+    /// Register a hook called after every successful [`persist`], with the
+    /// commit made and the hash of the memory of each module in it.
+    ///
+    /// Hooks are called before [`persist`] returns, in the order they were
+    /// registered, so external indexes can be kept in step with the state.
+    ///
+    /// [`persist`]: World::persist
+    pub fn on_commit<H>(&mut self, hook: H)
     where
-        Arg: for<'a> Serialize<StandardBufSerializer<'a>>,
-        Ret: Archive,
-        Ret::Archived: Deserialize<Ret, Infallible>
-            + for<'a> CheckBytes<DefaultValidator<'a>>,
+        H: 'static + CommitHook + Send,
     {
         let guard = self.0.lock();
         let w = unsafe { &mut *guard.get() };
 
-        w.call_stack = CallStack::new(m_id, w.limit);
+        w.commit_hooks.push(hook);
+    }
 
-        let instance = w.get(&m_id).expect("invalid module id").inner();
-        instance.set_remaining_points(w.limit);
+    /// Return the root of the current state of all modules.
+    ///
+    /// This is the id the commit would have if the world were persisted now.
+    /// Only the modules taking part in a call since the root was last
+    /// computed are hashed again.
+    pub fn root(&self) -> CommitId {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
 
-        let ret = instance.query(name, arg)?;
-        let remaining = instance.remaining_points();
+        w.state_root()
+    }
 
-        let events = mem::take(&mut w.events);
-        let debug = mem::take(&mut w.debug);
+    /// Return the heap usage of the modules across the transactions
+    /// performed on this world.
+    pub fn heap_report(&self) -> HeapReport {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
 
-        Ok(Receipt::new(ret, events, debug, w.limit - remaining))
+        w.heap.report().clone()
     }
 
-    pub fn transact<Arg, Ret>(
-        &mut self,
-        m_id: ModuleId,
-        name: &str,
-        arg: Arg,
-    ) -> Result<Receipt<Ret>, Error>
-    where
-        Arg: for<'a> Serialize<StandardBufSerializer<'a>> + core::fmt::Debug,
-        Ret: Archive,
-        Ret::Archived: Deserialize<Ret, Infallible>
-            + for<'a> CheckBytes<DefaultValidator<'a>>,
-    {
-        let w = self.0.lock();
-        let w = unsafe { &mut *w.get() };
+    /// Return the resources used by the world, aggregated over all its
+    /// modules.
+    pub fn stats(&self) -> Result<WorldStats, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        let memory_bytes = w
+            .environments
+            .values()
+            .map(|env| env.inner().with_memory(|memory| memory.len()))
+            .sum();
+
+        let snapshots: BTreeSet<_> = w
+            .commits
+            .values()
+            .flat_map(|commit| commit.snapshots())
+            .collect();
+
+        Ok(WorldStats {
+            modules: w.environments.len(),
+            memory_bytes,
+            commits: w.commits.len(),
+            snapshots: snapshots.len(),
+            disk_usage: stats::disk_usage(self.storage_path())?,
+            points_spent: w.points_spent,
+        })
+    }
 
-        w.call_stack = CallStack::new(m_id, w.limit);
+    /// Return how many times each function was called in the modules
+    /// deployed with coverage enabled.
+    pub fn coverage(&self) -> CoverageReport {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
 
-        let instance = w.get(&m_id).expect("invalid module id").inner_mut();
-        instance.set_remaining_points(w.limit);
+        let mut report = CoverageReport::default();
+        for (module_id, env) in w.environments.iter() {
+            if let Some(functions) = env.inner().function_hits() {
+                report.insert(*module_id, functions);
+            }
+        }
 
-        let ret = instance.transact(name, arg)?;
-        let remaining = instance.remaining_points();
+        report
+    }
 
-        let events = mem::take(&mut w.events);
-        let debug = mem::take(&mut w.debug);
+    /// Return how the memory of the given module is laid out, to interpret
+    /// its memory, for instance as returned by
+    /// [`export_module_state`](Self::export_module_state).
+    pub fn module_layout(
+        &self,
+        module_id: ModuleId,
+    ) -> Result<MemoryLayout, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        let env = w
+            .environments
+            .get(&module_id)
+            .ok_or(Error::ModuleNotFound(module_id))?;
 
-        Ok(Receipt::new(ret, events, debug, w.limit - remaining))
+        Ok(env.inner().layout())
     }
 
-    /// Set the height available to modules.
-    pub fn set_height(&mut self, height: u64) {
-        let w = self.0.lock();
-        let w = unsafe { &mut *w.get() };
+    /// Prepare the given modules to be called, faulting in their memories
+    /// ahead of time, and return how long each of them took.
+    ///
+    /// Modules are compiled and instantiated when deployed, so this leaves
+    /// only the cost of bringing their memories in to be paid up front.
+    pub fn warm_up(
+        &self,
+        modules: &[ModuleId],
+    ) -> Result<Vec<(ModuleId, Duration)>, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
 
-        w.height = height;
+        modules
+            .iter()
+            .map(|module_id| {
+                let env = w
+                    .environments
+                    .get(module_id)
+                    .ok_or(Error::ModuleNotFound(*module_id))?;
+
+                let start = Instant::now();
+                env.inner().warm_up();
+                Ok((*module_id, start.elapsed()))
+            })
+            .collect()
     }
 
-    /// Set the point limit for the next call.
-    pub fn set_point_limit(&mut self, limit: u64) {
-        let w = self.0.lock();
-        let w = unsafe { &mut *w.get() };
+    /// Return a handle to the given module, performing queries and
+    /// transactions on it.
+    pub fn contract(
+        &mut self,
+        module_id: ModuleId,
+    ) -> Result<ContractRef<'_>, Error> {
+        {
+            let guard = self.0.lock();
+            let w = unsafe { &*guard.get() };
+
+            if !w.environments.contains_key(&module_id) {
+                return Err(Error::ModuleNotFound(module_id));
+            }
+        }
 
-        w.limit = limit;
+        Ok(ContractRef::new(self, module_id))
     }
 
-    fn perform_query(
+    /// Look up the given methods of a module ahead of their first call.
+    fn prepare_methods(
         &self,
-        name: &str,
-        caller_id: ModuleId,
-        callee_id: ModuleId,
-        arg_len: u32,
-    ) -> Result<u32, Error> {
+        module_id: ModuleId,
+        methods: &[&str],
+    ) -> Result<(), Error> {
         let guard = self.0.lock();
-        let w = unsafe { &mut *guard.get() };
+        let w = unsafe { &*guard.get() };
 
-        let caller = w.get(&caller_id).expect("oh no").inner();
+        let instance = w
+            .environments
+            .get(&module_id)
+            .ok_or(Error::ModuleNotFound(module_id))?
+            .inner();
+
+        for method in methods {
+            instance
+                .function(method)
+                .map_err(|_| Error::MethodNotFound {
+                    module: module_id,
+                    method: String::from(*method),
+                })?;
+        }
 
-        let remaining = caller.remaining_points();
-        let limit = remaining * POINT_PASS_PERCENTAGE / 100;
+        Ok(())
+    }
+
+    /// Return the memory of a module as it was in the given commit, together
+    /// with the proof of its inclusion in it.
+    ///
+    /// The parts of the memory left out of the state by the
+    /// [`SnapshotPolicy`] of the module are zeroed.
+    pub fn export_module_state(
+        &self,
+        commit_id: CommitId,
+        module_id: ModuleId,
+    ) -> Result<(Vec<u8>, StateProof), Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        let commit = w
+            .commits
+            .get(&commit_id)
+            .ok_or(Error::CommitNotFound(commit_id))?;
 
-        w.call_stack.push(callee_id, limit);
+        let (snapshot_id, proof) = commit
+            .snapshot_id(&module_id)
+            .zip(commit.proof(&module_id))
+            .ok_or(Error::ModuleNotFound(module_id))?;
+
+        let memory_path = MemoryPath::new(self.memory_path(&module_id));
+        let mut memory =
+            Snapshot::from_id(snapshot_id, &memory_path)?.read()?;
+
+        if let Some(env) = w.environments.get(&module_id) {
+            env.inner().zero_transient(&mut memory);
+        }
+
+        Ok((memory, proof))
+    }
+
+    /// Return the modules in the given commit, each with the id of its
+    /// snapshot.
+    pub fn commit_modules(
+        &self,
+        commit_id: CommitId,
+    ) -> Result<BTreeMap<ModuleId, ModuleSnapshotId>, Error> {
+        Ok(self
+            .canonical_modules(commit_id)?
+            .into_iter()
+            .map(|(module_id, hash)| (module_id, hash.into()))
+            .collect())
+    }
+
+    /// Return the modules in the given commit, each with the hash of its
+    /// memory, in the canonical order used to compute the commit id.
+    ///
+    /// Passing them to [`CommitId::compute`] gives back the commit id.
+    pub fn canonical_modules(
+        &self,
+        commit_id: CommitId,
+    ) -> Result<Vec<(ModuleId, [u8; 32])>, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        let commit = w
+            .commits
+            .get(&commit_id)
+            .ok_or(Error::CommitNotFound(commit_id))?;
+
+        Ok(commit
+            .snapshots()
+            .map(|(module_id, snapshot_id)| (*module_id, (*snapshot_id).into()))
+            .collect())
+    }
+
+    /// Check the snapshots of the given commit are intact, failing with
+    /// [`Error::CorruptSnapshot`] on the first one whose contents no longer
+    /// match, and that they still add up to the commit, failing with
+    /// [`Error::CorruptCommit`] otherwise.
+    ///
+    /// Every snapshot of the commit is read back from disk, so this is meant
+    /// to be run by operators after crashes or disk failures, not during
+    /// normal operation. Snapshots of modules no longer in the world are
+    /// hashed using the [`SnapshotPolicy`] recorded for them.
+    pub fn verify(&self, commit_id: CommitId) -> Result<(), Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        let commit = w
+            .commits
+            .get(&commit_id)
+            .ok_or(Error::CommitNotFound(commit_id))?;
+
+        let mut recomputed = Commit::default();
+        for (module_id, snapshot_id) in commit.snapshots() {
+            let memory_path = MemoryPath::new(self.memory_path(module_id));
+            let memory = Snapshot::from_id(*snapshot_id, &memory_path)?
+                .read()
+                .map_err(|_| Error::CorruptSnapshot {
+                    module: *module_id,
+                    commit: commit_id,
+                })?;
+
+            let hash = match w.environments.get(module_id) {
+                Some(env) => env.inner().hash_state(&memory),
+                None => w.metadata.hash_state(module_id, &memory),
+            };
+            if hash != *snapshot_id {
+                return Err(Error::CorruptSnapshot {
+                    module: *module_id,
+                    commit: commit_id,
+                });
+            }
+
+            recomputed.insert(*module_id, hash);
+        }
+
+        if recomputed.id() != commit_id {
+            return Err(Error::CorruptCommit(commit_id));
+        }
+
+        Ok(())
+    }
+
+    pub fn restore(&self) -> Result<(), Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+        w.query_cache.clear();
+        for (module_id, environment) in w.environments.iter() {
+            let memory_path = MemoryPath::new(self.memory_path(module_id));
+            if let Some(snapshot_id) = environment.inner().snapshot_id() {
+                let snapshot = Snapshot::from_id(*snapshot_id, &memory_path)?;
+                snapshot.load(&memory_path)?;
+                environment.inner().clear_transient();
+                println!(
+                    "restored state of module: {:?} from file: {:?}",
+                    module_id_to_name(*module_id),
+                    snapshot.path()
+                );
+            }
+        }
+        w.dirty.extend(w.environments.keys());
+        // the world may not have persisted since it was opened, in which
+        // case the root is not yet the one of the restored state
+        w.root = *w.state_root().as_bytes();
+        w.event_log.clear();
+        w.history.clear();
+        w.schedule = Schedule::load(&self.schedule_path())?;
+        w.subscriptions = Subscriptions::load(&self.subscriptions_path())?;
+        #[cfg(feature = "tx")]
+        w.tx.load(&self.nonces_path())?;
+        Ok(())
+    }
+
+    /// Return a handle to the world that can run queries, but can't transact,
+    /// deploy, or persist.
+    ///
+    /// The handle shares the modules of the world, so its queries see the
+    /// current state of the world, including changes not yet persisted.
+    pub fn read_only(&self) -> ReadOnlyWorld {
+        ReadOnlyWorld::new(self.clone())
+    }
+
+    /// Bring the memories of the given modules back to the given commit,
+    /// leaving the other modules as they are.
+    ///
+    /// This is meant to repair single modules, such as one whose memory got
+    /// corrupted, so every other module must already match the commit, with
+    /// the restore failing with [`Error::StateMismatch`] before anything is
+    /// restored otherwise. A snapshot that no longer matches the commit fails
+    /// with [`Error::CorruptSnapshot`].
+    pub fn restore_modules(
+        &self,
+        commit_id: CommitId,
+        modules: &[ModuleId],
+    ) -> Result<(), Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        let commit = w
+            .commits
+            .get(&commit_id)
+            .ok_or(Error::CommitNotFound(commit_id))?;
+
+        for module_id in modules {
+            if commit.snapshot_id(module_id).is_none()
+                || !w.environments.contains_key(module_id)
+            {
+                return Err(Error::ModuleNotFound(*module_id));
+            }
+        }
+
+        let mismatch = |module_id: &ModuleId| Error::StateMismatch {
+            module: *module_id,
+            commit: commit_id,
+        };
+        for (module_id, env) in w.environments.iter() {
+            if modules.contains(module_id) {
+                continue;
+            }
+            let snapshot_id = commit
+                .snapshot_id(module_id)
+                .ok_or_else(|| mismatch(module_id))?;
+            let instance = env.inner();
+            if instance.with_memory(|memory| instance.hash_state(memory))
+                != snapshot_id
+            {
+                return Err(mismatch(module_id));
+            }
+        }
+        for (module_id, _) in commit.snapshots() {
+            if !w.environments.contains_key(module_id) {
+                return Err(mismatch(module_id));
+            }
+        }
+
+        w.query_cache.clear();
+        for module_id in modules {
+            let snapshot_id =
+                commit.snapshot_id(module_id).expect("checked above");
+            let memory_path = MemoryPath::new(self.memory_path(module_id));
+            Snapshot::from_id(snapshot_id, &memory_path)?.load(&memory_path)?;
+
+            let instance = w.environments[module_id].inner_mut();
+            instance.clear_transient();
+            instance.set_snapshot_id(snapshot_id);
+            w.dirty.insert(*module_id);
+
+            if instance.with_memory(|memory| instance.hash_state(memory))
+                != snapshot_id
+            {
+                return Err(Error::CorruptSnapshot {
+                    module: *module_id,
+                    commit: commit_id,
+                });
+            }
+        }
+        w.event_log.clear();
+        w.history.clear();
+        Ok(())
+    }
+
+    /// Pin the given commit, protecting it from being discarded.
+    ///
+    /// Any commit made using the same storage path can be pinned, including
+    /// ones made before the world was opened. Pins are saved to the storage
+    /// path right away, and survive the world being dropped.
+    pub fn pin(&mut self, commit_id: CommitId) -> Result<(), Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        if !w.storage_lock.is_exclusive() {
+            return Err(Error::StorageLocked(w.storage_path.clone()));
+        }
+        if !w.commits.contains_key(&commit_id) {
+            return Err(Error::CommitNotFound(commit_id));
+        }
+
+        if w.pins.pin(commit_id) {
+            w.pins.save(&self.pins_path())?;
+        }
+        Ok(())
+    }
+
+    /// Unpin the given commit, letting it be discarded.
+    pub fn unpin(&mut self, commit_id: CommitId) -> Result<(), Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        if !w.storage_lock.is_exclusive() {
+            return Err(Error::StorageLocked(w.storage_path.clone()));
+        }
+
+        if w.pins.unpin(&commit_id) {
+            w.pins.save(&self.pins_path())?;
+        }
+        Ok(())
+    }
+
+    /// Return the pinned commits, ordered by id.
+    pub fn pinned(&self) -> Vec<CommitId> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        w.pins.commits().copied().collect()
+    }
+
+    /// Remove the snapshot files in the storage path that are referenced
+    /// neither by a commit in the commit index, nor by the current state of a
+    /// module, returning their paths.
+    ///
+    /// Such files are left behind by processes that crashed between
+    /// snapshotting a module and recording the commit. With `dry_run` the
+    /// files are only reported, and left in place.
+    ///
+    /// The commit index on disk lists the snapshots of every commit ever made
+    /// using the storage path, so commits made before the world was opened
+    /// keep their snapshots. Pruning is refused with [`Error::CommitNotFound`]
+    /// if a pinned commit is not in the index, and with
+    /// [`Error::PersistenceError`] if there is no index, since the snapshots
+    /// still referenced can't be known then.
+    pub fn prune_sessions(
+        &mut self,
+        dry_run: bool,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        if !w.storage_lock.is_exclusive() {
+            return Err(Error::StorageLocked(w.storage_path.clone()));
+        }
+
+        let commits_path = self.commits_path();
+        if !commits_path.exists() {
+            return Err(PersistenceError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "no commit index to prune against",
+            )));
+        }
+        let commits = index::read(&commits_path)?;
+
+        if let Some(commit_id) =
+            w.pins.commits().find(|id| !commits.contains_key(id))
+        {
+            return Err(Error::CommitNotFound(*commit_id));
+        }
+
+        let current = w.environments.iter().filter_map(|(module_id, env)| {
+            env.inner()
+                .snapshot_id()
+                .map(|snapshot_id| (*module_id, *snapshot_id))
+        });
+        let referenced: BTreeSet<_> = commits
+            .values()
+            .flat_map(|commit| {
+                commit
+                    .snapshots()
+                    .map(|(module_id, snapshot_id)| (*module_id, *snapshot_id))
+            })
+            .chain(current)
+            .map(|(module_id, snapshot_id)| {
+                combine_module_snapshot_names(
+                    module_id_to_name(module_id),
+                    snapshot_id_to_name(snapshot_id),
+                )
+            })
+            .collect();
+
+        let orphans = prune::orphans(&w.storage_path, &referenced)?;
+        if !dry_run {
+            for path in &orphans {
+                std::fs::remove_file(path).map_err(PersistenceError)?;
+            }
+        }
+
+        Ok(orphans)
+    }
+
+    /// Return the modules on the call stack, from the one the initiating call
+    /// was made to, to the one currently executing.
+    ///
+    /// This is meant to be called while a call is running, for instance from
+    /// a [`NativeQuery`], to find out which module is making the query.
+    /// Between calls it reports the stack as the last call left it.
+    pub fn current_call_stack(&self) -> Vec<ModuleId> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        w.call_stack.module_ids()
+    }
+
+    /// Return false if the given module surely emitted no events in the
+    /// transactions of the given commit, allowing it to be skipped when
+    /// looking for its events.
+    ///
+    /// A return of true means the module may have emitted events.
+    pub fn maybe_has_events(
+        &self,
+        commit_id: CommitId,
+        module_id: ModuleId,
+    ) -> Result<bool, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        let commit = w
+            .commits
+            .get(&commit_id)
+            .ok_or(Error::CommitNotFound(commit_id))?;
+
+        Ok(commit.events().may_contain(&module_id))
+    }
+
+    /// Return the events emitted by the transactions of each commit in the
+    /// given range, in the order they were emitted.
+    ///
+    /// Commits are numbered from zero in the order they were made, across
+    /// all sessions using the same storage path. Events emitted by queries
+    /// are not included, and neither are the ones of transactions discarded
+    /// by a [`restore`](Self::restore).
+    pub fn replay_events<R>(
+        &self,
+        commit_range: R,
+    ) -> Result<Vec<(CommitId, Vec<Event>)>, Error>
+    where
+        R: RangeBounds<usize>,
+    {
+        log::read(&self.event_log_path(), commit_range)
+    }
+
+    /// Return the transactions performed on the given module, in the order
+    /// they were performed, as recorded while the call history was enabled.
+    ///
+    /// Only persisted transactions are part of the history.
+    pub fn call_history(
+        &self,
+        module_id: ModuleId,
+    ) -> Result<Vec<CallRecord>, Error> {
+        history::read(&self.history_path(), module_id)
+    }
+
+    fn history_path(&self) -> PathBuf {
+        self.storage_path().join("history")
+    }
+
+    fn event_log_path(&self) -> PathBuf {
+        self.storage_path().join("events")
+    }
+
+    fn schedule_path(&self) -> PathBuf {
+        self.storage_path().join("schedule")
+    }
+
+    fn subscriptions_path(&self) -> PathBuf {
+        self.storage_path().join("subscriptions")
+    }
+
+    fn metadata_path(&self) -> PathBuf {
+        self.storage_path().join("metadata")
+    }
+
+    fn pins_path(&self) -> PathBuf {
+        self.storage_path().join("pins")
+    }
+
+    fn commits_path(&self) -> PathBuf {
+        self.storage_path().join("commits")
+    }
+
+    #[cfg(feature = "tx")]
+    fn nonces_path(&self) -> PathBuf {
+        self.storage_path().join("nonces")
+    }
+
+    pub fn memory_path(&self, module_id: &ModuleId) -> PathBuf {
+        self.storage_path().join(module_id_to_name(*module_id))
+    }
+
+    pub fn deploy(&mut self, bytecode: &[u8]) -> Result<ModuleId, Error> {
+        self.deploy_module(bytecode)
+            .map(|(receipt, _)| receipt.module_id())
+    }
+
+    /// Deploy a module, returning how long it took to compile and
+    /// instantiate, and the size of the compiled module.
+    ///
+    /// The size is found by serializing the compiled module once it is
+    /// deployed, which [`deploy`](Self::deploy) doesn't do.
+    pub fn deploy_with_receipt(
+        &mut self,
+        bytecode: &[u8],
+    ) -> Result<DeployReceipt, Error> {
+        let (mut receipt, module) = self.deploy_module(bytecode)?;
+        receipt.artifact_size =
+            module.serialize().ok().map(|artifact| artifact.len());
+        Ok(receipt)
+    }
+
+    /// Deploy a module, returning its receipt, without the size of the
+    /// compiled module, and the compiled module.
+    fn deploy_module(
+        &mut self,
+        bytecode: &[u8],
+    ) -> Result<(DeployReceipt, wasmer::Module), Error> {
+        let id_bytes: [u8; MODULE_ID_BYTES] = blake3::hash(bytecode).into();
+        let id = ModuleId::from(id_bytes);
+
+        // Hooks are taken out of the world while the module is deployed, so
+        // that they can use it.
+        let mut hooks = {
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+            mem::take(&mut w.deploy_hooks)
+        };
+
+        let result = self.deploy_with_hooks(id, bytecode, &hooks);
+
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+        hooks.append(&mut w.deploy_hooks);
+        w.deploy_hooks = hooks;
+
+        let (env, receipt, module) = result?;
+
+        let stack_region = env.inner().layout().stack_region;
+        let metadata = w.metadata.module(id, stack_region);
+        env.inner_mut().set_snapshot_policy(metadata.policy.clone());
+        env.inner_mut().set_gas_multiplier(metadata.gas_multiplier);
+
+        w.query_cache.clear();
+        w.insert(id, env);
+        w.dirty.insert(id);
+
+        Ok((receipt, module))
+    }
+
+    /// Remove the given module from the world, unmapping its memory and
+    /// closing its files.
+    ///
+    /// The module can't be called anymore, and is left out of the commits
+    /// made from now on. Its snapshots in earlier commits are kept.
+    pub fn release(&mut self, module_id: ModuleId) -> Result<(), Error> {
+        let env = {
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+
+            let env = w
+                .environments
+                .remove(&module_id)
+                .ok_or(Error::ModuleNotFound(module_id))?;
+
+            w.query_cache.clear();
+            w.dirty.remove(&module_id);
+            w.pure_states.remove(&module_id);
+            w.state.remove(&module_id);
+
+            env
+        };
+
+        env.close();
+
+        Ok(())
+    }
+
+    /// Deploy a module with its memory seeded from the given image, such as
+    /// one returned by [`export_module_state`](Self::export_module_state).
+    ///
+    /// The image must hash to `memory_hash`, otherwise the deploy fails with
+    /// [`Error::MemoryMismatch`] before the module is compiled.
+    pub fn deploy_with_memory(
+        &mut self,
+        bytecode: &[u8],
+        memory: &[u8],
+        memory_hash: [u8; 32],
+    ) -> Result<ModuleId, Error> {
+        let id_bytes: [u8; MODULE_ID_BYTES] = blake3::hash(bytecode).into();
+        let id = ModuleId::from(id_bytes);
+
+        if *blake3::hash(memory).as_bytes() != memory_hash {
+            return Err(Error::MemoryMismatch(id));
+        }
+
+        self.deploy(bytecode)?;
+
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.environments[&id].inner().load_memory(memory)?;
+        w.query_cache.clear();
+
+        Ok(id)
+    }
+
+    /// Transform the memory of the given module outside of wasm execution,
+    /// such as when moving between protocol versions, and persist the world
+    /// with the result.
+    ///
+    /// The closure is given the memory of the module as of the last call,
+    /// and a builder starting as a copy of it. If it succeeds, the memory of
+    /// the module is replaced by the one built, and the commit made by
+    /// [`persist`](Self::persist) is returned. As with any persist, the
+    /// changes to other modules since the last one are committed too.
+    pub fn migrate<F>(
+        &mut self,
+        module_id: ModuleId,
+        f: F,
+    ) -> Result<CommitId, Error>
+    where
+        F: FnOnce(&ModuleState, &mut ModuleStateBuilder) -> Result<(), Error>,
+    {
+        {
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+
+            if !w.storage_lock.is_exclusive() {
+                return Err(Error::StorageLocked(w.storage_path.clone()));
+            }
+
+            let instance = w
+                .environments
+                .get(&module_id)
+                .ok_or(Error::ModuleNotFound(module_id))?
+                .inner();
+            let memory = instance.with_memory(|memory| memory.to_vec());
+
+            let mut builder = ModuleStateBuilder::new(memory.clone());
+            f(&ModuleState::new(module_id, &memory), &mut builder)?;
+
+            instance.load_memory(&builder.into_memory())?;
+            w.query_cache.clear();
+            w.dirty.insert(module_id);
+        }
+
+        self.persist()
+    }
+
+    fn deploy_with_hooks(
+        &self,
+        id: ModuleId,
+        bytecode: &[u8],
+        hooks: &DeployHooks,
+    ) -> Result<(Env, DeployReceipt, wasmer::Module), Error> {
+        let (features, float_policy, coverage, limit) = {
+            let guard = self.0.lock();
+            let w = unsafe { &*guard.get() };
+            (w.features, w.float_policy, w.coverage, w.limit())
+        };
+
+        hooks.pre_deploy(id, bytecode)?;
+        float_policy.check(bytecode)?;
+
+        let store = new_store(
+            self.storage_path().join(module_id_to_name(id)).as_path(),
+            features,
+            coverage,
+        );
+        let start = Instant::now();
+        let module = wasmer::Module::new(&store, bytecode)?;
+        let compile_time = start.elapsed();
+
+        let mut env = Env::uninitialized();
+
+        let imports = imports! {
+            "env" => {
+                "alloc" => Function::new_native_with_env(&store, env.clone(), host_alloc),
+                "dealloc" => Function::new_native_with_env(&store, env.clone(), host_dealloc),
+                "host_oom" => Function::new_native_with_env(&store, env.clone(), host_oom),
+
+                "snap" => Function::new_native_with_env(&store, env.clone(), host_snapshot),
+
+                "q" => Function::new_native_with_env(&store, env.clone(), host_query),
+                "nq" => Function::new_native_with_env(&store, env.clone(), host_native_query),
+                "t" => Function::new_native_with_env(&store, env.clone(), host_transact),
+                "qs" => Function::new_native_with_env(&store, env.clone(), host_query_selector),
+                "ts" => Function::new_native_with_env(&store, env.clone(), host_transact_selector),
+                "tq" => Function::new_native_with_env(&store, env.clone(), host_try_query),
+                "tt" => Function::new_native_with_env(&store, env.clone(), host_try_transact),
+                "revert" => Function::new_native_with_env(&store, env.clone(), host_revert),
+
+                "height" => Function::new_native_with_env(&store, env.clone(), host_height),
+                "timestamp" => Function::new_native_with_env(&store, env.clone(), host_timestamp),
+                "random" => Function::new_native_with_env(&store, env.clone(), host_random),
+                "heap_stats" => Function::new_native_with_env(&store, env.clone(), host_heap_stats),
+                "memory_pressure" => Function::new_native_with_env(&store, env.clone(), host_memory_pressure),
+                "memory_pages" => Function::new_native_with_env(&store, env.clone(), host_memory_pages),
+                "code_hash" => Function::new_native_with_env(&store, env.clone(), host_code_hash),
+                "commit_id" => Function::new_native_with_env(&store, env.clone(), host_commit_id),
+                "host_debug" => Function::new_native_with_env(&store, env.clone(), host_debug),
+                "host_panic" => Function::new_native_with_env(&store, env.clone(), host_panic),
+                "emit" => Function::new_native_with_env(&store, env.clone(), host_emit),
+                "defer" => Function::new_native_with_env(&store, env.clone(), host_defer),
+                "subscribe" => Function::new_native_with_env(&store, env.clone(), host_subscribe),
+                "unsubscribe" => Function::new_native_with_env(&store, env.clone(), host_unsubscribe),
+                "caller" => Function::new_native_with_env(&store, env.clone(), host_caller),
+                "entry_module" => Function::new_native_with_env(&store, env.clone(), host_entry_module),
+                "tx_meta" => Function::new_native_with_env(&store, env.clone(), host_tx_meta),
+                "limit" => Function::new_native_with_env(&store, env.clone(), host_limit),
+                "spent" => Function::new_native_with_env(&store, env.clone(), host_spent),
+                "tx_limit" => Function::new_native_with_env(&store, env.clone(), host_tx_limit),
+                "tx_spent" => Function::new_native_with_env(&store, env.clone(), host_tx_spent),
+            }
+        };
+
+        let start = Instant::now();
+        let instance = wasmer::Instance::new(&module, &imports)?;
+
+        let arg_buf_ofs = global_i32(&instance.exports, "A")?;
+
+        // TODO: We should check these buffers have the correct length.
+        let self_id_ofs = global_i32(&instance.exports, "SELF_ID")?;
+
+        let heap_base = global_i32(&instance.exports, "__heap_base")?;
+
+        // archived arguments are accessed in place, so they must be copied
+        // to an aligned buffer
+        if !(arg_buf_ofs as usize).is_multiple_of(dallo::ARGBUF_ALIGN) {
+            return Err(Error::MisalignedArgBuffer(id));
+        }
+
+        // We need to read the actual value of AL from the offset into memory
+
+        let pure_methods = pure_methods(&instance)?;
+        let selectors = selectors(&instance);
+
+        let instance = Instance::new(
+            id,
+            blake3::hash(bytecode).into(),
+            instance,
+            World(self.0.clone(), Handle::Internal),
+            MemHandler::new(heap_base as usize),
+            arg_buf_ofs,
+            heap_base,
+            self_id_ofs,
+            pure_methods,
+            selectors,
+        );
+        instance.write_self_id(id)?;
+
+        env.initialize(instance);
+        env.inner_mut().load_abi(limit)?;
+        let instantiate_time = start.elapsed();
+
+        hooks.post_deploy(id, bytecode)?;
+
+        let receipt = DeployReceipt {
+            module_id: id,
+            compile_time,
+            instantiate_time,
+            artifact_size: None,
+            validated_features: features,
+        };
+
+        Ok((env, receipt, module))
+    }
+
+    /// Register a hook called with the id and bytecode of every module
+    /// deployed from now on, before it is compiled. Returning an error
+    /// rejects the module, failing the deploy with
+    /// [`DeployRejected`](Error::DeployRejected).
+    pub fn on_pre_deploy<H>(&mut self, hook: H)
+    where
+        H: 'static + DeployHook + Send,
+    {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.deploy_hooks.push_pre(hook);
+    }
+
+    /// Register a hook called with the id and bytecode of every module
+    /// deployed from now on, once it is compiled and instantiated but before
+    /// it is added to the world. Returning an error rejects the module,
+    /// failing the deploy with [`DeployRejected`](Error::DeployRejected).
+    pub fn on_post_deploy<H>(&mut self, hook: H)
+    where
+        H: 'static + DeployHook + Send,
+    {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.deploy_hooks.push_post(hook);
+    }
+
+    /// Registers a [`NativeQuery`] with the given `name`.
+    pub fn register_native_query<Q>(&mut self, name: &'static str, query: Q)
+    where
+        Q: 'static + NativeQuery,
+    {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.query_cache.clear();
+        w.native_queries.insert(name, query);
+    }
+
+    /// Registers `E` as a type modules revert with, so that the errors of the
+    /// methods declaring it in their ABI are rendered in [`ModuleError`]s.
+    ///
+    /// Types are matched by name, as with the types of arguments.
+    pub fn register_error_type<E>(&mut self)
+    where
+        E: Archive + core::fmt::Debug,
+        E::Archived: Deserialize<E, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.error_types.insert::<E>();
+    }
+
+    /// Registers a [`SystemModule`] under the given id, which must be one of
+    /// the ids reserved using [`ModuleId::reserved`].
+    ///
+    /// Modules can then query it as if it were deployed.
+    pub fn register_system_module<M>(
+        &mut self,
+        module_id: ModuleId,
+        module: M,
+    ) -> Result<(), Error>
+    where
+        M: 'static + SystemModule,
+    {
+        if !module_id.is_reserved() {
+            return Err(Error::UnreservedModuleId(module_id));
+        }
+
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.query_cache.clear();
+        w.system_modules.insert(module_id, module);
+
+        Ok(())
+    }
+
+    /// Limit the number of events, and their cumulative size in bytes, a single
+    /// call may emit. Exceeding either makes the call fail with
+    /// [`Error::EmitLimit`].
+    pub fn set_emit_limit(&mut self, count: usize, bytes: usize) {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.emit_limit = OutputLimit { count, bytes };
+    }
+
+    /// Limit the number of debug messages, and their cumulative size in bytes,
+    /// a single call may output. Exceeding either makes the call fail with
+    /// [`Error::DebugLimit`].
+    pub fn set_debug_limit(&mut self, count: usize, bytes: usize) {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.debug_limit = OutputLimit { count, bytes };
+    }
+
+    /// Set the share of its heap, in percent, past which a module is under
+    /// memory pressure, as reported to it by `dallo::memory_pressure`.
+    ///
+    /// Modules can check for pressure to shed caches before their
+    /// allocations start failing. No module is under pressure unless a limit
+    /// is set.
+    ///
+    /// # Panics
+    /// If the percentage is zero or over a hundred.
+    pub fn set_soft_memory_limit(&mut self, percent: u64) {
+        assert!(
+            (1..=100).contains(&percent),
+            "soft memory limit must be between 1 and 100 percent"
+        );
+
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.query_cache.clear();
+        w.soft_memory_limit = Some(percent);
+    }
+
+    /// Stop reporting memory pressure to modules.
+    pub fn clear_soft_memory_limit(&mut self) {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.query_cache.clear();
+        w.soft_memory_limit = None;
+    }
+
+    /// Enable or disable caching of query results.
+    ///
+    /// When enabled, repeating a query with the same module, method and
+    /// argument on the same state returns the previous result without
+    /// executing the module. The state is identified by its root, so every
+    /// query hashes the modules taking part in calls since the root was last
+    /// computed. The cache is also cleared by any transaction, deploy,
+    /// restore, height or timestamp change.
+    ///
+    /// Queries leave their argument and return in the memory of the module,
+    /// which is part of its state, so the first repetition of a query is made
+    /// on a different state than the query itself and executes the module.
+    pub fn set_query_cache(&mut self, enabled: bool) {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.query_cache.set_enabled(enabled);
+    }
+
+    /// Enable or disable memoization of the results of [`NativeQuery`]s.
+    ///
+    /// When enabled, a native query performed again with the same name and
+    /// argument during the same query or transaction returns the same result
+    /// without calling the host, making repeated answers deterministic even
+    /// if the host's answer changes in the meantime.
+    pub fn set_native_query_memoization(&mut self, enabled: bool) {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.native_queries.set_memoize(enabled);
+    }
+
+    pub fn query<Arg, Ret>(
+        &self,
+        m_id: ModuleId,
+        name: &str,
+        arg: Arg,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        Arg: AbiType + for<'a> Serialize<StandardBufSerializer<'a>>,
+        Ret: Archive,
+        Ret::Archived: Deserialize<Ret, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        self.query_with(
+            m_id,
+            name,
+            |instance| {
+                instance.check_argument::<Arg>(name)?;
+                instance.write_to_arg_buffer(arg)
+            },
+            Instance::read_return,
+        )
+    }
+
+    /// Perform a query whose argument and return types aren't known, such as
+    /// one received over the network.
+    ///
+    /// Since the type of the argument isn't known, it is not checked against
+    /// the ABI declared by the module.
+    pub fn query_raw(
+        &self,
+        m_id: ModuleId,
+        raw: RawQuery,
+    ) -> Result<Receipt<RawResult>, Error> {
+        self.query_with(
+            m_id,
+            raw.name(),
+            |instance| instance.write_raw_argument(raw.arg_bytes()),
+            Instance::read_raw_return,
+        )
+    }
+
+    /// Perform a query without validating the data returned by the module.
+    ///
+    /// This skips the cost of validation, which can be significant for large
+    /// returns, when replaying calls on a set of trusted modules.
+    ///
+    /// # Safety
+    /// The module must return a valid archived `Ret`. Invalid data results in
+    /// undefined behavior.
+    pub unsafe fn query_unchecked<Arg, Ret>(
+        &self,
+        m_id: ModuleId,
+        name: &str,
+        arg: Arg,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        Arg: AbiType + for<'a> Serialize<StandardBufSerializer<'a>>,
+        Ret: Archive,
+        Ret::Archived: Deserialize<Ret, Infallible>,
+    {
+        self.query_with(
+            m_id,
+            name,
+            |instance| {
+                instance.check_argument::<Arg>(name)?;
+                instance.write_to_arg_buffer(arg)
+            },
+            |instance, name, ret_len| {
+                instance.read_return_unchecked(name, ret_len)
+            },
+        )
+    }
+
+    fn query_with<Ret, W, F>(
+        &self,
+        m_id: ModuleId,
+        name: &str,
+        write_arg: W,
+        read_return: F,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        W: FnOnce(&Instance) -> Result<u32, Error>,
+        F: Fn(&Instance, &str, u32) -> Result<Ret, Error>,
+    {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.events.clear();
+        w.debug.clear();
+        w.reverts.clear();
+        w.deferred.clear();
+        w.native_queries.clear_memo();
+        w.tx_meta.clear();
+
+        // the root of the state the query is made on, before its argument is
+        // written to the memory of the module
+        let root = w.query_cache.is_enabled().then(|| w.state_root());
+
+        let instance = w
+            .environments
+            .get(&m_id)
+            .ok_or(Error::ModuleNotFound(m_id))?
+            .inner();
+        instance.set_remaining_points(w.query_limit());
+
+        let pure = instance.is_pure(name);
+
+        let arg_len = write_arg(instance)?;
+        let key = root.map(|root| {
+            instance.with_arg_buffer(|buf| {
+                QueryCache::key(&root, &m_id, name, &buf[..arg_len as usize])
+            })
+        });
+
+        if let Some(cached) =
+            key.as_ref().and_then(|key| w.query_cache.get(key))
+        {
+            if cached.spent <= w.query_limit() {
+                w.dirty.insert(m_id);
+
+                let ret_len = cached.ret.len();
+                instance.with_arg_buffer(|buf| {
+                    buf[..ret_len].copy_from_slice(&cached.ret)
+                });
+                let ret = read_return(instance, name, ret_len as u32)?;
+
+                return Ok(Receipt::new(
+                    ret,
+                    cached.events.clone(),
+                    cached.debug.clone(),
+                    cached.spent,
+                )
+                .with_limit(w.query_limit())
+                .with_instructions(cached.instructions)
+                .with_ret_len(ret_len as u32)
+                .with_context(w.height, w.timestamp)
+                .with_reverts(cached.reverts.clone()));
+            }
+        }
+
+        let limit = w.query_limit();
+        w.querying = true;
+        let started = w.start_call(m_id, name, arg_len, limit, pure);
+
+        let instance = w.environments[&m_id].inner();
+        let ret_len = started.and_then(|_| {
+            instance
+                .perform_query(name, arg_len)
+                .map_err(|e| map_call_err(instance, e))
+        });
+        w.untrack_accesses();
+        let left = w.leave_pure();
+
+        let instance = w.environments[&m_id].inner();
+        let ret_len = ret_len?;
+        left?;
+        let ret = read_return(instance, name, ret_len)?;
+        let remaining = instance.remaining_points();
+
+        let events = mem::take(&mut w.events);
+        let debug = mem::take(&mut w.debug);
+        let reverts = mem::take(&mut w.reverts);
+        let spent = w.query_limit() - remaining;
+        w.points_spent += spent;
+        let instructions = w.instructions.finish(&w.environments);
+        let witness = w.witness.finish();
+
+        if let Some(key) = key {
+            let ret_bytes = instance
+                .with_arg_buffer(|buf| buf[..ret_len as usize].to_vec());
+            w.query_cache.insert(
+                key,
+                CachedQuery {
+                    ret: ret_bytes,
+                    events: events.clone(),
+                    debug: debug.clone(),
+                    reverts: reverts.clone(),
+                    spent,
+                    instructions,
+                },
+            );
+        }
+
+        Ok(Receipt::new(ret, events, debug, spent)
+            .with_limit(limit)
+            .with_instructions(instructions)
+            .with_ret_len(ret_len)
+            .with_context(w.height, w.timestamp)
+            .with_witness(witness)
+            .with_reverts(reverts))
+    }
+
+    pub fn transact<Arg, Ret>(
+        &mut self,
+        m_id: ModuleId,
+        name: &str,
+        arg: Arg,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        Arg: AbiType
+            + for<'a> Serialize<StandardBufSerializer<'a>>
+            + core::fmt::Debug,
+        Ret: Archive,
+        Ret::Archived: Deserialize<Ret, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        self.transact_with_meta(m_id, name, arg, vec![])
+    }
+
+    /// Perform a transaction with the given metadata, such as the fee payer
+    /// or a memo, available to every module it calls using
+    /// `dallo::tx_meta`.
+    pub fn transact_with_meta<Arg, Ret>(
+        &mut self,
+        m_id: ModuleId,
+        name: &str,
+        arg: Arg,
+        meta: Vec<u8>,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        Arg: AbiType
+            + for<'a> Serialize<StandardBufSerializer<'a>>
+            + core::fmt::Debug,
+        Ret: Archive,
+        Ret::Archived: Deserialize<Ret, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        let limit = {
+            let guard = self.0.lock();
+            let w = unsafe { &*guard.get() };
+            w.limit()
+        };
+
+        self.transact_with_limit(m_id, name, arg, meta, limit)
+    }
+
+    /// Perform a transaction whose points are paid for by the given sponsor,
+    /// as described by its [`SponsorMode`], reducing its budget by the points
+    /// charged to it.
+    ///
+    /// The receipt records the points charged to the sponsor, including for
+    /// the transactions deferred by the call, and the ones charged to the
+    /// transaction itself. A failed transaction charges the sponsor for the
+    /// points it spent before failing, since they were spent all the same.
+    pub fn transact_sponsored<Arg, Ret>(
+        &mut self,
+        m_id: ModuleId,
+        name: &str,
+        arg: Arg,
+        sponsor: &mut Sponsor,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        Arg: AbiType
+            + for<'a> Serialize<StandardBufSerializer<'a>>
+            + core::fmt::Debug,
+        Ret: Archive,
+        Ret::Archived: Deserialize<Ret, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        let limit = {
+            let guard = self.0.lock();
+            let w = unsafe { &*guard.get() };
+            let limit = sponsor.limit(w.limit());
+            w.reset_points(m_id, limit);
+            limit
+        };
+
+        match self.transact_with_limit(m_id, name, arg, vec![], limit) {
+            Ok(receipt) => {
+                let sponsored = sponsor.charge(receipt.total_spent());
+                Ok(receipt.with_sponsor(sponsor.id().to_vec(), sponsored))
+            }
+            Err(err) => {
+                let guard = self.0.lock();
+                let w = unsafe { &*guard.get() };
+                sponsor.charge(w.spent_by(m_id, limit));
+                Err(err)
+            }
+        }
+    }
+
+    fn transact_with_limit<Arg, Ret>(
+        &mut self,
+        m_id: ModuleId,
+        name: &str,
+        arg: Arg,
+        meta: Vec<u8>,
+        limit: u64,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        Arg: AbiType
+            + for<'a> Serialize<StandardBufSerializer<'a>>
+            + core::fmt::Debug,
+        Ret: Archive,
+        Ret::Archived: Deserialize<Ret, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        if meta.len() > dallo::ARGBUF_LEN {
+            return Err(Error::ArgumentTooLarge {
+                required: meta.len(),
+                available: dallo::ARGBUF_LEN,
+            });
+        }
+
+        let w = self.0.lock();
+        let w = unsafe { &mut *w.get() };
+
+        w.query_cache.clear();
+        w.events.clear();
+        w.debug.clear();
+        w.reverts.clear();
+        w.deferred.clear();
+        w.native_queries.clear_memo();
+        w.tx_meta = meta.clone();
+
+        let instance = w.get(&m_id).ok_or(Error::ModuleNotFound(m_id))?.inner();
+        instance.check_argument::<Arg>(name)?;
+        let arg_len = instance.write_to_arg_buffer(arg)?;
+
+        let (ret_len, spent) =
+            w.call_transaction(m_id, name, arg_len, limit)?;
+
+        let instance = w.environments[&m_id].inner();
+        let ret = instance.read_return::<Ret>(name, ret_len)?;
+
+        let events = mem::take(&mut w.events);
+        let debug = mem::take(&mut w.debug);
+        let reverts = mem::take(&mut w.reverts);
+        let instructions = w.instructions.finish(&w.environments);
+        let witness = w.witness.finish();
+        w.notify_subscribers(&events);
+
+        let (deferred, failures) = self.perform_deferred(&meta, limit - spent);
+
+        let receipt = Receipt::new(ret, events, debug, spent)
+            .with_limit(limit)
+            .with_instructions(instructions)
+            .with_ret_len(ret_len)
+            .with_context(w.height, w.timestamp)
+            .with_witness(witness)
+            .with_reverts(reverts)
+            .with_deferred(deferred)
+            .with_failures(failures);
+        self.publish_events(&receipt);
+
+        Ok(receipt)
+    }
+
+    /// Perform a transaction, persisting the world if it succeeds and
+    /// restoring it to the last persisted state if it fails.
+    ///
+    /// Since a failure restores the world, any changes made since the last
+    /// persist are discarded along with the ones of the failed transaction.
+    /// Modules that were never persisted can not be restored.
+    pub fn execute<Arg, Ret>(
+        &mut self,
+        m_id: ModuleId,
+        name: &str,
+        arg: Arg,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        Arg: AbiType
+            + for<'a> Serialize<StandardBufSerializer<'a>>
+            + core::fmt::Debug,
+        Ret: Archive,
+        Ret::Archived: Deserialize<Ret, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        self.with_session(|world| world.transact(m_id, name, arg))
+    }
+
+    /// Call the given closure with the world, persisting it if the closure
+    /// succeeds, and restoring it to the last persisted state if it fails or
+    /// panics.
+    ///
+    /// As with [`execute`](Self::execute), restoring discards all changes
+    /// since the last persist.
+    pub fn with_session<F, R>(&mut self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&mut World) -> Result<R, Error>,
+    {
+        let mut guard = RestoreGuard(Some(self.clone()));
+
+        let ret = f(self)?;
+
+        guard.0 = None;
+        self.persist()?;
+
+        Ok(ret)
+    }
+
+    /// Register a callback to be called with every event passing `filter`
+    /// emitted during a transaction.
+    ///
+    /// Observers are only notified once a transaction and all the
+    /// transactions it deferred succeed, and before [`transact`] returns.
+    /// This means that events are never observed for transactions that fail,
+    /// and that all events of a transaction are observed before its effects
+    /// can be persisted. Events are observed in the order they are emitted,
+    /// and observers in the order they were registered.
+    ///
+    /// [`transact`]: World::transact
+    pub fn on_event<F, C>(&mut self, filter: F, callback: C)
+    where
+        F: 'static + Fn(&Event) -> bool + Send,
+        C: 'static + FnMut(&Event) + Send,
+    {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.observers.push(Observer::new(filter, callback));
+    }
+
+    /// Notify the observers of the events of a transaction, and record them
+    /// to be logged on the next persist.
+    fn publish_events<T>(&self, receipt: &Receipt<T>) {
+        // Observers are taken out of the world while they are called, so
+        // that they can use it.
+        let mut observers = {
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+            mem::take(&mut w.observers)
+        };
+
+        for event in receipt.all_events() {
+            for observer in &mut observers {
+                observer.observe(event);
+            }
+        }
+
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        observers.append(&mut w.observers);
+        w.observers = observers;
+        w.event_log.record(receipt.all_events());
+    }
+
+    /// Perform a raw transaction using at most `limit` points.
+    fn transact_raw_with_limit(
+        &self,
+        m_id: ModuleId,
+        raw: &RawTransaction,
+        meta: Vec<u8>,
+        limit: u64,
+    ) -> Result<Receipt<RawResult>, Error> {
+        let w = self.0.lock();
+        let w = unsafe { &mut *w.get() };
+
+        w.query_cache.clear();
+        w.events.clear();
+        w.debug.clear();
+        w.reverts.clear();
+        w.deferred.clear();
+        w.native_queries.clear_memo();
+        w.tx_meta = meta;
+
+        let instance = w.get(&m_id).ok_or(Error::ModuleNotFound(m_id))?.inner();
+        let arg_len = instance.write_raw_argument(raw.arg_bytes())?;
+
+        let (ret_len, spent) =
+            w.call_transaction(m_id, raw.name(), arg_len, limit)?;
+
+        let instance = w.environments[&m_id].inner();
+        let ret = instance.read_raw_return(raw.name(), ret_len)?;
+
+        let events = mem::take(&mut w.events);
+        let debug = mem::take(&mut w.debug);
+        let reverts = mem::take(&mut w.reverts);
+        let instructions = w.instructions.finish(&w.environments);
+        let witness = w.witness.finish();
+        w.notify_subscribers(&events);
+
+        Ok(Receipt::new(ret, events, debug, spent)
+            .with_limit(limit)
+            .with_instructions(instructions)
+            .with_ret_len(ret_len)
+            .with_context(w.height, w.timestamp)
+            .with_witness(witness)
+            .with_reverts(reverts))
+    }
+
+    /// Perform a transaction whose argument and return types aren't known,
+    /// such as one received over the network, together with the
+    /// transactions it defers.
+    ///
+    /// Since the type of the argument isn't known, it is not checked against
+    /// the ABI declared by the module.
+    pub fn transact_raw(
+        &mut self,
+        m_id: ModuleId,
+        raw: RawTransaction,
+    ) -> Result<Receipt<RawResult>, Error> {
+        let limit = {
+            let guard = self.0.lock();
+            let w = unsafe { &*guard.get() };
+            w.limit()
+        };
+
+        self.perform_raw(m_id, &raw, limit)
+    }
+
+    /// Schedule a raw transaction to be performed on the given module once
+    /// the given height is reached.
+    ///
+    /// The schedule is saved on [`persist`] and reloaded on [`restore`].
+    ///
+    /// [`persist`]: World::persist
+    /// [`restore`]: World::restore
+    pub fn schedule(
+        &mut self,
+        height: u64,
+        m_id: ModuleId,
+        raw: RawTransaction,
+    ) {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.schedule.insert(height, m_id, raw);
+    }
+
+    /// Perform all transactions scheduled at or below the given height, in
+    /// the order of their heights, removing them from the schedule.
+    ///
+    /// A failing transaction does not prevent the others from being
+    /// performed, so a result is returned for each of them.
+    pub fn run_due(
+        &mut self,
+        height: u64,
+    ) -> Vec<Result<Receipt<RawResult>, Error>> {
+        let (due, limit) = {
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+            (w.schedule.take_due(height), w.limit())
+        };
+
+        due.into_iter()
+            .map(|(m_id, raw)| self.perform_raw(m_id, &raw, limit))
+            .collect()
+    }
+
+    /// Perform a batch of raw transactions in order, atomically: if any of
+    /// them fails, the world is rolled back to the state it had before the
+    /// batch, and the error is returned.
+    ///
+    /// The events of the transactions are only published once all of them
+    /// succeeded. Memories grown by a failed batch keep their size, since
+    /// memories can't shrink, but the pages grown are zeroed.
+    pub fn transact_batch(
+        &mut self,
+        txs: &[(ModuleId, RawTransaction)],
+    ) -> Result<Vec<Receipt<RawResult>>, Error> {
+        let (savepoint, limit) = {
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+            (Savepoint::capture(w), w.limit())
+        };
+
+        let receipts = txs
+            .iter()
+            .map(|(m_id, raw)| self.perform_raw_unpublished(*m_id, raw, limit))
+            .collect::<Result<Vec<_>, _>>();
+
+        let receipts = {
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+
+            match receipts {
+                Ok(receipts) => {
+                    savepoint.release(w);
+                    receipts
+                }
+                Err(err) => {
+                    savepoint.restore(w);
+                    return Err(err);
+                }
+            }
+        };
+
+        for receipt in &receipts {
+            self.publish_events(receipt);
+        }
+
+        Ok(receipts)
+    }
+
+    /// Set the signature scheme used to verify signed transactions.
+    #[cfg(feature = "tx")]
+    pub fn set_signature_verifier<V>(&mut self, verifier: V)
+    where
+        V: 'static + SignatureVerifier,
+    {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.tx.set_verifier(verifier);
+    }
+
+    /// Return the nonce the next transaction of the given signer must have.
+    #[cfg(feature = "tx")]
+    pub fn nonce(&self, signer: &[u8]) -> u64 {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        w.tx.nonce(signer)
+    }
+
+    /// Verify the signature and nonce of a transaction and perform it, using
+    /// at most the limit it was signed with.
+    ///
+    /// The nonce is consumed once the transaction is verified, even if it
+    /// then fails. Without a verifier set using [`set_signature_verifier`]
+    /// all signatures are considered invalid.
+    ///
+    /// [`set_signature_verifier`]: World::set_signature_verifier
+    #[cfg(feature = "tx")]
+    pub fn execute_signed(
+        &mut self,
+        tx: &SignedTransaction,
+    ) -> Result<Receipt<RawResult>, Error> {
+        {
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+
+            w.tx.verify(tx)?;
+        }
+
+        let payload = &tx.payload;
+        self.perform_raw(payload.module_id, &payload.raw, payload.limit)
+    }
+
+    /// Perform a transaction on behalf of the given identity, failing with
+    /// [`InvalidNonce`](Error::InvalidNonce) if the nonce is not the one the
+    /// next transaction of the identity must have.
+    ///
+    /// Identities share the nonces of the signers of
+    /// [`execute_signed`](Self::execute_signed). The nonce is consumed even
+    /// if the transaction then fails, and is persisted with the world.
+    #[cfg(feature = "tx")]
+    pub fn transact_with_nonce<Arg, Ret>(
+        &mut self,
+        identity: &[u8],
+        nonce: u64,
+        m_id: ModuleId,
+        name: &str,
+        arg: Arg,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        Arg: AbiType
+            + for<'a> Serialize<StandardBufSerializer<'a>>
+            + core::fmt::Debug,
+        Ret: Archive,
+        Ret::Archived: Deserialize<Ret, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        {
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+
+            w.tx.consume_nonce(identity, nonce)?;
+        }
+
+        self.transact(m_id, name, arg)
+    }
+
+    /// Perform a raw transaction together with the transactions it defers,
+    /// notifying the observers on success.
+    fn perform_raw(
+        &self,
+        m_id: ModuleId,
+        raw: &RawTransaction,
+        limit: u64,
+    ) -> Result<Receipt<RawResult>, Error> {
+        let receipt = self.perform_raw_unpublished(m_id, raw, limit)?;
+        self.publish_events(&receipt);
+
+        Ok(receipt)
+    }
+
+    /// Perform a raw transaction together with the transactions it defers,
+    /// leaving its events to be published by the caller.
+    fn perform_raw_unpublished(
+        &self,
+        m_id: ModuleId,
+        raw: &RawTransaction,
+        limit: u64,
+    ) -> Result<Receipt<RawResult>, Error> {
+        let receipt = self.transact_raw_with_limit(m_id, raw, vec![], limit)?;
+        let (deferred, failures) =
+            self.perform_deferred(&[], limit - receipt.spent());
+
+        Ok(receipt.with_deferred(deferred).with_failures(failures))
+    }
+
+    /// Perform the transactions deferred during a call, and the ones they
+    /// defer in turn, in the order they were deferred, sharing the given
+    /// amount of points. They are performed with the metadata of the call
+    /// that deferred them.
+    ///
+    /// Each transaction is performed atomically: if it fails, its changes and
+    /// the transactions it deferred are rolled back, and its failure is
+    /// recorded instead of failing the call that deferred it.
+    fn perform_deferred(
+        &self,
+        meta: &[u8],
+        mut remaining: u64,
+    ) -> (Vec<Receipt<RawResult>>, Vec<CallFailure>) {
+        let mut receipts = vec![];
+        let mut failures = vec![];
+
+        let mut queue = {
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+            VecDeque::from(mem::take(&mut w.deferred))
+        };
+
+        while let Some((m_id, raw)) = queue.pop_front() {
+            let savepoint = {
+                let guard = self.0.lock();
+                let w = unsafe { &mut *guard.get() };
+                w.reset_points(m_id, remaining);
+                Savepoint::capture(w)
+            };
+
+            let result = self.transact_raw_with_limit(
+                m_id,
+                &raw,
+                meta.to_vec(),
+                remaining,
+            );
+
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+
+            match result {
+                Ok(receipt) => {
+                    savepoint.release(w);
+                    queue.extend(mem::take(&mut w.deferred));
+                    remaining -= receipt.spent();
+                    receipts.push(receipt);
+                }
+                Err(err) => {
+                    let spent = w.spent_by(m_id, remaining);
+                    savepoint.restore(w);
+                    w.points_spent += spent;
+                    remaining -= spent;
+
+                    let method = String::from(raw.name());
+                    failures.push(CallFailure::new(m_id, method, &err, spent));
+                }
+            }
+        }
+
+        (receipts, failures)
+    }
+
+    /// Set the height available to modules.
+    pub fn set_height(&mut self, height: u64) {
+        let w = self.0.lock();
+        let w = unsafe { &mut *w.get() };
+
+        w.query_cache.clear();
+        w.height = height;
+    }
+
+    /// Set the WebAssembly proposals modules deployed from now on are allowed
+    /// to use. Modules using any other proposal fail to deploy.
+    pub fn set_wasm_features(&mut self, features: WasmFeatures) {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.features = features;
+    }
+
+    /// Set the percentage of the points spent by the given module that are
+    /// charged, such as 50 for a discounted module or 200 for a surcharged
+    /// one. Modules are charged in full by default.
+    ///
+    /// The multiplier is applied to the points passed into the frames of the
+    /// module, so the points reported by receipts and to modules all include
+    /// it, while the instructions executed are unaffected.
+    ///
+    /// The multiplier is persisted with the world, and applied again when the
+    /// module is deployed after opening the world from its storage path.
+    ///
+    /// # Panics
+    /// If the percentage is zero.
+    pub fn set_gas_multiplier(
+        &mut self,
+        module_id: ModuleId,
+        percent: u64,
+    ) -> Result<(), Error> {
+        assert!(percent > 0, "gas multiplier must not be zero");
+
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        let env = w
+            .environments
+            .get(&module_id)
+            .ok_or(Error::ModuleNotFound(module_id))?;
+        env.inner_mut().set_gas_multiplier(percent);
+        w.metadata.set_gas_multiplier(module_id, percent);
+        w.query_cache.clear();
+
+        Ok(())
+    }
+
+    /// Advise the operating system on how to page in the memory of the given
+    /// module, for instance to keep the memories of the modules called most
+    /// in RAM, or to drop the ones of modules that won't be called for a
+    /// while.
+    ///
+    /// Fails with [`PersistenceError`](Error::PersistenceError) if the
+    /// advice can't be applied, such as when locking more memory than the
+    /// process is allowed to.
+    pub fn set_memory_advice(
+        &mut self,
+        module_id: ModuleId,
+        advice: MemoryAdvice,
+    ) -> Result<(), Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        let env = w
+            .environments
+            .get(&module_id)
+            .ok_or(Error::ModuleNotFound(module_id))?;
+        env.inner_mut().set_memory_advice(advice)
+    }
+
+    /// Set which regions of the memory of the given module make up its
+    /// state, as described by [`SnapshotPolicy`].
+    ///
+    /// The policy is persisted with the world, and applied again when the
+    /// module is deployed after opening the world from its storage path.
+    pub fn set_snapshot_policy(
+        &mut self,
+        module_id: ModuleId,
+        policy: SnapshotPolicy,
+    ) -> Result<(), Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        let env = w
+            .environments
+            .get(&module_id)
+            .ok_or(Error::ModuleNotFound(module_id))?;
+        env.inner_mut().set_snapshot_policy(policy.clone());
+        w.metadata.set_policy(module_id, policy);
+        w.dirty.insert(module_id);
+
+        Ok(())
+    }
+
+    /// Set whether modules deployed from now on are instrumented to record
+    /// which of their functions are called, retrieved using
+    /// [`coverage`](Self::coverage).
+    pub fn set_coverage(&mut self, coverage: bool) {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.coverage = coverage;
+    }
+
+    /// Set whether modules deployed from now on are allowed to use floating
+    /// point operations.
+    pub fn set_float_policy(&mut self, policy: FloatPolicy) {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.float_policy = policy;
+    }
+
+    /// Enable or disable the recording of a [`Witness`] for each call,
+    /// returned in its receipt.
+    ///
+    /// Witnesses revoke access to the memory of every module taking part in a
+    /// call, copying each page as it is first accessed, and hash the state
+    /// changed since the last call to compute its root, so they are disabled
+    /// by default. A call whose modules' memories can't be tracked fails with
+    /// [`AccessTracking`](Error::AccessTracking).
+    pub fn set_witness(&mut self, enabled: bool) {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.witness.set_enabled(enabled);
+    }
+
+    /// Enable or disable the recording of the transactions performed on each
+    /// module in its call history, returned by [`call_history`].
+    ///
+    /// [`call_history`]: World::call_history
+    pub fn set_call_history(&mut self, enabled: bool) {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.history.set_enabled(enabled);
+    }
+
+    /// Set the timestamp available to modules.
+    pub fn set_timestamp(&mut self, timestamp: u64) {
+        let w = self.0.lock();
+        let w = unsafe { &mut *w.get() };
+
+        w.query_cache.clear();
+        w.timestamp = timestamp;
+    }
+
+    /// Limit the memory pages of 4KiB a transaction may write across all the
+    /// modules it calls, charging the given points for each page written.
+    ///
+    /// Exceeding the limit fails the transaction with
+    /// [`WriteLimit`](Error::WriteLimit), while running out of points to
+    /// pay for the pages fails it with [`OutOfPoints`](Error::OutOfPoints).
+    /// The transaction is rolled back in both cases. Pages are counted by
+    /// comparing memories before and after the transaction, so setting a
+    /// limit makes transactions slower.
+    pub fn set_write_limit(&mut self, pages: usize, points_per_page: u64) {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.writes.set_limit(pages, points_per_page);
+    }
+
+    /// Stop limiting and charging for the pages written by transactions.
+    pub fn clear_write_limit(&mut self) {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        w.writes.clear_limit();
+    }
+
+    /// Set the point limit given to calls when not overridden using
+    /// [`set_point_limit`](Self::set_point_limit).
+    pub fn set_default_point_limit(&mut self, limit: u64) {
+        let w = self.0.lock();
+        let w = unsafe { &mut *w.get() };
+
+        w.default_limit = limit;
+    }
+
+    /// Set the point limit for the next calls, overriding the default one
+    /// until cleared using [`clear_point_limit`](Self::clear_point_limit).
+    pub fn set_point_limit(&mut self, limit: u64) {
+        let w = self.0.lock();
+        let w = unsafe { &mut *w.get() };
+
+        w.limit = Some(limit);
+    }
+
+    /// Go back to giving calls the default point limit.
+    pub fn clear_point_limit(&mut self) {
+        let w = self.0.lock();
+        let w = unsafe { &mut *w.get() };
+
+        w.limit = None;
+    }
+
+    /// Return the point limit given to calls.
+    pub fn point_limit(&self) -> u64 {
+        let w = self.0.lock();
+        let w = unsafe { &*w.get() };
+
+        w.limit()
+    }
+
+    /// Set the point limit for queries, leaving the one of transactions
+    /// untouched.
+    pub fn set_query_point_limit(&mut self, limit: u64) {
+        let w = self.0.lock();
+        let w = unsafe { &mut *w.get() };
+
+        w.query_limit = Some(limit);
+    }
+
+    /// Go back to giving queries the same point limit as transactions.
+    pub fn clear_query_point_limit(&mut self) {
+        let w = self.0.lock();
+        let w = unsafe { &mut *w.get() };
+
+        w.query_limit = None;
+    }
+
+    /// Return the point limit given to queries.
+    pub fn query_point_limit(&self) -> u64 {
+        let w = self.0.lock();
+        let w = unsafe { &*w.get() };
+
+        w.query_limit()
+    }
+
+    fn perform_query(
+        &self,
+        name: &str,
+        caller_id: ModuleId,
+        callee_id: ModuleId,
+        arg_len: u32,
+    ) -> Result<u32, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        let caller = w.get(&caller_id).expect("oh no").inner();
+
+        if callee_id.is_reserved() {
+            return query_system_module(w, caller, callee_id, name, arg_len);
+        }
+
+        let remaining = caller.remaining_points();
+        let limit = remaining * POINT_PASS_PERCENTAGE / 100;
+
+        let callee = w
+            .environments
+            .get(&callee_id)
+            .ok_or(Error::ModuleNotFound(callee_id))?
+            .inner();
+        w.heap.enter(callee_id, callee.heap_top());
+        w.instructions.enter(callee_id, callee);
+        w.writes.enter(callee_id, callee);
+        w.journal.enter(callee_id, callee);
+        w.dirty.insert(callee_id);
+        w.call_stack.push(
+            callee_id,
+            name,
+            limit,
+            remaining - limit,
+            callee.is_pure(name),
+        );
+        if w.call_stack.is_pure() {
+            w.enter_pure(callee_id);
+        }
+        w.track_accesses(callee_id)?;
+
+        let caller = w.get(&caller_id).expect("oh no").inner();
+        let callee = w.get(&callee_id).expect("no oh").inner();
+
+        callee.set_remaining_points(limit);
+
+        let ret_ofs = copy_argument(caller, callee, arg_len)
+            .and_then(|_| {
+                callee
+                    .perform_query(name, arg_len)
+                    .map_err(|e| map_call_err(callee, e))
+            })
+            .and_then(|ret_ofs| {
+                copy_return(callee, caller, name, ret_ofs)?;
+                Ok(ret_ofs)
+            });
+
+        // the frame is left even if the call failed, since the caller may
+        // carry on using `try_query`
+        let callee_used = limit - callee.remaining_points();
+        caller.set_remaining_points(remaining - callee_used);
+
+        w.call_stack.pop();
+        if !w.call_stack.is_pure() {
+            let left = w.leave_pure();
+            return ret_ofs.and_then(|ret_ofs| left.map(|_| ret_ofs));
+        }
+
+        ret_ofs
+    }
+
+    /// Return the name of the method the given module exports under the
+    /// given selector.
+    fn selector_method(
+        &self,
+        module_id: ModuleId,
+        selector: u32,
+    ) -> Result<&str, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        let env = w
+            .environments
+            .get(&module_id)
+            .ok_or(Error::ModuleNotFound(module_id))?;
+
+        env.inner()
+            .selector_method(selector)
+            .ok_or(Error::SelectorNotFound {
+                module: module_id,
+                selector,
+            })
+    }
+
+    /// Perform the native query with the given name, whose argument is the
+    /// first `len` bytes of `buf`, returning `None` if there is no such query.
+    fn native_query(
+        &self,
+        name: &str,
+        buf: &mut [u8],
+        len: u32,
+    ) -> Result<Option<u32>, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        let arg = buf.get(..len as usize).ok_or(Error::ValidationError)?;
+
+        let arg = match w.native_queries.is_memoizing() {
+            true => {
+                if let Some(ret) = w.native_queries.memoized(name, arg) {
+                    buf[..ret.len()].copy_from_slice(ret);
+                    return Ok(Some(ret.len() as u32));
+                }
+                Some(arg.to_vec())
+            }
+            false => None,
+        };
+
+        let ret_len = match w.native_queries.call(name, buf, len) {
+            Some(ret_len) => ret_len,
+            None => return Ok(None),
+        };
+
+        if let Some(arg) = arg {
+            let ret = buf
+                .get(..ret_len as usize)
+                .ok_or(Error::ValidationError)?
+                .to_vec();
+            w.native_queries.memoize(name, arg, ret);
+        }
+
+        Ok(Some(ret_len))
+    }
+
+    fn perform_transaction(
+        &self,
+        name: &str,
+        caller_id: ModuleId,
+        callee_id: ModuleId,
+        arg_len: u32,
+    ) -> Result<u32, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        if w.call_stack.is_pure() {
+            return Err(Error::PureViolation(caller_id));
+        }
+
+        let caller = w.get(&caller_id).expect("oh no").inner();
+
+        // system modules are stateless, so transacting with them is the same
+        // as querying them
+        if callee_id.is_reserved() {
+            return query_system_module(w, caller, callee_id, name, arg_len);
+        }
+
+        let remaining = caller.remaining_points();
+        let limit = remaining * POINT_PASS_PERCENTAGE / 100;
+
+        let callee = w
+            .environments
+            .get(&callee_id)
+            .ok_or(Error::ModuleNotFound(callee_id))?
+            .inner();
+        w.heap.enter(callee_id, callee.heap_top());
+        w.instructions.enter(callee_id, callee);
+        w.writes.enter(callee_id, callee);
+        w.journal.enter(callee_id, callee);
+        w.dirty.insert(callee_id);
+        w.call_stack
+            .push(callee_id, name, limit, remaining - limit, false);
+        w.track_accesses(callee_id)?;
+
+        let caller = w.get(&caller_id).expect("oh no").inner();
+        let callee = w.get(&callee_id).expect("no oh").inner();
+
+        callee.set_remaining_points(limit);
+
+        let ret_len = copy_argument(caller, callee, arg_len)
+            .and_then(|_| {
+                callee
+                    .perform_transaction(name, arg_len)
+                    .map_err(|e| map_call_err(callee, e))
+            })
+            .and_then(|ret_len| {
+                copy_return(callee, caller, name, ret_len)?;
+                Ok(ret_len)
+            });
+
+        // the frame is left even if the call failed, since the caller may
+        // carry on using `try_transact`
+        let callee_used = limit - callee.remaining_points();
+        caller.set_remaining_points(remaining - callee_used);
+
+        w.call_stack.pop();
+
+        ret_len
+    }
+
+    /// Perform a call made by a module using `try_query` or `try_transact`,
+    /// writing an [`ErrorEnvelope`] to the argument buffer of the caller if
+    /// it fails, and returning its length flagged with
+    /// [`CALL_FAILED`](dallo::CALL_FAILED).
+    ///
+    /// The writes of the failed call to the memories of the modules it
+    /// entered are rolled back. The events it emitted, the transactions it
+    /// deferred and the subscriptions it changed are discarded, as are the
+    /// reverts it handled. If it reverted, its error is recorded in the
+    /// receipt instead.
+    fn try_call<F>(&self, caller_id: ModuleId, call: F) -> Result<u32, Error>
+    where
+        F: FnOnce() -> Result<u32, Error>,
+    {
+        let (events, deferred, reverts, changes) = {
+            let guard = self.0.lock();
+            let w = unsafe { &mut *guard.get() };
+            w.journal.open();
+            (
+                w.events.len(),
+                w.deferred.len(),
+                w.reverts.len(),
+                w.subscription_changes.len(),
+            )
+        };
+
+        let result = call();
+
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
+
+        let err = match result {
+            Ok(ret_len) => {
+                w.journal.close();
+                return Ok(ret_len);
+            }
+            Err(err) => err,
+        };
+
+        // the modules entered by the call have all returned, so their
+        // argument buffers can be restored too
+        for (module_id, saved) in w.journal.rollback() {
+            w.environments[&module_id]
+                .inner_mut()
+                .restore_all_memory(saved);
+        }
+        w.events.truncate(events);
+        w.deferred.truncate(deferred);
+        w.reverts.truncate(reverts);
+        w.subscription_changes.truncate(changes);
+
+        let code = err.code().as_u16();
+        let payload = match err {
+            Error::Revert(err) => {
+                let payload = AlignedBytes::from_slice(err.payload());
+                w.reverts.push(err);
+                payload
+            }
+            _ => AlignedBytes::default(),
+        };
 
-        let caller = w.get(&caller_id).expect("oh no").inner();
-        let callee = w.get(&callee_id).expect("no oh").inner();
+        let caller = w.environments[&caller_id].inner();
+        let len =
+            caller.write_to_arg_buffer(ErrorEnvelope { code, payload })?;
 
-        callee.set_remaining_points(limit);
+        Ok(len | dallo::CALL_FAILED)
+    }
 
-        let mut min_len = 0;
+    /// Build the error the currently executing module reverted with,
+    /// rendering it if the module declared its type and the type is
+    /// registered.
+    fn module_error(
+        &self,
+        instance: &Instance,
+        payload: Vec<u8>,
+    ) -> ModuleError {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
 
-        caller.with_arg_buffer(|buf_caller| {
-            callee.with_arg_buffer(|buf_callee| {
-                min_len = std::cmp::min(buf_caller.len(), buf_callee.len());
-                buf_callee[..min_len].copy_from_slice(&buf_caller[..min_len]);
-            })
+        let method = w.call_stack.method();
+        let decoded = instance.error_type(method).and_then(|type_name| {
+            w.error_types
+                .render(type_name, &payload)
+                .map(|rendering| (String::from(type_name), rendering))
         });
 
-        let ret_ofs = callee.perform_query(name, arg_len)?;
+        ModuleError::new(instance.id(), String::from(method), payload, decoded)
+    }
 
-        callee.with_arg_buffer(|buf_callee| {
-            caller.with_arg_buffer(|buf_caller| {
-                buf_caller[..min_len].copy_from_slice(&buf_callee[..min_len]);
-            })
-        });
+    fn height(&self, instance: &Instance) -> Result<u32, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
 
-        let callee_used = limit - callee.remaining_points();
-        caller.set_remaining_points(remaining - callee_used);
+        instance.write_to_arg_buffer(w.height)
+    }
 
-        w.call_stack.pop();
+    fn timestamp(&self, instance: &Instance) -> Result<u32, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
 
-        Ok(ret_ofs)
+        instance.write_to_arg_buffer(w.timestamp)
     }
 
-    fn native_query(
-        &self,
-        name: &str,
-        buf: &mut [u8],
-        len: u32,
-    ) -> Option<u32> {
+    fn commit_id(&self, instance: &Instance) -> Result<u32, Error> {
         let guard = self.0.lock();
         let w = unsafe { &*guard.get() };
 
-        w.native_queries.call(name, buf, len)
+        instance.write_to_arg_buffer(w.root)
     }
 
-    fn perform_transaction(
+    fn random(
         &self,
-        name: &str,
-        caller_id: ModuleId,
-        callee_id: ModuleId,
-        arg_len: u32,
+        instance: &Instance,
+        domain: &[u8],
+        len: u32,
     ) -> Result<u32, Error> {
         let guard = self.0.lock();
         let w = unsafe { &mut *guard.get() };
 
-        let caller = w.get(&caller_id).expect("oh no").inner();
-
-        let remaining = caller.remaining_points();
-        let limit = remaining * POINT_PASS_PERCENTAGE / 100;
-
-        w.call_stack.push(callee_id, limit);
+        let len = len as usize;
+        if len > dallo::ARGBUF_LEN {
+            return Err(Error::ValidationError);
+        }
 
-        let caller = w.get(&caller_id).expect("oh no").inner();
-        let callee = w.get(&callee_id).expect("no oh").inner();
+        let cost = RANDOM_POINTS + len as u64 * RANDOM_BYTE_POINTS;
+        let remaining = instance.remaining_points();
+        if remaining < cost {
+            instance.set_remaining_points(0);
+            return Err(Error::OutOfPoints(instance.id()));
+        }
+        instance.set_remaining_points(remaining - cost);
 
-        callee.set_remaining_points(limit);
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&w.root);
+        hasher.update(&w.call_hash);
+        hasher.update(&w.random_counter.to_le_bytes());
+        hasher.update(domain);
+        w.random_counter += 1;
 
-        caller.with_arg_buffer(|buf_caller| {
-            callee.with_arg_buffer(|buf_callee| {
-                let min_len = std::cmp::min(buf_caller.len(), buf_callee.len());
-                buf_callee[..min_len].copy_from_slice(&buf_caller[..min_len]);
-            })
+        instance.with_arg_buffer(|buf| {
+            hasher.finalize_xof().fill(&mut buf[..len]);
         });
 
-        let ret_len = callee.perform_transaction(name, arg_len)?;
-
-        callee.with_arg_buffer(|buf_callee| {
-            caller.with_arg_buffer(|buf_caller| {
-                let min_len = std::cmp::min(buf_caller.len(), buf_callee.len());
-                buf_caller[..min_len].copy_from_slice(&buf_callee[..min_len]);
-            })
-        });
+        Ok(len as u32)
+    }
 
-        let callee_used = limit - callee.remaining_points();
-        caller.set_remaining_points(remaining - callee_used);
+    fn defer(
+        &self,
+        module_id: ModuleId,
+        callee_id: ModuleId,
+        raw: RawTransaction,
+    ) -> Result<(), Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &mut *guard.get() };
 
-        w.call_stack.pop();
+        if w.call_stack.is_pure() {
+            return Err(Error::PureViolation(module_id));
+        }
+        if w.querying {
+            return Err(Error::DeferredInQuery(module_id));
+        }
 
-        Ok(ret_len)
+        w.deferred.push((callee_id, raw));
+        Ok(())
     }
 
-    fn height(&self, instance: &Instance) -> Result<u32, Error> {
+    fn subscribe(
+        &self,
+        subscriber: ModuleId,
+        publisher: ModuleId,
+        subscribe: bool,
+    ) -> Result<(), Error> {
         let guard = self.0.lock();
-        let w = unsafe { &*guard.get() };
+        let w = unsafe { &mut *guard.get() };
 
-        instance.write_to_arg_buffer(w.height)
+        if w.call_stack.is_pure() {
+            return Err(Error::PureViolation(subscriber));
+        }
+
+        w.subscription_changes.push(SubscriptionChange {
+            subscriber,
+            publisher,
+            subscribe,
+        });
+
+        Ok(())
     }
 
-    fn emit(&self, module_id: ModuleId, data: Vec<u8>) {
+    fn emit(&self, module_id: ModuleId, data: Vec<u8>) -> Result<(), Error> {
         let guard = self.0.lock();
         let w = unsafe { &mut *guard.get() };
 
-        w.events.push(Event::new(module_id, data));
+        let count = w.events.len() + 1;
+        let bytes =
+            w.events.iter().map(|e| e.data().len()).sum::<usize>() + data.len();
+        if !w.emit_limit.allows(count, bytes) {
+            return Err(Error::EmitLimit(module_id));
+        }
+
+        let callers = w.call_stack.callers();
+        let method = String::from(w.call_stack.method());
+        w.events.push(Event::new(module_id, callers, method, data));
+        Ok(())
     }
 
-    pub(crate) fn debug(&self, string: String) {
+    pub(crate) fn debug(
+        &self,
+        module_id: ModuleId,
+        string: String,
+    ) -> Result<(), Error> {
         let guard = self.0.lock();
         let w = unsafe { &mut *guard.get() };
 
+        let count = w.debug.len() + 1;
+        let bytes =
+            w.debug.iter().map(String::len).sum::<usize>() + string.len();
+        if !w.debug_limit.allows(count, bytes) {
+            return Err(Error::DebugLimit(module_id));
+        }
+
         println!("pushing string");
 
         w.debug.push(string);
+        Ok(())
     }
 
     fn limit(&self, instance: &Instance) -> Result<u32, Error> {
@@ -428,6 +3189,19 @@ impl World {
         instance.write_to_arg_buffer(limit)
     }
 
+    fn memory_pressure(&self, instance: &Instance) -> bool {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        match w.soft_memory_limit {
+            Some(percent) => {
+                let (used, limit) = instance.heap_stats();
+                used as u128 * 100 > limit as u128 * percent as u128
+            }
+            None => false,
+        }
+    }
+
     fn spent(&self, instance: &Instance) -> Result<u32, Error> {
         let guard = self.0.lock();
         let w = unsafe { &*guard.get() };
@@ -438,6 +3212,32 @@ impl World {
         instance.write_to_arg_buffer(limit - remaining)
     }
 
+    fn entry_module(&self, instance: &Instance) -> Result<u32, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+        let entry_module = w.call_stack.entry_module();
+
+        instance.write_to_arg_buffer(entry_module)
+    }
+
+    fn tx_limit(&self, instance: &Instance) -> Result<u32, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        let limit = w.call_stack.tx_limit();
+        instance.write_to_arg_buffer(limit)
+    }
+
+    fn tx_spent(&self, instance: &Instance) -> Result<u32, Error> {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        let limit = w.call_stack.tx_limit();
+        let remaining = instance.remaining_points() + w.call_stack.reserved();
+
+        instance.write_to_arg_buffer(limit - remaining)
+    }
+
     fn caller(&self, instance: &Instance) -> Result<u32, Error> {
         let guard = self.0.lock();
         let w = unsafe { &*guard.get() };
@@ -446,6 +3246,16 @@ impl World {
         instance.write_to_arg_buffer(caller)
     }
 
+    fn tx_meta(&self, instance: &Instance) -> u32 {
+        let guard = self.0.lock();
+        let w = unsafe { &*guard.get() };
+
+        let len = w.tx_meta.len();
+        instance.with_arg_buffer(|buf| buf[..len].copy_from_slice(&w.tx_meta));
+
+        len as u32
+    }
+
     pub fn storage_path(&self) -> &Path {
         let guard = self.0.lock();
         let world_inner = unsafe { &*guard.get() };
@@ -461,11 +3271,118 @@ fn global_i32(exports: &Exports, name: &str) -> Result<i32, Error> {
     }
 }
 
+/// Read the names of the methods the module declared as pure using
+/// `dallo::pure!`, if any.
+fn pure_methods(
+    instance: &wasmer::Instance,
+) -> Result<BTreeSet<String>, Error> {
+    let ofs = match instance.exports.get_global("PURE_METHODS") {
+        Ok(global) => match global.get() {
+            Val::I32(ofs) => ofs as usize,
+            _ => return Err(Error::MissingModuleExport),
+        },
+        Err(_) => return Ok(BTreeSet::new()),
+    };
+
+    let memory = instance.exports.get_memory("memory")?;
+    let bytes = unsafe { memory.data_unchecked() };
+
+    // the static is a `&str`, laid out as a pointer followed by a length
+    let read_u32 = |ofs: usize| {
+        bytes
+            .get(ofs..ofs + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().expect("4 bytes")))
+            .ok_or(Error::ValidationError)
+    };
+    let ptr = read_u32(ofs)? as usize;
+    let len = read_u32(ofs + 4)? as usize;
+
+    let names = bytes.get(ptr..ptr + len).ok_or(Error::ValidationError)?;
+    let names =
+        core::str::from_utf8(names).map_err(|_| Error::ValidationError)?;
+
+    Ok(names
+        .split('\n')
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Map the selector of each function exported by a module to its name,
+/// leaving out selectors shared by more than one function.
+fn selectors(instance: &wasmer::Instance) -> BTreeMap<u32, String> {
+    let mut selectors = BTreeMap::new();
+    let mut colliding = BTreeSet::new();
+
+    for (name, _) in instance.exports.iter().functions() {
+        let selector = dallo::selector(name);
+        if selectors.insert(selector, name.clone()).is_some() {
+            colliding.insert(selector);
+        }
+    }
+
+    for selector in colliding {
+        selectors.remove(&selector);
+    }
+
+    selectors
+}
+
+/// Copy the argument of an inter-contract call from the argument buffer of the
+/// caller to the one of the callee.
+fn copy_argument(
+    caller: &Instance,
+    callee: &Instance,
+    arg_len: u32,
+) -> Result<(), Error> {
+    let len = arg_len as usize;
+    if len > dallo::ARGBUF_LEN {
+        return Err(Error::ArgumentTooLarge {
+            required: len,
+            available: dallo::ARGBUF_LEN,
+        });
+    }
+
+    caller.with_arg_buffer(|buf_caller| {
+        callee.with_arg_buffer(|buf_callee| {
+            buf_callee[..len].copy_from_slice(&buf_caller[..len]);
+        })
+    });
+
+    Ok(())
+}
+
+/// Copy the return of an inter-contract call from the argument buffer of the
+/// callee to the one of the caller.
+fn copy_return(
+    callee: &Instance,
+    caller: &Instance,
+    method: &str,
+    ret_len: u32,
+) -> Result<(), Error> {
+    callee.check_return_len(method, ret_len)?;
+    let len = ret_len as usize;
+
+    callee.with_arg_buffer(|buf_callee| {
+        caller.with_arg_buffer(|buf_caller| {
+            buf_caller[..len].copy_from_slice(&buf_callee[..len]);
+        })
+    });
+
+    Ok(())
+}
+
+/// Returns 0 when the memory of the module is exhausted, leaving the module
+/// to handle the failure.
 fn host_alloc(env: &Env, amount: i32, align: i32) -> i32 {
     env.inner_mut()
         .alloc(amount as usize, align as usize)
-        .try_into()
-        .expect("i32 overflow")
+        .and_then(|ofs| i32::try_from(ofs).ok())
+        .unwrap_or(0)
+}
+
+fn host_oom(env: &Env, _size: u32) -> Result<(), Error> {
+    Err(Error::GuestOutOfMemory(env.inner().id()))
 }
 
 fn host_dealloc(env: &Env, addr: i32) {
@@ -483,28 +3400,41 @@ fn host_query(
     method_name_adr: i32,
     method_name_len: u32,
     arg_len: u32,
-) -> u32 {
-    let module_id_adr = module_id_adr as usize;
+) -> Result<u32, Error> {
     let method_name_adr = method_name_adr as usize;
     let method_name_len = method_name_len as usize;
 
     let instance = env.inner();
-    let mut mod_id = ModuleId::uninitialized();
+    let mod_id = read_module_id(instance, module_id_adr);
 
-    let name = instance.with_memory(|buf| {
-        mod_id.as_bytes_mut()[..].copy_from_slice(
-            &buf[module_id_adr..][..core::mem::size_of::<ModuleId>()],
-        );
-        // performance: use a dedicated buffer here?
-        core::str::from_utf8(&buf[method_name_adr..][..method_name_len])
-            .expect("TODO, error out cleaner")
-            .to_owned()
-    });
+    instance.with_method_name(method_name_adr, method_name_len, |name| {
+        instance
+            .world()
+            .perform_query(name, instance.id(), mod_id, arg_len)
+    })
+}
 
-    instance
-        .world()
-        .perform_query(&name, instance.id(), mod_id, arg_len)
-        .expect("TODO: error handling")
+/// Query a system module, with the argument and return in the argument buffer
+/// of the caller.
+fn query_system_module(
+    w: &WorldInner,
+    caller: &Instance,
+    module_id: ModuleId,
+    name: &str,
+    arg_len: u32,
+) -> Result<u32, Error> {
+    if !w.system_modules.contains(&module_id) {
+        return Err(Error::ModuleNotFound(module_id));
+    }
+
+    caller
+        .with_arg_buffer(|buf| {
+            w.system_modules.call(&module_id, name, buf, arg_len)
+        })
+        .ok_or_else(|| Error::MethodNotFound {
+            module: module_id,
+            method: String::from(name),
+        })
 }
 
 fn host_native_query(
@@ -512,24 +3442,19 @@ fn host_native_query(
     name_adr: i32,
     name_len: u32,
     arg_len: u32,
-) -> u32 {
+) -> Result<u32, Error> {
     let name_adr = name_adr as usize;
     let name_len = name_len as usize;
 
     let instance = env.inner();
 
-    let name = instance.with_memory(|buf| {
-        // performance: use a dedicated buffer here?
-        core::str::from_utf8(&buf[name_adr..][..name_len])
-            .expect("TODO, error out cleaner")
-            .to_owned()
-    });
-
-    instance
-        .with_arg_buffer(|buf| {
-            instance.world().native_query(&name, buf, arg_len)
+    let ret_len = instance.with_method_name(name_adr, name_len, |name| {
+        instance.with_arg_buffer(|buf| {
+            instance.world().native_query(name, buf, arg_len)
         })
-        .expect("TODO: error handling")
+    })?;
+
+    Ok(ret_len.expect("TODO: error handling"))
 }
 
 fn host_transact(
@@ -538,39 +3463,168 @@ fn host_transact(
     method_name_adr: i32,
     method_name_len: u32,
     arg_len: u32,
-) -> u32 {
-    let module_id_adr = module_id_adr as usize;
+) -> Result<u32, Error> {
     let method_name_adr = method_name_adr as usize;
     let method_name_len = method_name_len as usize;
 
     let instance = env.inner();
+    let mod_id = read_module_id(instance, module_id_adr);
+
+    instance.with_method_name(method_name_adr, method_name_len, |name| {
+        instance.world().perform_transaction(
+            name,
+            instance.id(),
+            mod_id,
+            arg_len,
+        )
+    })
+}
+
+fn host_query_selector(
+    env: &Env,
+    module_id_adr: i32,
+    selector: u32,
+    arg_len: u32,
+) -> Result<u32, Error> {
+    let instance = env.inner();
+    let mod_id = read_module_id(instance, module_id_adr);
+
+    let world = instance.world();
+    let name = world.selector_method(mod_id, selector)?;
+    world.perform_query(name, instance.id(), mod_id, arg_len)
+}
+
+fn host_transact_selector(
+    env: &Env,
+    module_id_adr: i32,
+    selector: u32,
+    arg_len: u32,
+) -> Result<u32, Error> {
+    let instance = env.inner();
+    let mod_id = read_module_id(instance, module_id_adr);
+
+    let world = instance.world();
+    let name = world.selector_method(mod_id, selector)?;
+    world.perform_transaction(name, instance.id(), mod_id, arg_len)
+}
+
+/// Read the id of the module a call is made to from the memory of the
+/// calling module.
+fn read_module_id(instance: &Instance, module_id_adr: i32) -> ModuleId {
+    let module_id_adr = module_id_adr as usize;
     let mut mod_id = ModuleId::uninitialized();
 
-    let name = instance.with_memory(|buf| {
+    instance.with_memory(|buf| {
         mod_id.as_bytes_mut()[..].copy_from_slice(
             &buf[module_id_adr..][..core::mem::size_of::<ModuleId>()],
-        );
-        // performance: use a dedicated buffer here?
-        core::str::from_utf8(&buf[method_name_adr..][..method_name_len])
-            .expect("TODO, error out cleaner")
-            .to_owned()
+        )
     });
 
+    mod_id
+}
+
+fn host_try_query(
+    env: &Env,
+    module_id_adr: i32,
+    method_name_adr: i32,
+    method_name_len: u32,
+    arg_len: u32,
+) -> Result<u32, Error> {
+    let instance = env.inner();
+    instance.world().try_call(instance.id(), || {
+        host_query(
+            env,
+            module_id_adr,
+            method_name_adr,
+            method_name_len,
+            arg_len,
+        )
+    })
+}
+
+fn host_try_transact(
+    env: &Env,
+    module_id_adr: i32,
+    method_name_adr: i32,
+    method_name_len: u32,
+    arg_len: u32,
+) -> Result<u32, Error> {
+    let instance = env.inner();
+    instance.world().try_call(instance.id(), || {
+        host_transact(
+            env,
+            module_id_adr,
+            method_name_adr,
+            method_name_len,
+            arg_len,
+        )
+    })
+}
+
+fn host_height(env: &Env) -> u32 {
+    let instance = env.inner();
     instance
         .world()
-        .perform_transaction(&name, instance.id(), mod_id, arg_len)
+        .height(instance)
         .expect("TODO: error handling")
 }
 
-fn host_height(env: &Env) -> u32 {
+fn host_timestamp(env: &Env) -> u32 {
     let instance = env.inner();
     instance
         .world()
-        .height(instance)
+        .timestamp(instance)
         .expect("TODO: error handling")
 }
 
-fn host_emit(env: &Env, arg_len: u32) {
+fn host_heap_stats(env: &Env) -> Result<u32, Error> {
+    let instance = env.inner();
+    instance.write_to_arg_buffer(instance.heap_stats())
+}
+
+fn host_memory_pressure(env: &Env) -> u32 {
+    let instance = env.inner();
+    instance.world().memory_pressure(instance) as u32
+}
+
+fn host_memory_pages(env: &Env) -> Result<u32, Error> {
+    let instance = env.inner();
+    instance.write_to_arg_buffer(instance.memory_pages())
+}
+
+fn host_code_hash(env: &Env) -> Result<u32, Error> {
+    let instance = env.inner();
+    instance.write_to_arg_buffer(instance.code_hash())
+}
+
+fn host_commit_id(env: &Env) -> Result<u32, Error> {
+    let instance = env.inner();
+    instance.world().commit_id(instance)
+}
+
+fn host_random(
+    env: &Env,
+    domain_adr: i32,
+    domain_len: u32,
+    len: u32,
+) -> Result<u32, Error> {
+    let instance = env.inner();
+
+    let domain_adr = domain_adr as usize;
+    let domain_len = domain_len as usize;
+
+    let domain = instance
+        .with_memory(|buf| {
+            buf.get(domain_adr..)
+                .and_then(|buf| buf.get(..domain_len))
+                .map(<[u8]>::to_vec)
+        })
+        .ok_or(Error::ValidationError)?;
+
+    instance.world().random(instance, &domain, len)
+}
+
+fn host_emit(env: &Env, arg_len: u32) -> Result<(), Error> {
     let instance = env.inner();
     let module_id = instance.id();
 
@@ -578,7 +3632,28 @@ fn host_emit(env: &Env, arg_len: u32) {
 
     let data = instance.with_arg_buffer(|buf| buf[..arg_len].to_vec());
 
-    instance.world().emit(module_id, data);
+    instance.world().emit(module_id, data)
+}
+
+fn host_defer(env: &Env, arg_len: u32) -> Result<(), Error> {
+    let instance = env.inner();
+
+    let (callee_id, raw) =
+        instance.read_from_arg_buffer::<(ModuleId, RawTransaction)>(arg_len)?;
+
+    instance.world().defer(instance.id(), callee_id, raw)
+}
+
+fn host_subscribe(env: &Env, arg_len: u32) -> Result<(), Error> {
+    let instance = env.inner();
+    let publisher = instance.read_from_arg_buffer::<ModuleId>(arg_len)?;
+    instance.world().subscribe(instance.id(), publisher, true)
+}
+
+fn host_unsubscribe(env: &Env, arg_len: u32) -> Result<(), Error> {
+    let instance = env.inner();
+    let publisher = instance.read_from_arg_buffer::<ModuleId>(arg_len)?;
+    instance.world().subscribe(instance.id(), publisher, false)
 }
 
 fn host_spent(env: &Env) -> u32 {
@@ -597,6 +3672,21 @@ fn host_limit(env: &Env) -> u32 {
         .expect("TODO: error handling")
 }
 
+fn host_entry_module(env: &Env) -> Result<u32, Error> {
+    let instance = env.inner();
+    instance.world().entry_module(instance)
+}
+
+fn host_tx_spent(env: &Env) -> Result<u32, Error> {
+    let instance = env.inner();
+    instance.world().tx_spent(instance)
+}
+
+fn host_tx_limit(env: &Env) -> Result<u32, Error> {
+    let instance = env.inner();
+    instance.world().tx_limit(instance)
+}
+
 fn host_caller(env: &Env) -> u32 {
     let instance = env.inner();
     instance
@@ -605,12 +3695,38 @@ fn host_caller(env: &Env) -> u32 {
         .expect("TODO: error handling")
 }
 
-fn host_debug(env: &Env, ofs: i32, len: u32) {
+fn host_tx_meta(env: &Env) -> u32 {
     let instance = env.inner();
-    instance.debug(ofs, len)
+    instance.world().tx_meta(instance)
 }
 
-fn host_panic(env: &Env, ofs: i32, len: u32) {
+fn host_debug(env: &Env, ofs: i32, len: u32) -> Result<(), Error> {
     let instance = env.inner();
     instance.debug(ofs, len)
 }
+
+fn host_revert(env: &Env, len: u32) -> Result<(), Error> {
+    let instance = env.inner();
+    let payload = instance
+        .with_arg_buffer(|buf| buf.get(..len as usize).map(<[u8]>::to_vec))
+        .ok_or(Error::ValidationError)?;
+
+    Err(Error::Revert(
+        instance.world().module_error(instance, payload),
+    ))
+}
+
+fn host_panic(env: &Env, len: u32) -> Result<(), Error> {
+    let instance = env.inner();
+    let message = instance
+        .with_arg_buffer(|buf| {
+            buf.get(..len as usize)
+                .map(|message| String::from_utf8_lossy(message).into_owned())
+        })
+        .ok_or(Error::ValidationError)?;
+
+    Err(Error::Panic {
+        module: instance.id(),
+        message,
+    })
+}
@@ -0,0 +1,572 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The accesses made to a memory while it was tracked.
+///
+/// Pages are keyed by their offset into the memory, and are as large as the
+/// pages of the operating system.
+#[derive(Debug, Default)]
+pub struct Accesses {
+    /// The length of the memory when tracking started.
+    pub len: usize,
+    /// The size of the pages accesses are tracked in.
+    pub page_size: usize,
+    /// The pages read or written, as they were before the first access.
+    pub pages: BTreeMap<usize, Vec<u8>>,
+    /// The pages written, including the ones the memory grew by that are no
+    /// longer all zeroes.
+    pub written: BTreeSet<usize>,
+}
+
+pub use imp::AccessTracker;
+
+/// Accesses are tracked by revoking access to the whole memory, and letting
+/// the faults raised on the first read and the first write to each page
+/// restore it one step at a time. Faults outside of a tracked memory are
+/// forwarded to the handler installed before, which is the one the runtime
+/// uses to turn faults in modules into traps, since memories are only
+/// tracked once a module was instantiated.
+///
+/// The fault handler can interrupt any code, including the allocator, so it
+/// takes no locks and allocates nothing. The memories being tracked are kept
+/// in a fixed table, with the pages accessed and written marked in bitmaps
+/// allocated when tracking starts. A page about to be written for the first
+/// time is copied to space reserved when tracking started, while the pages
+/// only read are copied once the call returns, since they are unchanged.
+#[cfg(unix)]
+mod imp {
+    use std::collections::BTreeMap;
+    use std::ffi::c_void;
+    use std::mem::{self, MaybeUninit};
+    use std::ops::Range;
+    use std::sync::atomic::{
+        AtomicPtr, AtomicU64, AtomicU8, AtomicUsize, Ordering,
+    };
+    use std::sync::Once;
+    use std::{ptr, slice};
+
+    use dallo::ModuleId;
+
+    use super::Accesses;
+    use crate::error::Error;
+
+    /// Number of memories that can be tracked at once, by any world.
+    const MAX_REGIONS: usize = 64;
+
+    const FREE: u8 = 0;
+    const RESERVED: u8 = 1;
+    const ACTIVE: u8 = 2;
+
+    /// An entry in the table of tracked memories, only read by the fault
+    /// handler once it is active.
+    struct Slot {
+        state: AtomicU8,
+        base: AtomicUsize,
+        len: AtomicUsize,
+        /// One bit per page, set once the page was accessed.
+        read: AtomicPtr<AtomicU64>,
+        /// One bit per page, set once the page was written.
+        written: AtomicPtr<AtomicU64>,
+        /// Where pages are copied to before they are first written.
+        shadow: AtomicUsize,
+    }
+
+    impl Slot {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const EMPTY: Slot = Slot {
+            state: AtomicU8::new(FREE),
+            base: AtomicUsize::new(0),
+            len: AtomicUsize::new(0),
+            read: AtomicPtr::new(ptr::null_mut()),
+            written: AtomicPtr::new(ptr::null_mut()),
+            shadow: AtomicUsize::new(0),
+        };
+    }
+
+    /// The memories being tracked, by any world.
+    static SLOTS: [Slot; MAX_REGIONS] = [Slot::EMPTY; MAX_REGIONS];
+
+    /// The size of the pages of the operating system, read once before the
+    /// handler is installed.
+    static PAGE_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+    static mut PREVIOUS_SEGV: MaybeUninit<libc::sigaction> =
+        MaybeUninit::uninit();
+    static mut PREVIOUS_BUS: MaybeUninit<libc::sigaction> =
+        MaybeUninit::uninit();
+
+    /// Tracks the pages accessed in the memories of modules.
+    #[derive(Debug, Default)]
+    pub struct AccessTracker {
+        tracked: BTreeMap<ModuleId, Tracked>,
+    }
+
+    impl AccessTracker {
+        pub fn is_tracking(&self, module_id: &ModuleId) -> bool {
+            self.tracked.contains_key(module_id)
+        }
+
+        /// Return the modules whose memories are tracked.
+        pub fn tracked(&self) -> Vec<ModuleId> {
+            self.tracked.keys().copied().collect()
+        }
+
+        /// Start tracking the accesses to the memory of the given module.
+        pub fn track(
+            &mut self,
+            module_id: ModuleId,
+            memory: &[u8],
+        ) -> Result<(), Error> {
+            let page_size = install_handler();
+            let tracked = Tracked::new(memory, page_size)
+                .ok_or(Error::AccessTracking(module_id))?;
+
+            self.tracked.insert(module_id, tracked);
+            Ok(())
+        }
+
+        /// Stop tracking the memory of the given module, whose current
+        /// contents are `memory`, returning the accesses made to it.
+        pub fn untrack(
+            &mut self,
+            module_id: &ModuleId,
+            memory: &[u8],
+        ) -> Option<Accesses> {
+            let tracked = self.tracked.remove(module_id)?;
+            Some(tracked.accesses(memory))
+        }
+
+        /// Stop tracking every memory.
+        pub fn clear(&mut self) {
+            self.tracked.clear();
+        }
+    }
+
+    /// A memory being tracked, owning the space its slot points to. Access
+    /// to the memory is given back when it is dropped.
+    ///
+    /// The faults in a memory are only raised by the thread running its
+    /// module, which holds the world until the memory stops being tracked,
+    /// so the space is never freed while the handler uses it.
+    #[derive(Debug)]
+    struct Tracked {
+        slot: usize,
+        base: usize,
+        len: usize,
+        page_size: usize,
+        read: Box<[AtomicU64]>,
+        written: Box<[AtomicU64]>,
+        shadow: Shadow,
+    }
+
+    impl Tracked {
+        /// Reserve a slot for the given memory and revoke access to it,
+        /// returning `None` if there is no free slot or if the memory can't
+        /// be protected.
+        fn new(memory: &[u8], page_size: usize) -> Option<Self> {
+            let base = memory.as_ptr() as usize;
+            let len = memory.len();
+
+            let words = len.div_ceil(page_size).div_ceil(64);
+            let bitmap = || (0..words).map(|_| AtomicU64::new(0));
+            let read: Box<[AtomicU64]> = bitmap().collect();
+            let written: Box<[AtomicU64]> = bitmap().collect();
+            let shadow = Shadow::new(len)?;
+
+            let slot = SLOTS.iter().position(|slot| {
+                slot.state
+                    .compare_exchange(
+                        FREE,
+                        RESERVED,
+                        Ordering::Acquire,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+            })?;
+
+            let entry = &SLOTS[slot];
+            entry.base.store(base, Ordering::Relaxed);
+            entry.len.store(len, Ordering::Relaxed);
+            entry
+                .read
+                .store(read.as_ptr() as *mut AtomicU64, Ordering::Relaxed);
+            entry
+                .written
+                .store(written.as_ptr() as *mut AtomicU64, Ordering::Relaxed);
+            entry.shadow.store(shadow.addr, Ordering::Relaxed);
+            entry.state.store(ACTIVE, Ordering::Release);
+
+            // dropping it frees the slot and gives access back, even if the
+            // protection was only partly applied
+            let tracked = Self {
+                slot,
+                base,
+                len,
+                page_size,
+                read,
+                written,
+                shadow,
+            };
+
+            protect(base, len, libc::PROT_NONE).then_some(tracked)
+        }
+
+        /// Collect the accesses made to the memory, whose current contents
+        /// are `memory`.
+        fn accesses(self, memory: &[u8]) -> Accesses {
+            let page_size = self.page_size;
+            let mut accesses = Accesses {
+                len: self.len,
+                page_size,
+                ..Accesses::default()
+            };
+
+            for index in set_bits(&self.read) {
+                let ofs = index * page_size;
+                let written = is_set(&self.written, index);
+
+                // pages only read are as they were before the call
+                let page = if written {
+                    self.shadow.get(ofs..ofs + page_size)
+                } else {
+                    &memory[ofs..ofs + page_size]
+                };
+                accesses.pages.insert(ofs, page.to_vec());
+                if written {
+                    accesses.written.insert(ofs);
+                }
+            }
+
+            // pages the memory grew by were never protected, but they were
+            // all zeroes before
+            for ofs in (self.len..memory.len()).step_by(page_size) {
+                let end = memory.len().min(ofs + page_size);
+                if memory[ofs..end].iter().any(|b| *b != 0) {
+                    accesses.written.insert(ofs);
+                }
+            }
+
+            accesses
+        }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            // the memory may be unmapped already if its module was dropped,
+            // in which case there's nothing to give back
+            protect(self.base, self.len, libc::PROT_READ | libc::PROT_WRITE);
+            SLOTS[self.slot].state.store(FREE, Ordering::Release);
+        }
+    }
+
+    /// Space as large as a tracked memory, only backed once written to.
+    #[derive(Debug)]
+    struct Shadow {
+        addr: usize,
+        len: usize,
+    }
+
+    impl Shadow {
+        fn new(len: usize) -> Option<Self> {
+            if len == 0 {
+                return Some(Self { addr: 0, len });
+            }
+
+            let addr = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    len,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_PRIVATE | libc::MAP_ANON,
+                    -1,
+                    0,
+                )
+            };
+            (addr != libc::MAP_FAILED).then(|| Self {
+                addr: addr as usize,
+                len,
+            })
+        }
+
+        fn get(&self, range: Range<usize>) -> &[u8] {
+            let bytes = match self.len {
+                0 => &[][..],
+                _ => unsafe {
+                    slice::from_raw_parts(self.addr as *const u8, self.len)
+                },
+            };
+            &bytes[range]
+        }
+    }
+
+    impl Drop for Shadow {
+        fn drop(&mut self) {
+            if self.len != 0 {
+                unsafe { libc::munmap(self.addr as *mut c_void, self.len) };
+            }
+        }
+    }
+
+    fn is_set(bitmap: &[AtomicU64], index: usize) -> bool {
+        bitmap[index / 64].load(Ordering::Relaxed) & (1 << (index % 64)) != 0
+    }
+
+    /// Return the indices of the bits set in the given bitmap.
+    fn set_bits(bitmap: &[AtomicU64]) -> impl Iterator<Item = usize> + '_ {
+        bitmap.iter().enumerate().flat_map(|(word, bits)| {
+            let bits = bits.load(Ordering::Relaxed);
+            (0..64)
+                .filter(move |bit| bits & (1 << bit) != 0)
+                .map(move |bit| word * 64 + bit)
+        })
+    }
+
+    /// Change the protection of the given pages, returning false if it
+    /// failed.
+    fn protect(addr: usize, len: usize, prot: libc::c_int) -> bool {
+        len == 0
+            || unsafe { libc::mprotect(addr as *mut c_void, len, prot) == 0 }
+    }
+
+    /// Record an access at the given address, returning false if it is not
+    /// in a tracked memory.
+    ///
+    /// The first access to a page gives read access to it, and the second,
+    /// only raised if the first was a write, copies the page and gives write
+    /// access too. Called from the fault handler, so it must not lock or
+    /// allocate.
+    fn record_access(addr: usize) -> bool {
+        let page_size = PAGE_SIZE.load(Ordering::Relaxed);
+
+        let slot = match SLOTS.iter().find(|slot| {
+            if slot.state.load(Ordering::Acquire) != ACTIVE {
+                return false;
+            }
+            let base = slot.base.load(Ordering::Relaxed);
+            let len = slot.len.load(Ordering::Relaxed);
+            (base..base + len).contains(&addr)
+        }) {
+            Some(slot) => slot,
+            None => return false,
+        };
+
+        let base = slot.base.load(Ordering::Relaxed);
+        let index = (addr - base) / page_size;
+        let page = base + index * page_size;
+
+        let word = index / 64;
+        let bit = 1 << (index % 64);
+        let (read, written) = unsafe {
+            (
+                &*slot.read.load(Ordering::Relaxed).add(word),
+                &*slot.written.load(Ordering::Relaxed).add(word),
+            )
+        };
+
+        if read.load(Ordering::Relaxed) & bit == 0 {
+            if !protect(page, page_size, libc::PROT_READ) {
+                return false;
+            }
+            read.fetch_or(bit, Ordering::Relaxed);
+            return true;
+        }
+
+        if written.load(Ordering::Relaxed) & bit != 0 {
+            return false;
+        }
+
+        let shadow = slot.shadow.load(Ordering::Relaxed) + index * page_size;
+        unsafe {
+            ptr::copy_nonoverlapping(
+                page as *const u8,
+                shadow as *mut u8,
+                page_size,
+            );
+        }
+        if !protect(page, page_size, libc::PROT_READ | libc::PROT_WRITE) {
+            return false;
+        }
+        written.fetch_or(bit, Ordering::Relaxed);
+        true
+    }
+
+    /// Install the fault handler, if it wasn't yet, returning the size of
+    /// the pages of the operating system.
+    fn install_handler() -> usize {
+        static INSTALL: Once = Once::new();
+
+        INSTALL.call_once(|| unsafe {
+            let page_size = libc::sysconf(libc::_SC_PAGESIZE) as usize;
+            PAGE_SIZE.store(page_size, Ordering::Relaxed);
+
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = on_fault as *const () as usize;
+            action.sa_flags =
+                libc::SA_SIGINFO | libc::SA_NODEFER | libc::SA_ONSTACK;
+            libc::sigemptyset(&mut action.sa_mask);
+
+            let previous = (*ptr::addr_of_mut!(PREVIOUS_SEGV)).as_mut_ptr();
+            libc::sigaction(libc::SIGSEGV, &action, previous);
+            let previous = (*ptr::addr_of_mut!(PREVIOUS_BUS)).as_mut_ptr();
+            libc::sigaction(libc::SIGBUS, &action, previous);
+        });
+
+        PAGE_SIZE.load(Ordering::Relaxed)
+    }
+
+    unsafe extern "C" fn on_fault(
+        signum: libc::c_int,
+        info: *mut libc::siginfo_t,
+        context: *mut c_void,
+    ) {
+        if record_access(fault_addr(info)) {
+            return;
+        }
+
+        let previous = match signum {
+            libc::SIGBUS => (*ptr::addr_of!(PREVIOUS_BUS)).as_ptr(),
+            _ => (*ptr::addr_of!(PREVIOUS_SEGV)).as_ptr(),
+        };
+        let previous = &*previous;
+
+        if previous.sa_flags & libc::SA_SIGINFO != 0 {
+            let handler: extern "C" fn(
+                libc::c_int,
+                *mut libc::siginfo_t,
+                *mut c_void,
+            ) = mem::transmute(previous.sa_sigaction);
+            handler(signum, info, context);
+        } else if previous.sa_sigaction == libc::SIG_DFL
+            || previous.sa_sigaction == libc::SIG_IGN
+        {
+            // the fault is raised again once this handler returns, this time
+            // with the previous action
+            libc::sigaction(signum, previous, ptr::null_mut());
+        } else {
+            let handler: extern "C" fn(libc::c_int) =
+                mem::transmute(previous.sa_sigaction);
+            handler(signum);
+        }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    unsafe fn fault_addr(info: *mut libc::siginfo_t) -> usize {
+        (*info).si_addr() as usize
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    unsafe fn fault_addr(info: *mut libc::siginfo_t) -> usize {
+        (*info).si_addr as usize
+    }
+}
+
+/// Accesses can't be observed on other platforms, so every page is taken to
+/// be accessed, and the pages written are found by comparing the memory with
+/// a copy taken when tracking started.
+#[cfg(not(unix))]
+mod imp {
+    use std::collections::BTreeMap;
+
+    use dallo::ModuleId;
+
+    use super::Accesses;
+    use crate::error::Error;
+
+    const PAGE_SIZE: usize = 4096;
+
+    #[derive(Debug, Default)]
+    pub struct AccessTracker {
+        memories: BTreeMap<ModuleId, Vec<u8>>,
+    }
+
+    impl AccessTracker {
+        pub fn is_tracking(&self, module_id: &ModuleId) -> bool {
+            self.memories.contains_key(module_id)
+        }
+
+        pub fn tracked(&self) -> Vec<ModuleId> {
+            self.memories.keys().copied().collect()
+        }
+
+        pub fn track(
+            &mut self,
+            module_id: ModuleId,
+            memory: &[u8],
+        ) -> Result<(), Error> {
+            self.memories.insert(module_id, memory.to_vec());
+            Ok(())
+        }
+
+        pub fn untrack(
+            &mut self,
+            module_id: &ModuleId,
+            memory: &[u8],
+        ) -> Option<Accesses> {
+            let before = self.memories.remove(module_id)?;
+
+            let empty = [0u8; PAGE_SIZE];
+            let written = (0..memory.len())
+                .step_by(PAGE_SIZE)
+                .filter(|ofs| {
+                    let end = memory.len().min(ofs + PAGE_SIZE);
+                    let page_before = match before.get(*ofs..end) {
+                        Some(page) => page,
+                        None => &empty[..end - ofs],
+                    };
+                    page_before != &memory[*ofs..end]
+                })
+                .collect();
+
+            let pages = before
+                .chunks(PAGE_SIZE)
+                .enumerate()
+                .map(|(index, page)| (index * PAGE_SIZE, page.to_vec()))
+                .collect();
+
+            Some(Accesses {
+                len: before.len(),
+                page_size: PAGE_SIZE,
+                pages,
+                written,
+            })
+        }
+
+        pub fn clear(&mut self) {
+            self.memories.clear();
+        }
+    }
+}
+
+impl Accesses {
+    /// Split the pages accessed and written into pages of the given size,
+    /// keyed by their index.
+    pub fn split(
+        self,
+        page_size: usize,
+    ) -> (BTreeMap<usize, Vec<u8>>, BTreeSet<usize>) {
+        let pages = self
+            .pages
+            .into_iter()
+            .flat_map(|(ofs, page)| {
+                page.chunks(page_size)
+                    .enumerate()
+                    .map(|(i, chunk)| (ofs / page_size + i, chunk.to_vec()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let page_len = self.page_size;
+        let written = self
+            .written
+            .into_iter()
+            .flat_map(|ofs| ofs / page_size..(ofs + page_len) / page_size)
+            .collect();
+
+        (pages, written)
+    }
+}
@@ -0,0 +1,74 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::io;
+
+/// How the operating system is advised to page in the memory of a module,
+/// which is mapped to a file in the storage path.
+///
+/// Advice is applied to the memory as it is when given, so it should be given
+/// again after the memory of the module grows. It has no effect on platforms
+/// other than Unix.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemoryAdvice {
+    /// Let the operating system page the memory in and out as it sees fit.
+    #[default]
+    Normal,
+    /// Read the memory in ahead of time, since it will be needed soon.
+    WillNeed,
+    /// Drop the memory from RAM, since it won't be needed soon. Its pages are
+    /// read back from the file when next touched.
+    DontNeed,
+    /// Keep the memory in RAM, never paging it out.
+    Lock,
+}
+
+/// Apply the given advice to a memory, replacing the previous one.
+#[cfg(unix)]
+pub(crate) fn apply(
+    memory: &[u8],
+    previous: MemoryAdvice,
+    advice: MemoryAdvice,
+) -> io::Result<()> {
+    if memory.is_empty() {
+        return Ok(());
+    }
+
+    let addr = memory.as_ptr() as *mut libc::c_void;
+    let len = memory.len();
+
+    let check = |ret: libc::c_int| match ret {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    };
+
+    if previous == MemoryAdvice::Lock && advice != MemoryAdvice::Lock {
+        check(unsafe { libc::munlock(addr, len) })?;
+    }
+
+    let ret = unsafe {
+        match advice {
+            MemoryAdvice::Normal => libc::madvise(addr, len, libc::MADV_NORMAL),
+            MemoryAdvice::WillNeed => {
+                libc::madvise(addr, len, libc::MADV_WILLNEED)
+            }
+            MemoryAdvice::DontNeed => {
+                libc::madvise(addr, len, libc::MADV_DONTNEED)
+            }
+            MemoryAdvice::Lock => libc::mlock(addr, len),
+        }
+    };
+    check(ret)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn apply(
+    _memory: &[u8],
+    _previous: MemoryAdvice,
+    _advice: MemoryAdvice,
+) -> io::Result<()> {
+    Ok(())
+}
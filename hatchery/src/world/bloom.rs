@@ -0,0 +1,83 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dallo::ModuleId;
+
+use crate::world::Event;
+
+const BLOOM_WORDS: usize = 32;
+const BLOOM_BITS: usize = BLOOM_WORDS * 64;
+const BLOOM_HASHES: usize = 3;
+
+/// A bloom filter over the modules that emitted events in a commit.
+///
+/// It can tell a module surely did not emit events, but may report that it
+/// did when it didn't.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventBloom {
+    words: [u64; BLOOM_WORDS],
+}
+
+impl Default for EventBloom {
+    fn default() -> Self {
+        EventBloom {
+            words: [0; BLOOM_WORDS],
+        }
+    }
+}
+
+impl EventBloom {
+    pub fn insert(&mut self, module_id: &ModuleId) {
+        for bit in bits(module_id) {
+            self.words[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    pub fn extend<'a, I>(&mut self, events: I)
+    where
+        I: IntoIterator<Item = &'a Event>,
+    {
+        for event in events {
+            self.insert(event.module_id());
+        }
+    }
+
+    pub fn union(&mut self, other: &EventBloom) {
+        for (word, other) in self.words.iter_mut().zip(other.words) {
+            *word |= other;
+        }
+    }
+
+    /// Return the words of the filter, for persisting it.
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Return the filter with the given words, or `None` if there are not as
+    /// many as a filter has.
+    pub fn from_words(words: &[u64]) -> Option<Self> {
+        Some(EventBloom {
+            words: words.try_into().ok()?,
+        })
+    }
+
+    /// Return false if the module surely emitted no events.
+    pub fn may_contain(&self, module_id: &ModuleId) -> bool {
+        bits(module_id).all(|bit| self.words[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+}
+
+/// The bits set in the filter by the given module.
+fn bits(module_id: &ModuleId) -> impl Iterator<Item = usize> {
+    let hash = blake3::hash(module_id.as_bytes());
+    let bytes = *hash.as_bytes();
+
+    (0..BLOOM_HASHES).map(move |i| {
+        let mut word = [0; 8];
+        word.copy_from_slice(&bytes[i * 8..][..8]);
+        u64::from_le_bytes(word) as usize % BLOOM_BITS
+    })
+}
@@ -0,0 +1,87 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+
+use dallo::ModuleId;
+
+use crate::world::{CommitId, Event, ModuleError};
+
+/// A query result kept around so that an identical query can be answered
+/// without executing the module again.
+#[derive(Debug, Clone)]
+pub struct CachedQuery {
+    pub ret: Vec<u8>,
+    pub events: Vec<Event>,
+    pub debug: Vec<String>,
+    pub reverts: Vec<ModuleError>,
+    pub spent: u64,
+    pub instructions: u64,
+}
+
+/// The key of a cached query, hashing the root of the state the query was
+/// made on, the module, the method name and the bytes of the serialized
+/// argument.
+pub type CacheKey = [u8; 32];
+
+/// Cache of query results keyed by the state they were computed on and the
+/// query made.
+///
+/// Since a query can read the state of any module through inter-contract
+/// calls, entries are keyed by the root of the state of all modules, so
+/// they are never served for a different state. The world still clears the
+/// cache whenever anything that could influence a query result happens, so
+/// that entries that can't be hit anymore don't pile up.
+#[derive(Debug, Default)]
+pub struct QueryCache {
+    enabled: bool,
+    entries: BTreeMap<CacheKey, CachedQuery>,
+}
+
+impl QueryCache {
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.entries.clear();
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Return the key of a query to the given module made on the state with
+    /// the given root.
+    pub fn key(
+        root: &CommitId,
+        module_id: &ModuleId,
+        name: &str,
+        arg: &[u8],
+    ) -> CacheKey {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(root.as_bytes());
+        hasher.update(module_id.as_bytes());
+        hasher.update(&(name.len() as u32).to_le_bytes());
+        hasher.update(name.as_bytes());
+        hasher.update(arg);
+        hasher.finalize().into()
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<&CachedQuery> {
+        self.entries.get(key)
+    }
+
+    pub fn insert(&mut self, key: CacheKey, query: CachedQuery) {
+        if self.enabled {
+            self.entries.insert(key, query);
+        }
+    }
+
+    /// Drop all cached results.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,292 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+
+use bytecheck::CheckBytes;
+use dallo::ModuleId;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::snapshot::SnapshotId;
+use crate::world::bloom::EventBloom;
+
+/// The root of the state of all modules at the time of a commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CommitId([u8; 32]);
+
+impl CommitId {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Compute the id of the commit of the given modules, each with the hash
+    /// of its memory.
+    ///
+    /// The modules are put in canonical order - sorted by module id - before
+    /// hashing, so any order gives the same id. Together with
+    /// [`World::canonical_modules`] this lets external verifiers reproduce
+    /// the root of a commit.
+    ///
+    /// [`World::canonical_modules`]: crate::World::canonical_modules
+    pub fn compute<I>(modules: I) -> Self
+    where
+        I: IntoIterator<Item = (ModuleId, [u8; 32])>,
+    {
+        let mut commit = Commit::default();
+        for (module_id, memory_hash) in modules {
+            commit.insert(module_id, SnapshotId::from(memory_hash));
+        }
+        commit.id()
+    }
+}
+
+impl From<[u8; 32]> for CommitId {
+    fn from(bytes: [u8; 32]) -> Self {
+        CommitId(bytes)
+    }
+}
+
+/// The id of the snapshot of a module in a commit, which is the hash of the
+/// state of the module.
+///
+/// It can be archived, so the modules of a commit - as returned by
+/// [`World::commit_modules`] - can be handed to other nodes or tools.
+///
+/// [`World::commit_modules`]: crate::World::commit_modules
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Archive,
+    Serialize,
+    Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+#[repr(C)]
+pub struct ModuleSnapshotId([u8; 32]);
+
+impl ModuleSnapshotId {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for ModuleSnapshotId {
+    fn from(bytes: [u8; 32]) -> Self {
+        ModuleSnapshotId(bytes)
+    }
+}
+
+impl From<ModuleSnapshotId> for [u8; 32] {
+    fn from(snapshot_id: ModuleSnapshotId) -> Self {
+        snapshot_id.0
+    }
+}
+
+impl From<SnapshotId> for ModuleSnapshotId {
+    fn from(snapshot_id: SnapshotId) -> Self {
+        ModuleSnapshotId(snapshot_id.into())
+    }
+}
+
+/// A successful commit, as passed to the hooks registered using
+/// [`World::on_commit`].
+///
+/// [`World::on_commit`]: crate::World::on_commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitInfo {
+    id: CommitId,
+    height: u64,
+    timestamp: u64,
+}
+
+impl CommitInfo {
+    pub(crate) fn new(id: CommitId, height: u64, timestamp: u64) -> Self {
+        CommitInfo {
+            id,
+            height,
+            timestamp,
+        }
+    }
+
+    /// Return the id of the commit.
+    pub fn id(&self) -> CommitId {
+        self.id
+    }
+
+    /// Return the block height at the time of the commit.
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Return the timestamp at the time of the commit.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+}
+
+/// The snapshots of all modules taken during a commit.
+///
+/// The commit id is the root of a binary merkle tree whose leaves are the
+/// hashes of each module id together with its snapshot id, ordered by module
+/// id. This canonical ordering is enforced by keeping the snapshots sorted,
+/// regardless of the order modules were deployed or snapshotted in. Nodes are
+/// the hash of the concatenation of their children, and a node
+/// without a sibling is carried up a level unchanged. Leaves and nodes are
+/// hashed under different tags, so one can't be passed off as the other.
+///
+/// The modules that emitted events in the transactions leading up to the
+/// commit are kept in a bloom filter, which has no bearing on the id.
+#[derive(Debug, Clone, Default)]
+pub struct Commit {
+    snapshots: BTreeMap<ModuleId, SnapshotId>,
+    events: EventBloom,
+}
+
+impl Commit {
+    pub fn insert(&mut self, module_id: ModuleId, snapshot_id: SnapshotId) {
+        self.snapshots.insert(module_id, snapshot_id);
+    }
+
+    pub fn remove(&mut self, module_id: &ModuleId) {
+        self.snapshots.remove(module_id);
+    }
+
+    pub fn snapshot_id(&self, module_id: &ModuleId) -> Option<SnapshotId> {
+        self.snapshots.get(module_id).copied()
+    }
+
+    /// Return the snapshots in the commit, ordered by module id.
+    pub fn snapshots(
+        &self,
+    ) -> impl Iterator<Item = (&ModuleId, &SnapshotId)> + '_ {
+        self.snapshots.iter()
+    }
+
+    pub fn events(&self) -> &EventBloom {
+        &self.events
+    }
+
+    pub fn events_mut(&mut self) -> &mut EventBloom {
+        &mut self.events
+    }
+
+    pub fn id(&self) -> CommitId {
+        let mut level = self.leaves();
+
+        if level.is_empty() {
+            return CommitId([0; 32]);
+        }
+
+        while level.len() > 1 {
+            level = level.chunks(2).map(parent).collect();
+        }
+
+        CommitId(level[0])
+    }
+
+    /// Produce the proof of inclusion of the snapshot of the given module.
+    pub fn proof(&self, module_id: &ModuleId) -> Option<StateProof> {
+        let snapshot_id = self.snapshot_id(module_id)?;
+        let mut index = self.snapshots.keys().position(|id| id == module_id)?;
+
+        let mut path = Vec::new();
+        let mut level = self.leaves();
+
+        while level.len() > 1 {
+            let sibling = index ^ 1;
+            if sibling < level.len() {
+                path.push((level[sibling], sibling < index));
+            }
+
+            level = level.chunks(2).map(parent).collect();
+            index /= 2;
+        }
+
+        Some(StateProof {
+            module_id: *module_id,
+            snapshot_id,
+            path,
+        })
+    }
+
+    fn leaves(&self) -> Vec<[u8; 32]> {
+        self.snapshots
+            .iter()
+            .map(|(module_id, snapshot_id)| leaf(module_id, snapshot_id))
+            .collect()
+    }
+}
+
+/// Prefixes of the hashes of leaves and nodes of the merkle tree.
+const LEAF_TAG: u8 = 0;
+const NODE_TAG: u8 = 1;
+
+fn leaf(module_id: &ModuleId, snapshot_id: &SnapshotId) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[LEAF_TAG]);
+    hasher.update(module_id.as_bytes());
+    hasher.update(snapshot_id.as_bytes());
+    hasher.finalize().into()
+}
+
+fn parent(children: &[[u8; 32]]) -> [u8; 32] {
+    match children {
+        [left, right] => {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update(&[NODE_TAG]);
+            hasher.update(left);
+            hasher.update(right);
+            hasher.finalize().into()
+        }
+        [single] => *single,
+        _ => unreachable!("nodes have one or two children"),
+    }
+}
+
+/// Proof that the memory of a module is part of a commit.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StateProof {
+    module_id: ModuleId,
+    snapshot_id: SnapshotId,
+    path: Vec<([u8; 32], bool)>,
+}
+
+impl StateProof {
+    /// Return the id of the module the proof is for.
+    pub fn module_id(&self) -> &ModuleId {
+        &self.module_id
+    }
+
+    /// Return the hash of the memory of the module in the commit.
+    pub fn memory_hash(&self) -> [u8; 32] {
+        self.snapshot_id.into()
+    }
+
+    /// Verify that the given memory is the state of the module in the commit
+    /// with the given id.
+    pub fn verify(&self, commit_id: &CommitId, memory: &[u8]) -> bool {
+        let snapshot_id = SnapshotId::from(*blake3::hash(memory).as_bytes());
+        if snapshot_id != self.snapshot_id {
+            return false;
+        }
+
+        let mut node = leaf(&self.module_id, &self.snapshot_id);
+        for (sibling, is_left) in &self.path {
+            node = match is_left {
+                true => parent(&[*sibling, node]),
+                false => parent(&[node, *sibling]),
+            };
+        }
+
+        node == commit_id.0
+    }
+}
@@ -0,0 +1,75 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use bytecheck::CheckBytes;
+use dallo::{AbiType, ModuleId, StandardBufSerializer};
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{Archive, Deserialize, Infallible, Serialize};
+
+use crate::error::Error;
+use crate::world::{Receipt, World};
+
+/// A handle to a module deployed in a [`World`], performing queries and
+/// transactions on it without repeating its id.
+///
+/// Returned by [`World::contract`].
+#[derive(Debug)]
+pub struct ContractRef<'w> {
+    world: &'w mut World,
+    module_id: ModuleId,
+}
+
+impl<'w> ContractRef<'w> {
+    pub(crate) fn new(world: &'w mut World, module_id: ModuleId) -> Self {
+        Self { world, module_id }
+    }
+
+    /// Return the id of the module.
+    pub fn module_id(&self) -> ModuleId {
+        self.module_id
+    }
+
+    /// Perform a query on the module, as with [`World::query`].
+    pub fn query<Arg, Ret>(
+        &self,
+        name: &str,
+        arg: Arg,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        Arg: AbiType + for<'a> Serialize<StandardBufSerializer<'a>>,
+        Ret: Archive,
+        Ret::Archived: Deserialize<Ret, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        self.world.query(self.module_id, name, arg)
+    }
+
+    /// Perform a transaction on the module, as with [`World::transact`].
+    pub fn transact<Arg, Ret>(
+        &mut self,
+        name: &str,
+        arg: Arg,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        Arg: AbiType
+            + for<'a> Serialize<StandardBufSerializer<'a>>
+            + core::fmt::Debug,
+        Ret: Archive,
+        Ret::Archived: Deserialize<Ret, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        self.world.transact(self.module_id, name, arg)
+    }
+
+    /// Look up the given methods of the module ahead of time, so that the
+    /// first calls to them don't have to.
+    ///
+    /// Methods are otherwise looked up the first time they're called, and
+    /// kept for as long as the module is loaded.
+    pub fn prepare(&self, methods: &[&str]) -> Result<(), Error> {
+        self.world.prepare_methods(self.module_id, methods)
+    }
+}
@@ -0,0 +1,207 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use dallo::ModuleId;
+use loupe::MemoryUsage;
+use wasmer::wasmparser::Operator;
+use wasmer::{
+    ExportIndex, FunctionMiddleware, GlobalInit, LocalFunctionIndex,
+    MiddlewareError, MiddlewareReaderState, ModuleMiddleware, Val,
+};
+use wasmer_types::{GlobalType, ModuleInfo, Mutability, Type};
+
+/// Prefix of the exported globals counting the calls to each function.
+const COVERAGE_PREFIX: &str = "hatchery_coverage_";
+
+/// Instruments each function of a module to count how many times it was
+/// called, in a global exported as [`COVERAGE_PREFIX`] followed by the index
+/// of the function among the ones defined by the module.
+///
+/// As with metering, an instance of the middleware can only be used to
+/// compile a single module.
+#[derive(Debug, Default, MemoryUsage)]
+pub struct Coverage {
+    #[loupe(skip)]
+    first_global: Mutex<Option<u32>>,
+}
+
+impl ModuleMiddleware for Coverage {
+    fn generate_function_middleware(
+        &self,
+        local_function_index: LocalFunctionIndex,
+    ) -> Box<dyn FunctionMiddleware> {
+        let first_global = self
+            .first_global
+            .lock()
+            .unwrap()
+            .expect("Module info should be transformed first");
+
+        Box::new(FunctionCoverage {
+            global_index: first_global + local_function_index.as_u32(),
+            entered: false,
+        })
+    }
+
+    fn transform_module_info(&self, module_info: &mut ModuleInfo) {
+        let mut first_global = self.first_global.lock().unwrap();
+        if first_global.is_some() {
+            panic!("Coverage middleware used to compile multiple modules");
+        }
+
+        let n_local =
+            module_info.functions.len() - module_info.num_imported_functions;
+
+        for index in 0..n_local {
+            let global_index = module_info
+                .globals
+                .push(GlobalType::new(Type::I64, Mutability::Var));
+            module_info
+                .global_initializers
+                .push(GlobalInit::I64Const(0));
+            module_info.exports.insert(
+                format!("{}{}", COVERAGE_PREFIX, index),
+                ExportIndex::Global(global_index),
+            );
+
+            if index == 0 {
+                *first_global = Some(global_index.as_u32());
+            }
+        }
+
+        first_global.get_or_insert(0);
+    }
+}
+
+#[derive(Debug)]
+struct FunctionCoverage {
+    global_index: u32,
+    entered: bool,
+}
+
+impl FunctionMiddleware for FunctionCoverage {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        if !self.entered {
+            self.entered = true;
+            state.extend(&[
+                Operator::GlobalGet {
+                    global_index: self.global_index,
+                },
+                Operator::I64Const { value: 1 },
+                Operator::I64Add,
+                Operator::GlobalSet {
+                    global_index: self.global_index,
+                },
+            ]);
+        }
+
+        state.push_operator(operator);
+        Ok(())
+    }
+}
+
+/// How many times a function of a module was called.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FunctionHits {
+    /// Index of the function among the ones defined by the module.
+    pub index: u32,
+    /// Name of the function in the name section, if present.
+    pub name: Option<String>,
+    pub hits: u64,
+}
+
+/// Read the call counters of an instance of a module compiled with
+/// [`Coverage`], returning `None` if it was compiled without it.
+pub fn function_hits(instance: &wasmer::Instance) -> Option<Vec<FunctionHits>> {
+    let info = instance.module().info();
+    let n_local = info.functions.len() - info.num_imported_functions;
+
+    let mut functions = Vec::with_capacity(n_local);
+
+    for index in 0..n_local {
+        let export = format!("{}{}", COVERAGE_PREFIX, index);
+        let hits = match instance.exports.get_global(&export).ok()?.get() {
+            Val::I64(hits) => hits as u64,
+            _ => return None,
+        };
+
+        let func_index =
+            info.func_index(LocalFunctionIndex::from_u32(index as u32));
+        let name = info.function_names.get(&func_index).cloned();
+
+        functions.push(FunctionHits {
+            index: index as u32,
+            name,
+            hits,
+        });
+    }
+
+    Some(functions)
+}
+
+/// The functions called in the modules deployed with coverage enabled.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoverageReport {
+    modules: BTreeMap<ModuleId, Vec<FunctionHits>>,
+}
+
+impl CoverageReport {
+    pub(crate) fn insert(
+        &mut self,
+        module_id: ModuleId,
+        functions: Vec<FunctionHits>,
+    ) {
+        self.modules.insert(module_id, functions);
+    }
+
+    /// Return the functions of each module with their call counts.
+    pub fn modules(&self) -> &BTreeMap<ModuleId, Vec<FunctionHits>> {
+        &self.modules
+    }
+
+    /// Render the report in the lcov tracefile format.
+    ///
+    /// Each module is a source file named after the hex encoding of its id,
+    /// and functions are numbered by their index in place of a line. Unnamed
+    /// functions are called `func<index>`.
+    pub fn to_lcov(&self) -> String {
+        let mut lcov = String::new();
+
+        for (module_id, functions) in &self.modules {
+            let name = |f: &FunctionHits| match &f.name {
+                Some(name) => name.clone(),
+                None => format!("func{}", f.index),
+            };
+
+            lcov.push_str("TN:\nSF:");
+            for byte in module_id.as_bytes() {
+                let _ = write!(lcov, "{:02x}", byte);
+            }
+            lcov.push('\n');
+
+            for f in functions {
+                let _ = writeln!(lcov, "FN:{},{}", f.index, name(f));
+            }
+            for f in functions {
+                let _ = writeln!(lcov, "FNDA:{},{}", f.hits, name(f));
+            }
+
+            let hit = functions.iter().filter(|f| f.hits > 0).count();
+            let _ = writeln!(lcov, "FNF:{}", functions.len());
+            let _ = writeln!(lcov, "FNH:{}", hit);
+            lcov.push_str("end_of_record\n");
+        }
+
+        lcov
+    }
+}
@@ -0,0 +1,50 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::time::Duration;
+
+use dallo::ModuleId;
+
+use crate::world::WasmFeatures;
+
+/// The receipt of a deploy, with what it cost to bring the module in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeployReceipt {
+    pub(super) module_id: ModuleId,
+    pub(super) compile_time: Duration,
+    pub(super) instantiate_time: Duration,
+    pub(super) artifact_size: Option<usize>,
+    pub(super) validated_features: WasmFeatures,
+}
+
+impl DeployReceipt {
+    /// Return the id of the module deployed.
+    pub fn module_id(&self) -> ModuleId {
+        self.module_id
+    }
+
+    /// Return how long the bytecode took to validate and compile.
+    pub fn compile_time(&self) -> Duration {
+        self.compile_time
+    }
+
+    /// Return how long the compiled module took to instantiate, including
+    /// running the declaration of its ABI.
+    pub fn instantiate_time(&self) -> Duration {
+        self.instantiate_time
+    }
+
+    /// Return the size in bytes of the compiled module, as serialized, or
+    /// `None` if it could not be serialized.
+    pub fn artifact_size(&self) -> Option<usize> {
+        self.artifact_size
+    }
+
+    /// Return the WebAssembly proposals the bytecode was validated against.
+    pub fn validated_features(&self) -> WasmFeatures {
+        self.validated_features
+    }
+}
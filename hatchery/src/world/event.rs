@@ -4,9 +4,16 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use dallo::ModuleId;
+use bytecheck::CheckBytes;
+use dallo::{ModuleId, RawResult};
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{AlignedVec, Archive, Deserialize, Infallible};
+use std::fmt::{self, Debug, Formatter};
 use std::ops::Deref;
 
+use crate::error::Error;
+use crate::world::{CallFailure, ModuleError, Witness};
+
 /// The receipt of a query or transaction, containing the return and the events
 /// emitted.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -15,6 +22,17 @@ pub struct Receipt<T> {
     events: Vec<Event>,
     debug: Vec<String>,
     spent: u64,
+    limit: u64,
+    instructions: u64,
+    ret_len: usize,
+    height: u64,
+    timestamp: u64,
+    deferred: Vec<Receipt<RawResult>>,
+    failures: Vec<CallFailure>,
+    witness: Option<Witness>,
+    reverts: Vec<ModuleError>,
+    sponsor: Option<Vec<u8>>,
+    sponsored: u64,
 }
 
 impl<T> Receipt<T> {
@@ -29,9 +47,74 @@ impl<T> Receipt<T> {
             events,
             spent,
             debug,
+            limit: 0,
+            instructions: 0,
+            ret_len: 0,
+            height: 0,
+            timestamp: 0,
+            deferred: vec![],
+            failures: vec![],
+            witness: None,
+            reverts: vec![],
+            sponsor: None,
+            sponsored: 0,
         }
     }
 
+    pub(crate) fn with_limit(mut self, limit: u64) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub(crate) fn with_instructions(mut self, instructions: u64) -> Self {
+        self.instructions = instructions;
+        self
+    }
+
+    pub(crate) fn with_ret_len(mut self, ret_len: u32) -> Self {
+        self.ret_len = ret_len as usize;
+        self
+    }
+
+    pub(crate) fn with_context(mut self, height: u64, timestamp: u64) -> Self {
+        self.height = height;
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub(crate) fn with_witness(mut self, witness: Option<Witness>) -> Self {
+        self.witness = witness;
+        self
+    }
+
+    pub(crate) fn with_reverts(mut self, reverts: Vec<ModuleError>) -> Self {
+        self.reverts = reverts;
+        self
+    }
+
+    pub(crate) fn with_sponsor(
+        mut self,
+        sponsor: Vec<u8>,
+        sponsored: u64,
+    ) -> Self {
+        self.sponsor = Some(sponsor);
+        self.sponsored = sponsored;
+        self
+    }
+
+    pub(crate) fn with_deferred(
+        mut self,
+        deferred: Vec<Receipt<RawResult>>,
+    ) -> Self {
+        self.deferred = deferred;
+        self
+    }
+
+    pub(crate) fn with_failures(mut self, failures: Vec<CallFailure>) -> Self {
+        self.failures = failures;
+        self
+    }
+
     /// Get the return of the query or transaction.
     pub fn ret(&self) -> &T {
         &self.ret
@@ -52,10 +135,169 @@ impl<T> Receipt<T> {
         self.spent
     }
 
+    /// Return the point limit the call was given.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Return the number of instructions executed by the call, regardless of
+    /// how many points each of them costs.
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    /// Return the length of the serialized return, as written by the module
+    /// in its argument buffer.
+    pub fn ret_len(&self) -> usize {
+        self.ret_len
+    }
+
+    /// Return the height available to modules during the call.
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Return the timestamp available to modules during the call.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Return the receipts of the transactions deferred by the call, in the
+    /// order they were performed.
+    pub fn deferred(&self) -> &[Receipt<RawResult>] {
+        &self.deferred
+    }
+
+    /// Return the failures of the transactions deferred by the call, in the
+    /// order they were performed.
+    ///
+    /// A failed deferred transaction is rolled back, together with the
+    /// transactions it deferred, without failing the call or the other
+    /// transactions it deferred.
+    pub fn failures(&self) -> &[CallFailure] {
+        &self.failures
+    }
+
+    /// Return the witness of the call, if witnesses are enabled.
+    pub fn witness(&self) -> Option<&Witness> {
+        self.witness.as_ref()
+    }
+
+    /// Return the errors of the calls that reverted and were handled by their
+    /// callers using `try_query` or `try_transact`, in the order they
+    /// reverted.
+    pub fn reverts(&self) -> &[ModuleError] {
+        &self.reverts
+    }
+
+    /// Return the identity of the sponsor of the transaction, if it had one.
+    pub fn sponsor(&self) -> Option<&[u8]> {
+        self.sponsor.as_deref()
+    }
+
+    /// Return the points charged to the sponsor of the transaction, including
+    /// for the transactions it deferred.
+    pub fn sponsored(&self) -> u64 {
+        self.sponsored
+    }
+
+    /// Return the points charged to the transaction itself, including for the
+    /// transactions it deferred, once the sponsor paid its share.
+    pub fn charged(&self) -> u64 {
+        self.total_spent() - self.sponsored
+    }
+
+    /// Return the points spent by the call and by the transactions it
+    /// deferred, including the ones that failed.
+    pub fn total_spent(&self) -> u64 {
+        self.spent
+            + self.deferred.iter().map(Receipt::spent).sum::<u64>()
+            + self.failures.iter().map(CallFailure::spent).sum::<u64>()
+    }
+
     /// Convert into result
     pub fn into_inner(self) -> T {
         self.ret
     }
+
+    /// Convert the return using the given function, keeping the rest of the
+    /// receipt.
+    pub fn map<U, F>(self, f: F) -> Receipt<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Receipt {
+            ret: f(self.ret),
+            events: self.events,
+            debug: self.debug,
+            spent: self.spent,
+            limit: self.limit,
+            instructions: self.instructions,
+            ret_len: self.ret_len,
+            height: self.height,
+            timestamp: self.timestamp,
+            deferred: self.deferred,
+            failures: self.failures,
+            witness: self.witness,
+            reverts: self.reverts,
+            sponsor: self.sponsor,
+            sponsored: self.sponsored,
+        }
+    }
+
+    /// Borrow the return, cloning the rest of the receipt.
+    pub fn as_ref(&self) -> Receipt<&T> {
+        Receipt {
+            ret: &self.ret,
+            events: self.events.clone(),
+            debug: self.debug.clone(),
+            spent: self.spent,
+            limit: self.limit,
+            instructions: self.instructions,
+            ret_len: self.ret_len,
+            height: self.height,
+            timestamp: self.timestamp,
+            deferred: self.deferred.clone(),
+            failures: self.failures.clone(),
+            witness: self.witness.clone(),
+            reverts: self.reverts.clone(),
+            sponsor: self.sponsor.clone(),
+            sponsored: self.sponsored,
+        }
+    }
+
+    /// Return the data of the events emitted by the given module,
+    /// deserialized as `D`, in the order they were emitted.
+    ///
+    /// Events whose data is not a valid archived `D` yield
+    /// [`Error::ValidationError`].
+    pub fn events_of<'r, D>(
+        &'r self,
+        module_id: ModuleId,
+    ) -> impl Iterator<Item = Result<D, Error>> + 'r
+    where
+        D: Archive + 'r,
+        D::Archived: Deserialize<D, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        self.events
+            .iter()
+            .filter(move |event| event.module_id == module_id)
+            .map(Event::decode)
+    }
+
+    /// Split into the return, the events emitted, and the points spent.
+    pub fn split(self) -> (T, Vec<Event>, u64) {
+        (self.ret, self.events, self.spent)
+    }
+
+    /// Iterate over the events emitted by the call, followed by the ones
+    /// emitted by the transactions it deferred.
+    pub(crate) fn all_events(&self) -> impl Iterator<Item = &Event> {
+        self.events
+            .iter()
+            .chain(self.deferred.iter().flat_map(|r| r.events.iter()))
+    }
 }
 
 impl<T> Deref for Receipt<T> {
@@ -66,16 +308,47 @@ impl<T> Deref for Receipt<T> {
     }
 }
 
-/// An event emitted by a module.
+impl<'a, T> IntoIterator for &'a Receipt<T> {
+    type Item = &'a Event;
+    type IntoIter = std::slice::Iter<'a, Event>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.events.iter()
+    }
+}
+
+impl<T> IntoIterator for Receipt<T> {
+    type Item = Event;
+    type IntoIter = std::vec::IntoIter<Event>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.events.into_iter()
+    }
+}
+
+/// An event emitted by a module, together with the call frame it was
+/// emitted from.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Event {
     module_id: ModuleId,
+    callers: Vec<ModuleId>,
+    method: String,
     data: Vec<u8>,
 }
 
 impl Event {
-    pub(crate) fn new(module_id: ModuleId, data: Vec<u8>) -> Self {
-        Self { module_id, data }
+    pub(crate) fn new(
+        module_id: ModuleId,
+        callers: Vec<ModuleId>,
+        method: String,
+        data: Vec<u8>,
+    ) -> Self {
+        Self {
+            module_id,
+            callers,
+            method,
+            data,
+        }
     }
 
     /// Return the id of the module that emitted this event.
@@ -83,8 +356,78 @@ impl Event {
         &self.module_id
     }
 
+    /// Return the modules that called the emitting one, from the one the
+    /// call was made to, to its direct caller.
+    ///
+    /// This is empty for events emitted directly by the module called.
+    pub fn callers(&self) -> &[ModuleId] {
+        &self.callers
+    }
+
+    /// Return the index of the frame the event was emitted from, with the
+    /// frame of the module called being zero.
+    pub fn frame(&self) -> usize {
+        self.callers.len()
+    }
+
+    /// Return the name of the method that emitted this event.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
     /// Return data contained with the event
     pub fn data(&self) -> &[u8] {
         &self.data
     }
+
+    /// Deserialize the data contained with the event as `D`.
+    pub fn decode<D>(&self) -> Result<D, Error>
+    where
+        D: Archive,
+        D::Archived: Deserialize<D, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(&self.data);
+
+        let archived = rkyv::check_archived_root::<D>(&bytes[..])
+            .map_err(|_| Error::ValidationError)?;
+
+        Ok(archived.deserialize(&mut Infallible).expect("Infallible"))
+    }
+}
+
+type EventFilter = Box<dyn Fn(&Event) -> bool + Send>;
+type EventCallback = Box<dyn FnMut(&Event) + Send>;
+
+/// A callback registered by the embedder, called with the events that pass
+/// its filter.
+pub(crate) struct Observer {
+    filter: EventFilter,
+    callback: EventCallback,
+}
+
+impl Observer {
+    pub(crate) fn new<F, C>(filter: F, callback: C) -> Self
+    where
+        F: 'static + Fn(&Event) -> bool + Send,
+        C: 'static + FnMut(&Event) + Send,
+    {
+        Self {
+            filter: Box::new(filter),
+            callback: Box::new(callback),
+        }
+    }
+
+    pub(crate) fn observe(&mut self, event: &Event) {
+        if (self.filter)(event) {
+            (self.callback)(event)
+        }
+    }
+}
+
+impl Debug for Observer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Observer").finish_non_exhaustive()
+    }
 }
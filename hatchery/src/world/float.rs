@@ -0,0 +1,215 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use wasmer::wasmparser::{BinaryReaderError, Operator, Parser, Payload};
+use wasmer::CompileError;
+
+use crate::error::Error;
+
+/// Whether modules are allowed to use floating point operations.
+///
+/// The bit patterns of NaNs produced by floating point arithmetic differ
+/// across platforms, and could make the state of a module diverge between
+/// nodes. Rejecting floats guarantees they can't reach the state.
+///
+/// Both scalar and vector operations on floats are rejected, while vector
+/// operations on integers are still allowed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FloatPolicy {
+    #[default]
+    Allow,
+    Reject,
+}
+
+impl FloatPolicy {
+    /// Check the given bytecode complies with the policy.
+    ///
+    /// When floats are rejected, the bytecode must be in the binary format.
+    pub fn check(self, bytecode: &[u8]) -> Result<(), Error> {
+        match self {
+            FloatPolicy::Allow => Ok(()),
+            FloatPolicy::Reject => reject_floats(bytecode).map_err(|msg| {
+                Error::CompileError(CompileError::Validate(msg))
+            }),
+        }
+    }
+}
+
+fn reject_floats(bytecode: &[u8]) -> Result<(), String> {
+    let to_string = |err: BinaryReaderError| err.to_string();
+
+    for payload in Parser::new(0).parse_all(bytecode) {
+        if let Payload::CodeSectionEntry(body) = payload.map_err(to_string)? {
+            let mut reader = body.get_operators_reader().map_err(to_string)?;
+            while !reader.eof() {
+                let (op, offset) =
+                    reader.read_with_offset().map_err(to_string)?;
+                if is_float(&op) {
+                    return Err(format!(
+                        "floating point operation {:?} at offset {}",
+                        op, offset
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn is_float(op: &Operator) -> bool {
+    is_scalar_float(op) || is_vector_float(op)
+}
+
+fn is_scalar_float(op: &Operator) -> bool {
+    use Operator::*;
+
+    matches!(
+        op,
+        F32Load { .. }
+            | F64Load { .. }
+            | F32Store { .. }
+            | F64Store { .. }
+            | F32Const { .. }
+            | F64Const { .. }
+            | F32Eq
+            | F32Ne
+            | F32Lt
+            | F32Gt
+            | F32Le
+            | F32Ge
+            | F64Eq
+            | F64Ne
+            | F64Lt
+            | F64Gt
+            | F64Le
+            | F64Ge
+            | F32Abs
+            | F32Neg
+            | F32Ceil
+            | F32Floor
+            | F32Trunc
+            | F32Nearest
+            | F32Sqrt
+            | F32Add
+            | F32Sub
+            | F32Mul
+            | F32Div
+            | F32Min
+            | F32Max
+            | F32Copysign
+            | F64Abs
+            | F64Neg
+            | F64Ceil
+            | F64Floor
+            | F64Trunc
+            | F64Nearest
+            | F64Sqrt
+            | F64Add
+            | F64Sub
+            | F64Mul
+            | F64Div
+            | F64Min
+            | F64Max
+            | F64Copysign
+            | I32TruncF32S
+            | I32TruncF32U
+            | I32TruncF64S
+            | I32TruncF64U
+            | I64TruncF32S
+            | I64TruncF32U
+            | I64TruncF64S
+            | I64TruncF64U
+            | F32ConvertI32S
+            | F32ConvertI32U
+            | F32ConvertI64S
+            | F32ConvertI64U
+            | F32DemoteF64
+            | F64ConvertI32S
+            | F64ConvertI32U
+            | F64ConvertI64S
+            | F64ConvertI64U
+            | F64PromoteF32
+            | I32ReinterpretF32
+            | I64ReinterpretF64
+            | F32ReinterpretI32
+            | F64ReinterpretI64
+            | I32TruncSatF32S
+            | I32TruncSatF32U
+            | I32TruncSatF64S
+            | I32TruncSatF64U
+            | I64TruncSatF32S
+            | I64TruncSatF32U
+            | I64TruncSatF64S
+            | I64TruncSatF64U
+    )
+}
+
+fn is_vector_float(op: &Operator) -> bool {
+    use Operator::*;
+
+    matches!(
+        op,
+        F32x4Splat
+            | F64x2Splat
+            | F32x4ExtractLane { .. }
+            | F32x4ReplaceLane { .. }
+            | F64x2ExtractLane { .. }
+            | F64x2ReplaceLane { .. }
+            | F32x4Eq
+            | F32x4Ne
+            | F32x4Lt
+            | F32x4Gt
+            | F32x4Le
+            | F32x4Ge
+            | F64x2Eq
+            | F64x2Ne
+            | F64x2Lt
+            | F64x2Gt
+            | F64x2Le
+            | F64x2Ge
+            | F32x4Ceil
+            | F32x4Floor
+            | F32x4Trunc
+            | F32x4Nearest
+            | F32x4Abs
+            | F32x4Neg
+            | F32x4Sqrt
+            | F32x4Add
+            | F32x4Sub
+            | F32x4Mul
+            | F32x4Div
+            | F32x4Min
+            | F32x4Max
+            | F32x4PMin
+            | F32x4PMax
+            | F64x2Ceil
+            | F64x2Floor
+            | F64x2Trunc
+            | F64x2Nearest
+            | F64x2Abs
+            | F64x2Neg
+            | F64x2Sqrt
+            | F64x2Add
+            | F64x2Sub
+            | F64x2Mul
+            | F64x2Div
+            | F64x2Min
+            | F64x2Max
+            | F64x2PMin
+            | F64x2PMax
+            | I32x4TruncSatF32x4S
+            | I32x4TruncSatF32x4U
+            | F32x4ConvertI32x4S
+            | F32x4ConvertI32x4U
+            | I32x4TruncSatF64x2SZero
+            | I32x4TruncSatF64x2UZero
+            | F64x2ConvertLowI32x4S
+            | F64x2ConvertLowI32x4U
+            | F32x4DemoteF64x2Zero
+            | F64x2PromoteLowF32x4
+    )
+}
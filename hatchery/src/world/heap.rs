@@ -0,0 +1,96 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+
+use dallo::ModuleId;
+
+/// The growth of the heap of a module during a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HeapGrowth {
+    /// Index of the transaction, counting from the creation of the world.
+    pub transaction: u64,
+    /// Method called by the transaction.
+    pub method: String,
+    /// Module whose heap grew.
+    pub module_id: ModuleId,
+    /// Number of bytes the heap grew by.
+    pub bytes: usize,
+}
+
+/// Heap usage of the modules over the life of a world.
+///
+/// Since memory allocated by modules is never freed, a module whose heap
+/// keeps growing with each transaction is likely leaking memory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeapReport {
+    high_water_marks: BTreeMap<ModuleId, usize>,
+    growth: Vec<HeapGrowth>,
+}
+
+impl HeapReport {
+    /// Return the highest the top of the heap of each module has been.
+    pub fn high_water_marks(&self) -> &BTreeMap<ModuleId, usize> {
+        &self.high_water_marks
+    }
+
+    /// Return every growth of a heap during a transaction, in the order
+    /// they happened.
+    pub fn growth(&self) -> &[HeapGrowth] {
+        &self.growth
+    }
+}
+
+/// Keeps track of the heaps of the modules entered during a transaction.
+#[derive(Debug, Clone, Default)]
+pub struct HeapTracker {
+    report: HeapReport,
+    transactions: u64,
+    entered: BTreeMap<ModuleId, usize>,
+}
+
+impl HeapTracker {
+    pub fn report(&self) -> &HeapReport {
+        &self.report
+    }
+
+    pub fn clear(&mut self) {
+        self.entered.clear();
+    }
+
+    /// Record the top of the heap of a module, if it was not yet entered
+    /// during the call.
+    pub fn enter(&mut self, module_id: ModuleId, heap_top: usize) {
+        self.entered.entry(module_id).or_insert(heap_top);
+    }
+
+    /// Record the growth of the heaps of the modules entered during a
+    /// transaction, given their current tops.
+    pub fn finish<F>(&mut self, method: &str, heap_top: F)
+    where
+        F: Fn(ModuleId) -> usize,
+    {
+        let transaction = self.transactions;
+        self.transactions += 1;
+
+        for (module_id, before) in std::mem::take(&mut self.entered) {
+            let after = heap_top(module_id);
+
+            let mark =
+                self.report.high_water_marks.entry(module_id).or_default();
+            *mark = (*mark).max(after);
+
+            if after > before {
+                self.report.growth.push(HeapGrowth {
+                    transaction,
+                    method: String::from(method),
+                    module_id,
+                    bytes: after - before,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::mem;
+use std::path::Path;
+
+use dallo::ModuleId;
+use rkyv::{AlignedVec, Deserialize, Infallible};
+
+use crate::error::Error;
+use crate::world::CommitId;
+use crate::Error::PersistenceError;
+
+type Entry = (ModuleId, String, [u8; 32], [u8; 32]);
+type Record = ([u8; 32], Vec<Entry>);
+
+/// A transaction performed on a module, as recorded in its call history.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CallRecord {
+    method: String,
+    arg_hash: [u8; 32],
+    tx_hash: [u8; 32],
+    commit: CommitId,
+}
+
+impl CallRecord {
+    /// Return the name of the method called.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Return the hash of the serialized argument of the call.
+    pub fn arg_hash(&self) -> &[u8; 32] {
+        &self.arg_hash
+    }
+
+    /// Return the hash of the transaction, committing to the module, the
+    /// method, and the argument.
+    pub fn tx_hash(&self) -> &[u8; 32] {
+        &self.tx_hash
+    }
+
+    /// Return the commit the transaction was persisted in.
+    pub fn commit(&self) -> CommitId {
+        self.commit
+    }
+}
+
+/// The transactions performed since the last commit, appended to the history
+/// when the world is persisted.
+///
+/// The history has the same layout as the event log - a sequence of records,
+/// each prefixed by its length as a little endian `u32` - but only commits
+/// with at least one transaction get a record.
+#[derive(Debug, Default)]
+pub struct CallHistory {
+    enabled: bool,
+    pending: Vec<Entry>,
+}
+
+impl CallHistory {
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn record(&mut self, module_id: ModuleId, method: &str, arg: &[u8]) {
+        let arg_hash = blake3::hash(arg);
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(module_id.as_bytes());
+        hasher.update(method.as_bytes());
+        hasher.update(arg);
+        let tx_hash = hasher.finalize();
+
+        self.pending.push((
+            module_id,
+            String::from(method),
+            arg_hash.into(),
+            tx_hash.into(),
+        ));
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Discard the transactions recorded since the given number of pending
+    /// ones.
+    pub fn truncate(&mut self, len: usize) {
+        self.pending.truncate(len);
+    }
+
+    /// Append the pending transactions to the history at the given path,
+    /// under the given commit.
+    pub fn append(
+        &mut self,
+        path: &Path,
+        commit_id: CommitId,
+    ) -> Result<(), Error> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let record: Record =
+            (*commit_id.as_bytes(), mem::take(&mut self.pending));
+
+        let bytes = rkyv::to_bytes::<_, 1024>(&record)
+            .expect("Serializing the history should succeed");
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(PersistenceError)?;
+
+        let len = bytes.len() as u32;
+        file.write_all(&len.to_le_bytes())
+            .map_err(PersistenceError)?;
+        file.write_all(&bytes).map_err(PersistenceError)
+    }
+}
+
+/// Read the transactions performed on the given module from the history at
+/// the given path, in the order they were performed.
+pub fn read(
+    path: &Path,
+    module_id: ModuleId,
+) -> Result<Vec<CallRecord>, Error> {
+    let mut calls = vec![];
+
+    if !path.exists() {
+        return Ok(calls);
+    }
+
+    let mut file = File::open(path).map_err(PersistenceError)?;
+    let mut contents = vec![];
+    file.read_to_end(&mut contents).map_err(PersistenceError)?;
+
+    let mut rest = &contents[..];
+
+    while !rest.is_empty() {
+        if rest.len() < 4 {
+            return Err(Error::ValidationError);
+        }
+        let (len, tail) = rest.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+
+        if tail.len() < len {
+            return Err(Error::ValidationError);
+        }
+        let (record, tail) = tail.split_at(len);
+        rest = tail;
+
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(record);
+
+        let archived = rkyv::check_archived_root::<Record>(&bytes[..])
+            .map_err(|_| Error::ValidationError)?;
+        let (commit, entries): Record =
+            archived.deserialize(&mut Infallible).expect("Infallible");
+
+        calls.extend(entries.into_iter().filter(|e| e.0 == module_id).map(
+            |(_, method, arg_hash, tx_hash)| CallRecord {
+                method,
+                arg_hash,
+                tx_hash,
+                commit: CommitId::from(commit),
+            },
+        ));
+    }
+
+    Ok(calls)
+}
@@ -0,0 +1,129 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::fmt::{self, Debug, Formatter};
+
+use dallo::ModuleId;
+
+use crate::error::Error;
+use crate::world::CommitInfo;
+
+/// A callback inspecting a module being deployed, given its id and bytecode,
+/// and returning the reason for rejecting it, if any.
+pub trait DeployHook: Fn(ModuleId, &[u8]) -> Result<(), String> {}
+impl<F> DeployHook for F where F: Fn(ModuleId, &[u8]) -> Result<(), String> {}
+
+type BoxedHook = Box<dyn DeployHook + Send>;
+
+/// A callback called after each successful commit, given the commit and the
+/// hash of the memory of each module in it, in canonical order.
+pub trait CommitHook: FnMut(&CommitInfo, &[(ModuleId, [u8; 32])]) {}
+impl<F> CommitHook for F where F: FnMut(&CommitInfo, &[(ModuleId, [u8; 32])]) {}
+
+/// The hooks called before a module is compiled, and after it is
+/// instantiated but before it is added to the world.
+#[derive(Default)]
+pub struct DeployHooks {
+    pre: Vec<BoxedHook>,
+    post: Vec<BoxedHook>,
+}
+
+impl Debug for DeployHooks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeployHooks")
+            .field("pre", &self.pre.len())
+            .field("post", &self.post.len())
+            .finish()
+    }
+}
+
+impl DeployHooks {
+    pub fn push_pre<H>(&mut self, hook: H)
+    where
+        H: 'static + DeployHook + Send,
+    {
+        self.pre.push(Box::new(hook));
+    }
+
+    pub fn push_post<H>(&mut self, hook: H)
+    where
+        H: 'static + DeployHook + Send,
+    {
+        self.post.push(Box::new(hook));
+    }
+
+    /// Put back the hooks registered while these were taken out, after them.
+    pub fn append(&mut self, other: &mut DeployHooks) {
+        self.pre.append(&mut other.pre);
+        self.post.append(&mut other.post);
+    }
+
+    pub fn pre_deploy(
+        &self,
+        id: ModuleId,
+        bytecode: &[u8],
+    ) -> Result<(), Error> {
+        call_all(&self.pre, id, bytecode)
+    }
+
+    pub fn post_deploy(
+        &self,
+        id: ModuleId,
+        bytecode: &[u8],
+    ) -> Result<(), Error> {
+        call_all(&self.post, id, bytecode)
+    }
+}
+
+fn call_all(
+    hooks: &[BoxedHook],
+    id: ModuleId,
+    bytecode: &[u8],
+) -> Result<(), Error> {
+    for hook in hooks {
+        hook(id, bytecode)
+            .map_err(|reason| Error::DeployRejected(id, reason))?;
+    }
+    Ok(())
+}
+
+/// The hooks called after each successful commit.
+#[derive(Default)]
+pub struct CommitHooks {
+    hooks: Vec<Box<dyn CommitHook + Send>>,
+}
+
+impl Debug for CommitHooks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommitHooks")
+            .field("hooks", &self.hooks.len())
+            .finish()
+    }
+}
+
+impl CommitHooks {
+    pub fn push<H>(&mut self, hook: H)
+    where
+        H: 'static + CommitHook + Send,
+    {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// Put back the hooks registered while these were taken out, after them.
+    pub fn append(&mut self, other: &mut CommitHooks) {
+        self.hooks.append(&mut other.hooks);
+    }
+
+    pub fn committed(
+        &mut self,
+        info: &CommitInfo,
+        modules: &[(ModuleId, [u8; 32])],
+    ) {
+        for hook in &mut self.hooks {
+            hook(info, modules);
+        }
+    }
+}
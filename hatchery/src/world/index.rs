@@ -0,0 +1,118 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use dallo::ModuleId;
+use rkyv::{AlignedVec, Deserialize, Infallible};
+
+use crate::error::Error;
+use crate::snapshot::SnapshotId;
+use crate::world::bloom::EventBloom;
+use crate::world::commit::Commit;
+use crate::world::CommitId;
+use crate::Error::PersistenceError;
+
+type Record = ([u8; 32], Vec<(ModuleId, [u8; 32])>, Vec<u64>);
+
+/// Append the given commit to the index at the given path.
+///
+/// The index is a sequence of records, one per commit made, each consisting
+/// of its length as a little endian `u32` followed by the archived commit id,
+/// the snapshot of each module in it, and the bloom filter of the modules
+/// that emitted events.
+pub fn append(
+    path: &Path,
+    commit_id: CommitId,
+    commit: &Commit,
+) -> Result<(), Error> {
+    let snapshots = commit
+        .snapshots()
+        .map(|(module_id, snapshot_id)| (*module_id, (*snapshot_id).into()))
+        .collect();
+    let events = commit.events().words().to_vec();
+    let record: Record = (*commit_id.as_bytes(), snapshots, events);
+
+    let bytes = rkyv::to_bytes::<_, 1024>(&record)
+        .expect("Serializing the commit should succeed");
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(PersistenceError)?;
+
+    let len = bytes.len() as u32;
+    file.write_all(&len.to_le_bytes())
+        .map_err(PersistenceError)?;
+    file.write_all(&bytes).map_err(PersistenceError)
+}
+
+/// Read the commits in the index at the given path, returning no commits if
+/// the index does not exist.
+///
+/// A commit made more than once has the events of every time it was made.
+///
+/// Fails with [`Error::CorruptCommit`] if the snapshots of a commit don't add
+/// up to its id.
+pub fn read(path: &Path) -> Result<BTreeMap<CommitId, Commit>, Error> {
+    let mut commits = BTreeMap::new();
+
+    if !path.exists() {
+        return Ok(commits);
+    }
+
+    let mut file = File::open(path).map_err(PersistenceError)?;
+    let mut contents = vec![];
+    file.read_to_end(&mut contents).map_err(PersistenceError)?;
+
+    let mut rest = &contents[..];
+
+    while !rest.is_empty() {
+        if rest.len() < 4 {
+            return Err(Error::ValidationError);
+        }
+        let (len, tail) = rest.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+
+        if tail.len() < len {
+            return Err(Error::ValidationError);
+        }
+        let (record, tail) = tail.split_at(len);
+        rest = tail;
+
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(record);
+
+        let archived = rkyv::check_archived_root::<Record>(&bytes[..])
+            .map_err(|_| Error::ValidationError)?;
+        let (commit_id, snapshots, events): Record =
+            archived.deserialize(&mut Infallible).expect("Infallible");
+        let commit_id = CommitId::from(commit_id);
+
+        let mut commit = Commit::default();
+        for (module_id, snapshot_id) in snapshots {
+            commit.insert(module_id, SnapshotId::from(snapshot_id));
+        }
+        if commit.id() != commit_id {
+            return Err(Error::CorruptCommit(commit_id));
+        }
+
+        let events =
+            EventBloom::from_words(&events).ok_or(Error::ValidationError)?;
+        commit.events_mut().union(&events);
+        if let Some(previous) = commits.get(&commit_id) {
+            commit.events_mut().union(previous.events());
+        }
+
+        commits.insert(commit_id, commit);
+    }
+
+    Ok(commits)
+}
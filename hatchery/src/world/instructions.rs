@@ -0,0 +1,204 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use dallo::ModuleId;
+use loupe::MemoryUsage;
+use wasmer::wasmparser::Operator;
+use wasmer::{
+    ExportIndex, FunctionMiddleware, GlobalInit, LocalFunctionIndex,
+    MiddlewareError, MiddlewareReaderState, ModuleMiddleware, Val,
+};
+use wasmer_types::{GlobalType, ModuleInfo, Mutability, Type};
+
+use crate::env::Env;
+use crate::instance::Instance;
+
+/// Name of the exported global counting the instructions executed.
+const INSTRUCTIONS_EXPORT: &str = "hatchery_instructions";
+
+/// Name of the global the metering middleware keeps the remaining points in.
+const METERING_EXPORT: &str = "wasmer_metering_remaining_points";
+
+/// Counts the instructions executed by a module in a global exported as
+/// [`INSTRUCTIONS_EXPORT`].
+///
+/// Like metering, the count is updated before each instruction that may
+/// branch, with the instructions since the previous update. It must come
+/// after metering in the middleware chain, so that the counting is not
+/// charged for. The instructions inserted by metering are recognized by
+/// their use of its global, and are not counted.
+#[derive(Debug, Default, MemoryUsage)]
+pub struct Instructions {
+    #[loupe(skip)]
+    globals: Mutex<Option<(u32, Option<u32>)>>,
+}
+
+impl ModuleMiddleware for Instructions {
+    fn generate_function_middleware(
+        &self,
+        _: LocalFunctionIndex,
+    ) -> Box<dyn FunctionMiddleware> {
+        let (counter, metering) = self
+            .globals
+            .lock()
+            .unwrap()
+            .expect("Module info should be transformed first");
+
+        Box::new(FunctionInstructions {
+            counter,
+            metering,
+            accumulated: 0,
+            metering_code: false,
+        })
+    }
+
+    fn transform_module_info(&self, module_info: &mut ModuleInfo) {
+        let mut globals = self.globals.lock().unwrap();
+        if globals.is_some() {
+            panic!("Instructions middleware used to compile multiple modules");
+        }
+
+        let metering = match module_info.exports.get(METERING_EXPORT) {
+            Some(ExportIndex::Global(index)) => Some(index.as_u32()),
+            _ => None,
+        };
+
+        let counter = module_info
+            .globals
+            .push(GlobalType::new(Type::I64, Mutability::Var));
+        module_info
+            .global_initializers
+            .push(GlobalInit::I64Const(0));
+        module_info.exports.insert(
+            INSTRUCTIONS_EXPORT.to_string(),
+            ExportIndex::Global(counter),
+        );
+
+        *globals = Some((counter.as_u32(), metering));
+    }
+}
+
+#[derive(Debug)]
+struct FunctionInstructions {
+    counter: u32,
+    metering: Option<u32>,
+    accumulated: u64,
+    /// Whether the operators being fed were inserted by metering, which
+    /// starts by reading the remaining points and ends by writing them.
+    metering_code: bool,
+}
+
+impl FunctionMiddleware for FunctionInstructions {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        if let Some(metering) = self.metering {
+            match operator {
+                Operator::GlobalGet { global_index }
+                    if global_index == metering =>
+                {
+                    self.metering_code = true;
+                }
+                Operator::GlobalSet { global_index }
+                    if global_index == metering =>
+                {
+                    self.metering_code = false;
+                    state.push_operator(operator);
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if self.metering_code {
+            state.push_operator(operator);
+            return Ok(());
+        }
+
+        self.accumulated += 1;
+
+        match operator {
+            Operator::Loop { .. }
+            | Operator::End
+            | Operator::If { .. }
+            | Operator::Else
+            | Operator::Br { .. }
+            | Operator::BrTable { .. }
+            | Operator::BrIf { .. }
+            | Operator::Call { .. }
+            | Operator::CallIndirect { .. }
+            | Operator::Return
+            | Operator::Unreachable => {
+                state.extend(&[
+                    Operator::GlobalGet {
+                        global_index: self.counter,
+                    },
+                    Operator::I64Const {
+                        value: self.accumulated as i64,
+                    },
+                    Operator::I64Add,
+                    Operator::GlobalSet {
+                        global_index: self.counter,
+                    },
+                ]);
+                self.accumulated = 0;
+            }
+            _ => {}
+        }
+
+        state.push_operator(operator);
+        Ok(())
+    }
+}
+
+/// Read the number of instructions an instance executed since it was
+/// created.
+pub fn instructions(instance: &wasmer::Instance) -> u64 {
+    match instance.exports.get_global(INSTRUCTIONS_EXPORT) {
+        Ok(global) => match global.get() {
+            Val::I64(count) => count as u64,
+            _ => 0,
+        },
+        Err(_) => 0,
+    }
+}
+
+/// Keeps the instruction counts of the modules entered during a call, as they
+/// were when first entered.
+#[derive(Debug, Default)]
+pub struct InstructionTracker {
+    entered: BTreeMap<ModuleId, u64>,
+}
+
+impl InstructionTracker {
+    pub fn clear(&mut self) {
+        self.entered.clear();
+    }
+
+    pub fn enter(&mut self, module_id: ModuleId, instance: &Instance) {
+        self.entered
+            .entry(module_id)
+            .or_insert_with(|| instance.instructions());
+    }
+
+    /// Return the number of instructions executed by all modules entered
+    /// during the call.
+    pub fn finish(&mut self, environments: &BTreeMap<ModuleId, Env>) -> u64 {
+        let entered = std::mem::take(&mut self.entered);
+
+        entered
+            .into_iter()
+            .map(|(module_id, start)| {
+                environments[&module_id].inner().instructions() - start
+            })
+            .sum()
+    }
+}
@@ -0,0 +1,23 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::ops::Range;
+
+/// How the memory of a module is laid out, as offsets into it.
+///
+/// The static data of the module comes first, followed by the stack, which
+/// grows downwards from the heap base. The heap takes the rest of the memory.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MemoryLayout {
+    /// End of the static data.
+    pub data_end: usize,
+    /// Start of the heap.
+    pub heap_base: usize,
+    /// The argument buffer, part of the static data.
+    pub arg_buf: Range<usize>,
+    /// The region reserved for the stack.
+    pub stack_region: Range<usize>,
+}
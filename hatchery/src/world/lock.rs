@@ -0,0 +1,64 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::Path;
+
+use crate::error::Error;
+use crate::Error::PersistenceError;
+
+const LOCK_FILE: &str = "lock";
+
+/// An advisory lock on a storage path, released when dropped.
+///
+/// A world that can persist holds the lock exclusively, while any number of
+/// read-only worlds can share it.
+#[derive(Debug)]
+pub struct StorageLock {
+    _file: File,
+    exclusive: bool,
+}
+
+impl StorageLock {
+    pub fn exclusive(path: &Path) -> Result<Self, Error> {
+        Self::acquire(path, true)
+    }
+
+    pub fn shared(path: &Path) -> Result<Self, Error> {
+        Self::acquire(path, false)
+    }
+
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    fn acquire(path: &Path, exclusive: bool) -> Result<Self, Error> {
+        std::fs::create_dir_all(path).map_err(PersistenceError)?;
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path.join(LOCK_FILE))
+            .map_err(PersistenceError)?;
+
+        let locked = match exclusive {
+            true => file.try_lock(),
+            false => file.try_lock_shared(),
+        };
+
+        match locked {
+            Ok(()) => Ok(StorageLock {
+                _file: file,
+                exclusive,
+            }),
+            Err(TryLockError::WouldBlock) => {
+                Err(Error::StorageLocked(path.to_path_buf()))
+            }
+            Err(TryLockError::Error(e)) => Err(PersistenceError(e)),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::mem;
+use std::ops::RangeBounds;
+use std::path::Path;
+
+use dallo::ModuleId;
+use rkyv::{AlignedVec, Deserialize, Infallible};
+
+use crate::error::Error;
+use crate::world::{CommitId, Event};
+use crate::Error::PersistenceError;
+
+type Entry = (ModuleId, Vec<ModuleId>, String, Vec<u8>);
+type Record = ([u8; 32], Vec<Entry>);
+
+/// The events emitted by the transactions performed since the last commit,
+/// appended to the log when the world is persisted.
+///
+/// The log is a sequence of records, one per commit, each consisting of its
+/// length as a little endian `u32` followed by the archived commit id and
+/// events.
+#[derive(Debug, Default)]
+pub struct EventLog {
+    pending: Vec<Event>,
+}
+
+impl EventLog {
+    pub fn record<'a, I>(&mut self, events: I)
+    where
+        I: IntoIterator<Item = &'a Event>,
+    {
+        self.pending.extend(events.into_iter().cloned());
+    }
+
+    /// Return the events recorded since the last commit.
+    pub fn pending(&self) -> &[Event] {
+        &self.pending
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Append the pending events to the log at the given path, under the
+    /// given commit.
+    pub fn append(
+        &mut self,
+        path: &Path,
+        commit_id: CommitId,
+    ) -> Result<(), Error> {
+        let events = mem::take(&mut self.pending)
+            .into_iter()
+            .map(|event| {
+                (
+                    *event.module_id(),
+                    event.callers().to_vec(),
+                    String::from(event.method()),
+                    event.data().to_vec(),
+                )
+            })
+            .collect();
+        let record: Record = (*commit_id.as_bytes(), events);
+
+        let bytes = rkyv::to_bytes::<_, 1024>(&record)
+            .expect("Serializing the events should succeed");
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(PersistenceError)?;
+
+        let len = bytes.len() as u32;
+        file.write_all(&len.to_le_bytes())
+            .map_err(PersistenceError)?;
+        file.write_all(&bytes).map_err(PersistenceError)
+    }
+}
+
+/// Read the events of the commits in the given range from the log at the
+/// given path, with commits numbered in the order they were made.
+pub fn read<R>(
+    path: &Path,
+    range: R,
+) -> Result<Vec<(CommitId, Vec<Event>)>, Error>
+where
+    R: RangeBounds<usize>,
+{
+    let mut commits = vec![];
+
+    if !path.exists() {
+        return Ok(commits);
+    }
+
+    let mut file = File::open(path).map_err(PersistenceError)?;
+    let mut contents = vec![];
+    file.read_to_end(&mut contents).map_err(PersistenceError)?;
+
+    let mut rest = &contents[..];
+    let mut index = 0;
+
+    while !rest.is_empty() {
+        if rest.len() < 4 {
+            return Err(Error::ValidationError);
+        }
+        let (len, tail) = rest.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+
+        if tail.len() < len {
+            return Err(Error::ValidationError);
+        }
+        let (record, tail) = tail.split_at(len);
+        rest = tail;
+
+        if range.contains(&index) {
+            let mut bytes = AlignedVec::new();
+            bytes.extend_from_slice(record);
+
+            let archived = rkyv::check_archived_root::<Record>(&bytes[..])
+                .map_err(|_| Error::ValidationError)?;
+            let (commit_id, events): Record =
+                archived.deserialize(&mut Infallible).expect("Infallible");
+
+            let events = events
+                .into_iter()
+                .map(|(module_id, callers, method, data)| {
+                    Event::new(module_id, callers, method, data)
+                })
+                .collect();
+
+            commits.push((CommitId::from(commit_id), events));
+        }
+
+        index += 1;
+    }
+
+    Ok(commits)
+}
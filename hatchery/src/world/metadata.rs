@@ -0,0 +1,186 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+
+use dallo::ModuleId;
+use rkyv::{AlignedVec, Deserialize, Infallible};
+
+use crate::error::Error;
+use crate::snapshot::SnapshotId;
+use crate::world::regions::{self, SnapshotPolicy};
+use crate::Error::PersistenceError;
+
+const FULL: u8 = 0;
+const DATA_AND_HEAP: u8 = 1;
+const RANGES: u8 = 2;
+
+type Entry = (ModuleId, u8, Vec<(u64, u64)>, (u64, u64), u64);
+
+/// The settings of a module that outlive its instance, reapplied when it is
+/// deployed again after the world is opened.
+#[derive(Debug, Clone)]
+pub struct ModuleMetadata {
+    pub policy: SnapshotPolicy,
+    /// The region reserved for the stack, kept so that the state of the
+    /// module can be hashed without instantiating it.
+    pub stack_region: Range<usize>,
+    /// Percentage of the points spent by the module that are charged.
+    pub gas_multiplier: u64,
+}
+
+impl Default for ModuleMetadata {
+    fn default() -> Self {
+        Self {
+            policy: SnapshotPolicy::default(),
+            stack_region: 0..0,
+            gas_multiplier: 100,
+        }
+    }
+}
+
+impl ModuleMetadata {
+    /// Hash the state of the module in the given memory.
+    pub fn hash_state(&self, memory: &[u8]) -> SnapshotId {
+        let regions = self.policy.regions(&self.stack_region, memory.len());
+        regions::hash(&regions, memory)
+    }
+}
+
+/// The metadata of every module the world has deployed, including the ones
+/// released since.
+#[derive(Debug, Default)]
+pub struct Metadata {
+    modules: BTreeMap<ModuleId, ModuleMetadata>,
+}
+
+impl Metadata {
+    /// Return the metadata of the given module, recording it with the given
+    /// stack region and the default settings if it's new.
+    pub fn module(
+        &mut self,
+        module_id: ModuleId,
+        stack_region: Range<usize>,
+    ) -> &ModuleMetadata {
+        self.modules
+            .entry(module_id)
+            .or_insert_with(|| ModuleMetadata {
+                stack_region,
+                ..ModuleMetadata::default()
+            })
+    }
+
+    pub fn set_policy(&mut self, module_id: ModuleId, policy: SnapshotPolicy) {
+        if let Some(metadata) = self.modules.get_mut(&module_id) {
+            metadata.policy = policy;
+        }
+    }
+
+    pub fn set_gas_multiplier(&mut self, module_id: ModuleId, percent: u64) {
+        if let Some(metadata) = self.modules.get_mut(&module_id) {
+            metadata.gas_multiplier = percent;
+        }
+    }
+
+    /// Hash the state of the given module in the given memory.
+    ///
+    /// Modules without metadata were deployed before it was recorded, when
+    /// the whole memory was always the state.
+    pub fn hash_state(
+        &self,
+        module_id: &ModuleId,
+        memory: &[u8],
+    ) -> SnapshotId {
+        match self.modules.get(module_id) {
+            Some(metadata) => metadata.hash_state(memory),
+            None => ModuleMetadata::default().hash_state(memory),
+        }
+    }
+
+    /// Write the metadata to the given file.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let entries: Vec<Entry> = self
+            .modules
+            .iter()
+            .map(|(module_id, metadata)| {
+                let (kind, ranges) = match &metadata.policy {
+                    SnapshotPolicy::Full => (FULL, vec![]),
+                    SnapshotPolicy::DataAndHeap => (DATA_AND_HEAP, vec![]),
+                    SnapshotPolicy::Ranges(ranges) => {
+                        (RANGES, ranges.iter().map(to_pair).collect())
+                    }
+                };
+                (
+                    *module_id,
+                    kind,
+                    ranges,
+                    to_pair(&metadata.stack_region),
+                    metadata.gas_multiplier,
+                )
+            })
+            .collect();
+
+        let bytes = rkyv::to_bytes::<_, 1024>(&entries)
+            .expect("Serializing the metadata should succeed");
+        std::fs::write(path, bytes).map_err(PersistenceError)
+    }
+
+    /// Read the metadata from the given file, returning no metadata if the
+    /// file does not exist.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let mut metadata = Metadata::default();
+
+        if !path.exists() {
+            return Ok(metadata);
+        }
+
+        let contents = std::fs::read(path).map_err(PersistenceError)?;
+
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(&contents);
+
+        let archived = rkyv::check_archived_root::<Vec<Entry>>(&bytes[..])
+            .map_err(|_| Error::ValidationError)?;
+        let entries: Vec<Entry> =
+            archived.deserialize(&mut Infallible).expect("Infallible");
+
+        for (module_id, kind, ranges, stack_region, gas_multiplier) in entries {
+            let policy = match kind {
+                FULL => SnapshotPolicy::Full,
+                DATA_AND_HEAP => SnapshotPolicy::DataAndHeap,
+                RANGES => SnapshotPolicy::Ranges(
+                    ranges.into_iter().map(from_pair).collect(),
+                ),
+                _ => return Err(Error::ValidationError),
+            };
+            let stack_region = from_pair(stack_region);
+            if gas_multiplier == 0 {
+                return Err(Error::ValidationError);
+            }
+
+            metadata.modules.insert(
+                module_id,
+                ModuleMetadata {
+                    policy,
+                    stack_region,
+                    gas_multiplier,
+                },
+            );
+        }
+
+        Ok(metadata)
+    }
+}
+
+fn to_pair(range: &Range<usize>) -> (u64, u64) {
+    (range.start as u64, range.end as u64)
+}
+
+fn from_pair((start, end): (u64, u64)) -> Range<usize> {
+    start as usize..end as usize
+}
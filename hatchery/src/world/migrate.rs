@@ -0,0 +1,84 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dallo::ModuleId;
+
+/// The memory of a module before a migration.
+///
+/// Passed to the closure given to [`World::migrate`].
+///
+/// [`World::migrate`]: crate::World::migrate
+#[derive(Debug)]
+pub struct ModuleState<'a> {
+    module_id: ModuleId,
+    memory: &'a [u8],
+}
+
+impl<'a> ModuleState<'a> {
+    pub(crate) fn new(module_id: ModuleId, memory: &'a [u8]) -> Self {
+        Self { module_id, memory }
+    }
+
+    /// Return the id of the module being migrated.
+    pub fn module_id(&self) -> ModuleId {
+        self.module_id
+    }
+
+    /// Return the memory of the module.
+    pub fn memory(&self) -> &[u8] {
+        self.memory
+    }
+
+    /// Return the given range of the memory, or `None` if it's out of
+    /// bounds.
+    pub fn read(&self, offset: usize, len: usize) -> Option<&[u8]> {
+        self.memory.get(offset..offset.checked_add(len)?)
+    }
+}
+
+/// The memory of a module after a migration, starting as a copy of the
+/// memory before it.
+///
+/// Passed to the closure given to [`World::migrate`].
+///
+/// [`World::migrate`]: crate::World::migrate
+#[derive(Debug)]
+pub struct ModuleStateBuilder {
+    memory: Vec<u8>,
+}
+
+impl ModuleStateBuilder {
+    pub(crate) fn new(memory: Vec<u8>) -> Self {
+        Self { memory }
+    }
+
+    /// Return the memory being built.
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Return the memory being built, for modifying it in place.
+    pub fn memory_mut(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+
+    /// Write the given bytes at the given offset, extending the memory with
+    /// zeroes if they don't fit.
+    ///
+    /// The memory of the module is grown to fit once the migration is
+    /// applied.
+    pub fn write(&mut self, offset: usize, bytes: &[u8]) {
+        let end = offset + bytes.len();
+        if end > self.memory.len() {
+            self.memory.resize(end, 0);
+        }
+        self.memory[offset..end].copy_from_slice(bytes);
+    }
+
+    pub(crate) fn into_memory(self) -> Vec<u8> {
+        self.memory
+    }
+}
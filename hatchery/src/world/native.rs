@@ -9,6 +9,10 @@ use std::fmt::{self, Debug, Formatter};
 
 pub struct NativeQueries {
     map: BTreeMap<&'static str, Box<dyn NativeQuery>>,
+    memoize: bool,
+    /// Results of the queries performed during the current transaction,
+    /// keyed by the name of the query, then by its argument.
+    memo: BTreeMap<String, BTreeMap<Vec<u8>, Vec<u8>>>,
 }
 
 impl Debug for NativeQueries {
@@ -21,9 +25,36 @@ impl NativeQueries {
     pub fn new() -> Self {
         NativeQueries {
             map: BTreeMap::new(),
+            memoize: false,
+            memo: BTreeMap::new(),
         }
     }
 
+    pub fn set_memoize(&mut self, memoize: bool) {
+        self.memoize = memoize;
+        self.memo.clear();
+    }
+
+    pub fn is_memoizing(&self) -> bool {
+        self.memoize
+    }
+
+    pub fn clear_memo(&mut self) {
+        self.memo.clear();
+    }
+
+    pub fn memoized(&self, name: &str, arg: &[u8]) -> Option<&[u8]> {
+        // the keys are nested so the lookup can borrow both parts of the key
+        self.memo.get(name)?.get(arg).map(Vec::as_slice)
+    }
+
+    pub fn memoize(&mut self, name: &str, arg: Vec<u8>, ret: Vec<u8>) {
+        self.memo
+            .entry(String::from(name))
+            .or_default()
+            .insert(arg, ret);
+    }
+
     pub fn insert<Q>(&mut self, name: &'static str, query: Q)
     where
         Q: 'static + NativeQuery,
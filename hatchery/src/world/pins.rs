@@ -0,0 +1,73 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use rkyv::{AlignedVec, Deserialize, Infallible};
+
+use crate::error::Error;
+use crate::world::CommitId;
+use crate::Error::PersistenceError;
+
+/// The commits protected from being discarded.
+#[derive(Debug, Default)]
+pub struct Pins {
+    commits: BTreeSet<CommitId>,
+}
+
+impl Pins {
+    /// Pin the commit, returning false if it was already pinned.
+    pub fn pin(&mut self, commit_id: CommitId) -> bool {
+        self.commits.insert(commit_id)
+    }
+
+    /// Unpin the commit, returning false if it wasn't pinned.
+    pub fn unpin(&mut self, commit_id: &CommitId) -> bool {
+        self.commits.remove(commit_id)
+    }
+
+    /// Return the pinned commits, ordered by id.
+    pub fn commits(&self) -> impl Iterator<Item = &CommitId> {
+        self.commits.iter()
+    }
+
+    /// Write the pins to the given file.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let entries: Vec<[u8; 32]> =
+            self.commits.iter().map(|id| *id.as_bytes()).collect();
+
+        let bytes = rkyv::to_bytes::<_, 1024>(&entries)
+            .expect("Serializing the pins should succeed");
+        std::fs::write(path, bytes).map_err(PersistenceError)
+    }
+
+    /// Read the pins from the given file, returning no pins if the file does
+    /// not exist.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let mut pins = Pins::default();
+
+        if !path.exists() {
+            return Ok(pins);
+        }
+
+        let contents = std::fs::read(path).map_err(PersistenceError)?;
+
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(&contents);
+
+        let archived = rkyv::check_archived_root::<Vec<[u8; 32]>>(&bytes[..])
+            .map_err(|_| Error::ValidationError)?;
+        let entries: Vec<[u8; 32]> =
+            archived.deserialize(&mut Infallible).expect("Infallible");
+
+        for entry in entries {
+            pins.pin(CommitId::from(entry));
+        }
+
+        Ok(pins)
+    }
+}
@@ -0,0 +1,61 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::Error::PersistenceError;
+
+/// Length of the name of a module or a snapshot, hex encoded.
+const NAME_LEN: usize = 64;
+
+/// Return the snapshot files in the given directory whose names are not in
+/// `referenced`, ordered by path.
+pub fn orphans(
+    path: &Path,
+    referenced: &BTreeSet<String>,
+) -> Result<Vec<PathBuf>, Error> {
+    let mut orphans = vec![];
+
+    for entry in std::fs::read_dir(path).map_err(PersistenceError)? {
+        let entry = entry.map_err(PersistenceError)?;
+        if !entry.file_type().map_err(PersistenceError)?.is_file() {
+            continue;
+        }
+
+        let name = entry.file_name();
+        let name = match name.to_str() {
+            Some(name) => name,
+            None => continue,
+        };
+
+        if is_snapshot_name(name) && !referenced.contains(name) {
+            orphans.push(entry.path());
+        }
+    }
+
+    orphans.sort();
+    Ok(orphans)
+}
+
+/// Snapshot files are named after their module and snapshot, joined by an
+/// underscore.
+fn is_snapshot_name(name: &str) -> bool {
+    match name.split_once('_') {
+        Some((module, snapshot)) => {
+            is_hex_name(module) && is_hex_name(snapshot)
+        }
+        None => false,
+    }
+}
+
+fn is_hex_name(name: &str) -> bool {
+    name.len() == NAME_LEN
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'A'..=b'F').contains(&b))
+}
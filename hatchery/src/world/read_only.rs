@@ -0,0 +1,99 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::ops::RangeBounds;
+use std::path::Path;
+
+use bytecheck::CheckBytes;
+use dallo::{AbiType, ModuleId, StandardBufSerializer};
+use rkyv::{
+    validation::validators::DefaultValidator, Archive, Deserialize, Infallible,
+    Serialize,
+};
+
+use super::{CallRecord, CommitId, Event, Receipt, StateProof, World};
+use crate::error::Error;
+
+/// A handle to a [`World`] that can only be queried.
+///
+/// It exposes no way to transact, deploy, or persist, so a process holding
+/// only read-only handles can't alter the state in the storage path.
+///
+/// The handle shares the module instances of the world it was created from,
+/// so it always sees the current state of that world. It can't be moved to
+/// another commit, since that would move the world along with it.
+#[derive(Debug, Clone)]
+pub struct ReadOnlyWorld(World);
+
+impl ReadOnlyWorld {
+    pub(crate) fn new(world: World) -> Self {
+        ReadOnlyWorld(world)
+    }
+
+    pub fn query<Arg, Ret>(
+        &self,
+        m_id: ModuleId,
+        name: &str,
+        arg: Arg,
+    ) -> Result<Receipt<Ret>, Error>
+    where
+        Arg: AbiType + for<'a> Serialize<StandardBufSerializer<'a>>,
+        Ret: Archive,
+        Ret::Archived: Deserialize<Ret, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        self.0.query(m_id, name, arg)
+    }
+
+    /// Return the root of the current state of all modules.
+    pub fn root(&self) -> CommitId {
+        self.0.root()
+    }
+
+    /// Return the modules in the given commit, each with the hash of its
+    /// memory, in canonical order.
+    pub fn canonical_modules(
+        &self,
+        commit_id: CommitId,
+    ) -> Result<Vec<(ModuleId, [u8; 32])>, Error> {
+        self.0.canonical_modules(commit_id)
+    }
+
+    /// Return the memory of a module as it was in the given commit, together
+    /// with the proof of its inclusion in it.
+    pub fn export_module_state(
+        &self,
+        commit_id: CommitId,
+        module_id: ModuleId,
+    ) -> Result<(Vec<u8>, StateProof), Error> {
+        self.0.export_module_state(commit_id, module_id)
+    }
+
+    /// Return the events emitted by the transactions of each commit in the
+    /// given range.
+    pub fn replay_events<R>(
+        &self,
+        commit_range: R,
+    ) -> Result<Vec<(CommitId, Vec<Event>)>, Error>
+    where
+        R: RangeBounds<usize>,
+    {
+        self.0.replay_events(commit_range)
+    }
+
+    /// Return the transactions performed on the given module, as recorded in
+    /// its call history.
+    pub fn call_history(
+        &self,
+        module_id: ModuleId,
+    ) -> Result<Vec<CallRecord>, Error> {
+        self.0.call_history(module_id)
+    }
+
+    pub fn storage_path(&self) -> &Path {
+        self.0.storage_path()
+    }
+}
@@ -0,0 +1,122 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::ops::Range;
+
+use crate::snapshot::SnapshotId;
+
+/// Which regions of the memory of a module make up its state.
+///
+/// Only the regions are hashed into commits and compared when counting the
+/// pages written by a transaction. The rest of the memory is taken to be
+/// zeroes, and is zeroed when the module is restored, so modules can use it
+/// for transient data such as caches.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub enum SnapshotPolicy {
+    /// The whole memory.
+    #[default]
+    Full,
+    /// The static data and the heap, leaving out the stack region.
+    DataAndHeap,
+    /// The given ranges of offsets into the memory.
+    Ranges(Vec<Range<usize>>),
+}
+
+impl SnapshotPolicy {
+    /// Return the regions of a memory of `len` bytes with the given stack
+    /// region, sorted, disjoint, and within the memory.
+    pub(crate) fn regions(
+        &self,
+        stack_region: &Range<usize>,
+        len: usize,
+    ) -> Vec<Range<usize>> {
+        let mut ranges = match self {
+            SnapshotPolicy::Full => vec![0..len],
+            SnapshotPolicy::DataAndHeap => {
+                vec![0..stack_region.start, stack_region.end..len]
+            }
+            SnapshotPolicy::Ranges(ranges) => ranges.clone(),
+        };
+        ranges.sort_by_key(|range| range.start);
+
+        let mut regions: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            let range = range.start.min(len)..range.end.min(len);
+            if range.is_empty() {
+                continue;
+            }
+            match regions.last_mut() {
+                Some(last) if range.start <= last.end => {
+                    last.end = last.end.max(range.end)
+                }
+                _ => regions.push(range),
+            }
+        }
+
+        regions
+    }
+}
+
+/// Hash the given memory with everything outside of `regions` taken to be
+/// zeroes.
+///
+/// A single region covering the whole memory hashes the same as the memory.
+pub(crate) fn hash(regions: &[Range<usize>], memory: &[u8]) -> SnapshotId {
+    let mut hasher = blake3::Hasher::new();
+
+    let mut ofs = 0;
+    for region in regions {
+        hash_zeroes(&mut hasher, region.start - ofs);
+        hasher.update(&memory[region.clone()]);
+        ofs = region.end;
+    }
+    hash_zeroes(&mut hasher, memory.len() - ofs);
+
+    SnapshotId::from(*hasher.finalize().as_bytes())
+}
+
+/// Zero everything in the given memory outside of `regions`.
+pub(crate) fn zero_outside(regions: &[Range<usize>], memory: &mut [u8]) {
+    let mut ofs = 0;
+    for region in regions {
+        memory[ofs..region.start].fill(0);
+        ofs = region.end;
+    }
+    memory[ofs..].fill(0);
+}
+
+/// Return the parts of the given regions outside of all of the `holes`.
+pub(crate) fn without(
+    regions: &[Range<usize>],
+    holes: &[Range<usize>],
+) -> Vec<Range<usize>> {
+    let mut parts = regions.to_vec();
+
+    for hole in holes.iter().filter(|hole| !hole.is_empty()) {
+        parts = parts
+            .into_iter()
+            .flat_map(|part| {
+                [
+                    part.start..part.end.min(hole.start),
+                    part.start.max(hole.end)..part.end,
+                ]
+            })
+            .filter(|part| !part.is_empty())
+            .collect();
+    }
+
+    parts
+}
+
+fn hash_zeroes(hasher: &mut blake3::Hasher, mut len: usize) {
+    const ZEROES: [u8; 4096] = [0; 4096];
+
+    while len > 0 {
+        let n = len.min(ZEROES.len());
+        hasher.update(&ZEROES[..n]);
+        len -= n;
+    }
+}
@@ -0,0 +1,235 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Display, Formatter};
+
+use bytecheck::CheckBytes;
+use dallo::ModuleId;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{AlignedVec, Archive, Deserialize, Infallible};
+
+use crate::error::{Error, ErrorCode};
+
+/// The error a module reverted its call with, using `dallo::revert`.
+///
+/// If the module declared the type of the errors of the method in its ABI,
+/// and the type was registered using [`register_error_type`], the error is
+/// also rendered using its `Debug` implementation.
+///
+/// [`register_error_type`]: crate::World::register_error_type
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ModuleError {
+    module: ModuleId,
+    method: String,
+    payload: Vec<u8>,
+    /// The name of the type of the error, and its rendering.
+    decoded: Option<(String, String)>,
+}
+
+impl ModuleError {
+    pub(crate) fn new(
+        module: ModuleId,
+        method: String,
+        payload: Vec<u8>,
+        decoded: Option<(String, String)>,
+    ) -> Self {
+        Self {
+            module,
+            method,
+            payload,
+            decoded,
+        }
+    }
+
+    /// Return the id of the module that reverted.
+    pub fn module(&self) -> ModuleId {
+        self.module
+    }
+
+    /// Return the name of the method that reverted.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Return the archived error.
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Return the name of the type of the error, if it was decoded.
+    pub fn type_name(&self) -> Option<&str> {
+        self.decoded
+            .as_ref()
+            .map(|(type_name, _)| type_name.as_str())
+    }
+
+    /// Return the error rendered using its `Debug` implementation, if it was
+    /// decoded.
+    pub fn rendering(&self) -> Option<&str> {
+        self.decoded
+            .as_ref()
+            .map(|(_, rendering)| rendering.as_str())
+    }
+
+    /// Deserialize the error as `E`.
+    pub fn decode<E>(&self) -> Result<E, Error>
+    where
+        E: Archive,
+        E::Archived: Deserialize<E, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        decode(&self.payload).ok_or(Error::ValidationError)
+    }
+}
+
+impl Display for ModuleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "module {:?} reverted {} with ", self.module, self.method)?;
+        match &self.decoded {
+            Some((type_name, rendering)) => {
+                write!(f, "{}: {}", type_name, rendering)
+            }
+            None => write!(f, "{} bytes", self.payload.len()),
+        }
+    }
+}
+
+/// A transaction deferred by a call that failed, and whose changes were
+/// rolled back without failing the call that deferred it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CallFailure {
+    module: ModuleId,
+    method: String,
+    code: ErrorCode,
+    message: String,
+    spent: u64,
+}
+
+impl CallFailure {
+    pub(crate) fn new(
+        module: ModuleId,
+        method: String,
+        err: &Error,
+        spent: u64,
+    ) -> Self {
+        Self {
+            module,
+            method,
+            code: err.code(),
+            message: err.to_string(),
+            spent,
+        }
+    }
+
+    pub(crate) fn from_parts(
+        module: ModuleId,
+        method: String,
+        code: ErrorCode,
+        message: String,
+        spent: u64,
+    ) -> Self {
+        Self {
+            module,
+            method,
+            code,
+            message,
+            spent,
+        }
+    }
+
+    /// Return the id of the module the transaction was deferred to.
+    pub fn module(&self) -> ModuleId {
+        self.module
+    }
+
+    /// Return the name of the method the transaction called.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Return the code of the error the transaction failed with.
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    /// Return the error the transaction failed with, rendered as text.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Return the points spent by the transaction before it failed.
+    pub fn spent(&self) -> u64 {
+        self.spent
+    }
+}
+
+impl Display for CallFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "deferred call to {} on {:?} failed: {}",
+            self.method, self.module, self.message
+        )
+    }
+}
+
+type Render = fn(&[u8]) -> Option<String>;
+
+/// The error types registered by the embedder, keyed by their names.
+#[derive(Default)]
+pub(crate) struct ErrorTypes {
+    types: BTreeMap<&'static str, Render>,
+}
+
+impl ErrorTypes {
+    pub(crate) fn insert<E>(&mut self)
+    where
+        E: Archive + Debug,
+        E::Archived: Deserialize<E, Infallible>
+            + for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        self.types.insert(std::any::type_name::<E>(), render::<E>);
+    }
+
+    /// Render the given archived error of the given type, if the type is
+    /// registered and the error is valid.
+    pub(crate) fn render(
+        &self,
+        type_name: &str,
+        payload: &[u8],
+    ) -> Option<String> {
+        self.types.get(type_name).and_then(|render| render(payload))
+    }
+}
+
+impl Debug for ErrorTypes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.types.keys()).finish()
+    }
+}
+
+fn decode<E>(payload: &[u8]) -> Option<E>
+where
+    E: Archive,
+    E::Archived:
+        Deserialize<E, Infallible> + for<'a> CheckBytes<DefaultValidator<'a>>,
+{
+    let mut bytes = AlignedVec::new();
+    bytes.extend_from_slice(payload);
+
+    let archived = rkyv::check_archived_root::<E>(&bytes[..]).ok()?;
+    Some(archived.deserialize(&mut Infallible).expect("Infallible"))
+}
+
+fn render<E>(payload: &[u8]) -> Option<String>
+where
+    E: Archive + Debug,
+    E::Archived:
+        Deserialize<E, Infallible> + for<'a> CheckBytes<DefaultValidator<'a>>,
+{
+    decode::<E>(payload).map(|err| format!("{:?}", err))
+}
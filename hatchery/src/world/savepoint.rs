@@ -0,0 +1,110 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use dallo::ModuleId;
+
+use crate::instance::{Instance, SavedMemory};
+use crate::world::heap::HeapTracker;
+use crate::world::subscriptions::Subscriptions;
+use crate::world::WorldInner;
+
+/// The memories of the modules entered since each open savepoint was
+/// captured, as they were when first entered.
+///
+/// Memories only change while their module is executing, so saving them on
+/// entry is enough to roll them back, without copying the memories of the
+/// modules a savepoint never sees called.
+#[derive(Debug, Default)]
+pub(super) struct Journal {
+    frames: Vec<BTreeMap<ModuleId, SavedMemory>>,
+}
+
+impl Journal {
+    /// Start saving the memories of the modules entered.
+    pub(super) fn open(&mut self) {
+        self.frames.push(BTreeMap::new());
+    }
+
+    /// Save the memory of a module being entered, unless it was already
+    /// saved since the last frame was opened.
+    pub(super) fn enter(&mut self, module_id: ModuleId, instance: &Instance) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame
+                .entry(module_id)
+                .or_insert_with(|| instance.save_memory());
+        }
+    }
+
+    /// Close the last frame, keeping the changes made since it was opened.
+    ///
+    /// The memories it saved are handed to the enclosing frame, which rolls
+    /// back to them unless it saved the module earlier.
+    pub(super) fn close(&mut self) {
+        let frame = self.frames.pop().expect("a frame should be open");
+        if let Some(parent) = self.frames.last_mut() {
+            for (module_id, saved) in frame {
+                parent.entry(module_id).or_insert(saved);
+            }
+        }
+    }
+
+    /// Close the last frame, returning the memories to roll back to.
+    pub(super) fn rollback(&mut self) -> BTreeMap<ModuleId, SavedMemory> {
+        self.frames.pop().expect("a frame should be open")
+    }
+}
+
+/// The state of a world at some point between persists, which the world can
+/// be rolled back to without touching its storage.
+///
+/// Once captured, a savepoint must be either restored or released.
+#[must_use]
+pub(super) struct Savepoint {
+    dirty: BTreeSet<ModuleId>,
+    subscriptions: Subscriptions,
+    heap: HeapTracker,
+    calls: usize,
+    points_spent: u64,
+}
+
+impl Savepoint {
+    pub(super) fn capture(w: &mut WorldInner) -> Self {
+        w.journal.open();
+
+        Self {
+            dirty: w.dirty.clone(),
+            subscriptions: w.subscriptions.clone(),
+            heap: w.heap.clone(),
+            calls: w.history.pending_len(),
+            points_spent: w.points_spent,
+        }
+    }
+
+    /// Keep the transactions performed since the savepoint.
+    pub(super) fn release(self, w: &mut WorldInner) {
+        w.journal.close();
+    }
+
+    /// Roll the world back to the savepoint, discarding the transactions
+    /// performed since.
+    pub(super) fn restore(self, w: &mut WorldInner) {
+        for (module_id, memory) in w.journal.rollback() {
+            w.environments[&module_id]
+                .inner_mut()
+                .restore_all_memory(memory);
+        }
+
+        w.dirty = self.dirty;
+        w.subscriptions = self.subscriptions;
+        w.heap = self.heap;
+        w.history.truncate(self.calls);
+        w.points_spent = self.points_spent;
+        w.deferred.clear();
+        w.query_cache.clear();
+    }
+}
@@ -0,0 +1,93 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::mem;
+use std::path::Path;
+
+use dallo::{ModuleId, RawTransaction};
+use rkyv::{AlignedVec, Deserialize, Infallible};
+
+use crate::error::Error;
+use crate::Error::PersistenceError;
+
+type Entry = (u64, ModuleId, RawTransaction);
+
+/// Transactions scheduled to be performed once a given height is reached.
+///
+/// Transactions scheduled for the same height are kept in the order they
+/// were scheduled.
+#[derive(Debug, Default)]
+pub struct Schedule {
+    entries: BTreeMap<u64, Vec<(ModuleId, RawTransaction)>>,
+}
+
+impl Schedule {
+    pub fn insert(
+        &mut self,
+        height: u64,
+        module_id: ModuleId,
+        raw: RawTransaction,
+    ) {
+        self.entries
+            .entry(height)
+            .or_default()
+            .push((module_id, raw));
+    }
+
+    /// Remove and return all transactions scheduled at or below the given
+    /// height, ordered by height.
+    pub fn take_due(&mut self, height: u64) -> Vec<(ModuleId, RawTransaction)> {
+        let pending = match height.checked_add(1) {
+            Some(next) => self.entries.split_off(&next),
+            None => BTreeMap::new(),
+        };
+        let due = mem::replace(&mut self.entries, pending);
+
+        due.into_values().flatten().collect()
+    }
+
+    /// Write the schedule to the given file.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let entries: Vec<Entry> = self
+            .entries
+            .iter()
+            .flat_map(|(height, txs)| {
+                txs.iter().map(|(id, raw)| (*height, *id, raw.clone()))
+            })
+            .collect();
+
+        let bytes = rkyv::to_bytes::<_, 1024>(&entries)
+            .expect("Serializing the schedule should succeed");
+        std::fs::write(path, bytes).map_err(PersistenceError)
+    }
+
+    /// Read the schedule from the given file, returning an empty schedule if
+    /// the file does not exist.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let mut schedule = Schedule::default();
+
+        if !path.exists() {
+            return Ok(schedule);
+        }
+
+        let contents = std::fs::read(path).map_err(PersistenceError)?;
+
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(&contents);
+
+        let archived = rkyv::check_archived_root::<Vec<Entry>>(&bytes[..])
+            .map_err(|_| Error::ValidationError)?;
+        let entries: Vec<Entry> =
+            archived.deserialize(&mut Infallible).expect("Infallible");
+
+        for (height, module_id, raw) in entries {
+            schedule.insert(height, module_id, raw);
+        }
+
+        Ok(schedule)
+    }
+}
@@ -0,0 +1,81 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+/// How the budget of a [`Sponsor`] relates to the point limit of the
+/// transactions it sponsors.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SponsorMode {
+    /// The budget replaces the point limit, so the sponsor pays for the
+    /// whole transaction.
+    #[default]
+    Instead,
+    /// The budget is added to the point limit, with the sponsor paying
+    /// first and the transaction paying whatever exceeds the budget.
+    InAddition,
+}
+
+/// A party paying for the points spent by transactions on behalf of their
+/// senders, such as in fee delegation schemes.
+///
+/// The embedder keeps track of the budget of the sponsor across
+/// transactions, each sponsored transaction reducing it by the points
+/// charged to the sponsor.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Sponsor {
+    id: Vec<u8>,
+    budget: u64,
+    mode: SponsorMode,
+}
+
+impl Sponsor {
+    /// Create a sponsor with the given identity and budget, paying for the
+    /// whole of the transactions it sponsors.
+    pub fn new<I: Into<Vec<u8>>>(id: I, budget: u64) -> Self {
+        Self {
+            id: id.into(),
+            budget,
+            mode: SponsorMode::Instead,
+        }
+    }
+
+    /// Set how the budget relates to the point limit of transactions.
+    pub fn with_mode(mut self, mode: SponsorMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Return the identity of the sponsor.
+    pub fn id(&self) -> &[u8] {
+        &self.id
+    }
+
+    /// Return the budget left to the sponsor.
+    pub fn budget(&self) -> u64 {
+        self.budget
+    }
+
+    /// Return how the budget relates to the point limit of transactions.
+    pub fn mode(&self) -> SponsorMode {
+        self.mode
+    }
+
+    /// Return the point limit of a sponsored transaction, given the limit
+    /// it would have otherwise.
+    pub(crate) fn limit(&self, limit: u64) -> u64 {
+        match self.mode {
+            SponsorMode::Instead => self.budget,
+            SponsorMode::InAddition => limit.saturating_add(self.budget),
+        }
+    }
+
+    /// Charge the sponsor for the given points, up to its budget, returning
+    /// the points charged.
+    pub(crate) fn charge(&mut self, spent: u64) -> u64 {
+        let charged = spent.min(self.budget);
+        self.budget -= charged;
+        charged
+    }
+}
@@ -9,7 +9,11 @@ use dallo::ModuleId;
 #[derive(Debug)]
 struct CallData {
     module_id: ModuleId,
+    method: String,
     limit: u64,
+    /// Points kept by the caller when making the call.
+    reserved: u64,
+    pure: bool,
 }
 
 #[derive(Debug, Default)]
@@ -18,17 +22,43 @@ pub struct CallStack {
 }
 
 impl CallStack {
-    /// Create a new call stack, with the initiating call being made to
-    /// `module_id` with the given `limit`.
-    pub fn new(module_id: ModuleId, limit: u64) -> Self {
+    /// Create a new call stack, with the initiating call being made to the
+    /// method of `module_id` with the given `limit`.
+    pub fn new(
+        module_id: ModuleId,
+        method: &str,
+        limit: u64,
+        pure: bool,
+    ) -> Self {
         Self {
-            inner: vec![CallData { module_id, limit }],
+            inner: vec![CallData {
+                module_id,
+                method: String::from(method),
+                limit,
+                reserved: 0,
+                pure,
+            }],
         }
     }
 
-    /// Push a call onto the call stack.
-    pub fn push(&mut self, module_id: ModuleId, limit: u64) {
-        self.inner.push(CallData { module_id, limit })
+    /// Push a call onto the call stack, with the caller keeping `reserved`
+    /// points. A call made from a pure frame is pure as well.
+    pub fn push(
+        &mut self,
+        module_id: ModuleId,
+        method: &str,
+        limit: u64,
+        reserved: u64,
+        pure: bool,
+    ) {
+        let pure = pure || self.is_pure();
+        self.inner.push(CallData {
+            module_id,
+            method: String::from(method),
+            limit,
+            reserved,
+            pure,
+        })
     }
 
     /// Pop a call from the call stack.
@@ -49,8 +79,48 @@ impl CallStack {
         }
     }
 
+    /// Return the contract the initiating call was made to
+    pub fn entry_module(&self) -> ModuleId {
+        self.inner[0].module_id
+    }
+
+    /// Return the contracts on the call stack, from the one the initiating
+    /// call was made to, to the currently executing one
+    pub fn module_ids(&self) -> Vec<ModuleId> {
+        self.inner.iter().map(|c| c.module_id).collect()
+    }
+
+    /// Return the contracts that called the currently executing one, from
+    /// the one the initiating call was made to, to its direct caller
+    pub fn callers(&self) -> Vec<ModuleId> {
+        let len = self.inner.len();
+        self.inner[..len - 1].iter().map(|c| c.module_id).collect()
+    }
+
+    /// Return the method of the currently executing contract
+    pub fn method(&self) -> &str {
+        &self.inner[self.inner.len() - 1].method
+    }
+
     /// Return the point limit given to the currently executing contract
     pub fn limit(&self) -> u64 {
         self.inner[self.inner.len() - 1].limit
     }
+
+    /// Return the point limit given to the initiating call
+    pub fn tx_limit(&self) -> u64 {
+        self.inner[0].limit
+    }
+
+    /// Return the points kept by all callers of the currently executing
+    /// contract, which it can not spend but are still available to the
+    /// transaction
+    pub fn reserved(&self) -> u64 {
+        self.inner.iter().map(|c| c.reserved).sum()
+    }
+
+    /// Return true if the currently executing contract is in a pure frame
+    pub fn is_pure(&self) -> bool {
+        self.inner.last().map(|c| c.pure).unwrap_or(false)
+    }
 }
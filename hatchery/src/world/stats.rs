@@ -0,0 +1,71 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::path::Path;
+
+use crate::error::Error;
+use crate::Error::PersistenceError;
+
+/// Resource usage of a world, aggregated over all its modules.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct WorldStats {
+    pub(super) modules: usize,
+    pub(super) memory_bytes: usize,
+    pub(super) commits: usize,
+    pub(super) snapshots: usize,
+    pub(super) disk_usage: u64,
+    pub(super) points_spent: u64,
+}
+
+impl WorldStats {
+    /// Return the number of modules deployed.
+    pub fn modules(&self) -> usize {
+        self.modules
+    }
+
+    /// Return the size of the memories of the modules, each mapped to a file
+    /// in the storage path.
+    pub fn memory_bytes(&self) -> usize {
+        self.memory_bytes
+    }
+
+    /// Return the number of commits made.
+    pub fn commits(&self) -> usize {
+        self.commits
+    }
+
+    /// Return the number of distinct module snapshots across all commits.
+    pub fn snapshots(&self) -> usize {
+        self.snapshots
+    }
+
+    /// Return the size in bytes of the files in the storage path.
+    pub fn disk_usage(&self) -> u64 {
+        self.disk_usage
+    }
+
+    /// Return the points spent by all calls performed on the world, cached
+    /// queries excluded.
+    pub fn points_spent(&self) -> u64 {
+        self.points_spent
+    }
+}
+
+/// Return the size in bytes of the files in the given directory.
+pub fn disk_usage(path: &Path) -> Result<u64, Error> {
+    let mut usage = 0;
+
+    for entry in std::fs::read_dir(path).map_err(PersistenceError)? {
+        let metadata = entry
+            .and_then(|entry| entry.metadata())
+            .map_err(PersistenceError)?;
+        if metadata.is_file() {
+            usage += metadata.len();
+        }
+    }
+
+    Ok(usage)
+}
@@ -8,24 +8,89 @@ use std::path::Path;
 use std::sync::Arc;
 
 use wasmer::wasmparser::Operator;
-use wasmer::{BaseTunables, CompilerConfig, Store, Target, Universal};
+use wasmer::{
+    BaseTunables, CompilerConfig, Features, Store, Target, Universal,
+};
 use wasmer_compiler_singlepass::Singlepass;
 use wasmer_middlewares::Metering;
 
+use super::coverage::Coverage;
+use super::instructions::Instructions;
+
 fn cost_function(_: &Operator) -> u64 {
     1
 }
 
+/// The WebAssembly proposals modules are allowed to use.
+///
+/// Every proposal is set explicitly when compiling modules, so that which
+/// modules are accepted does not change with the defaults of the runtime.
+/// Proposals not listed here are always rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WasmFeatures {
+    pub simd: bool,
+    pub bulk_memory: bool,
+    pub reference_types: bool,
+    pub multi_value: bool,
+    pub threads: bool,
+}
+
+impl Default for WasmFeatures {
+    fn default() -> Self {
+        Self {
+            simd: true,
+            bulk_memory: true,
+            reference_types: true,
+            multi_value: true,
+            threads: false,
+        }
+    }
+}
+
+impl WasmFeatures {
+    fn to_wasmer(self) -> Features {
+        let mut features = Features::new();
+
+        features.simd = self.simd;
+        features.bulk_memory = self.bulk_memory;
+        features.reference_types = self.reference_types;
+        features.multi_value = self.multi_value;
+        features.threads = self.threads;
+
+        features.tail_call = false;
+        features.module_linking = false;
+        features.multi_memory = false;
+        features.memory64 = false;
+        features.exceptions = false;
+
+        features
+    }
+}
+
 /// Creates a new store using the singlepass compiler configured to meter using
-/// the default cost function.
-pub fn new_store<P: AsRef<Path>>(path: P) -> Store {
+/// the default cost function, and accepting the given features.
+///
+/// The instructions executed are counted as well. If `coverage` is set,
+/// functions are also instrumented to count their calls. Both happen after
+/// metering, so the instrumentation is not charged for.
+pub fn new_store<P: AsRef<Path>>(
+    path: P,
+    features: WasmFeatures,
+    coverage: bool,
+) -> Store {
     let mut compiler_config = Singlepass::default();
     let metering = Arc::new(Metering::new(0, cost_function));
 
     compiler_config.push_middleware(metering);
+    compiler_config.push_middleware(Arc::new(Instructions::default()));
+    if coverage {
+        compiler_config.push_middleware(Arc::new(Coverage::default()));
+    }
 
     Store::new_with_tunables_and_path(
-        &Universal::new(compiler_config).engine(),
+        &Universal::new(compiler_config)
+            .features(features.to_wasmer())
+            .engine(),
         BaseTunables::for_target(&Target::default()),
         path.as_ref().into(),
     )
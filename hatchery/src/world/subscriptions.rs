@@ -0,0 +1,107 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use dallo::ModuleId;
+use rkyv::{AlignedVec, Deserialize, Infallible};
+
+use crate::error::Error;
+use crate::Error::PersistenceError;
+
+type Entry = (ModuleId, Vec<ModuleId>);
+
+/// A subscription or unsubscription made by a module during a call, applied
+/// once the call succeeds.
+#[derive(Debug, Clone, Copy)]
+pub struct SubscriptionChange {
+    pub subscriber: ModuleId,
+    pub publisher: ModuleId,
+    pub subscribe: bool,
+}
+
+/// The modules subscribed to the events of each module.
+#[derive(Debug, Default, Clone)]
+pub struct Subscriptions {
+    subscribers: BTreeMap<ModuleId, BTreeSet<ModuleId>>,
+}
+
+impl Subscriptions {
+    pub fn subscribe(&mut self, subscriber: ModuleId, publisher: ModuleId) {
+        self.subscribers
+            .entry(publisher)
+            .or_default()
+            .insert(subscriber);
+    }
+
+    pub fn unsubscribe(&mut self, subscriber: ModuleId, publisher: ModuleId) {
+        if let Some(subscribers) = self.subscribers.get_mut(&publisher) {
+            subscribers.remove(&subscriber);
+            if subscribers.is_empty() {
+                self.subscribers.remove(&publisher);
+            }
+        }
+    }
+
+    pub fn apply(&mut self, change: SubscriptionChange) {
+        match change.subscribe {
+            true => self.subscribe(change.subscriber, change.publisher),
+            false => self.unsubscribe(change.subscriber, change.publisher),
+        }
+    }
+
+    /// Return the modules subscribed to the given module, ordered by id.
+    pub fn subscribers(
+        &self,
+        publisher: &ModuleId,
+    ) -> impl Iterator<Item = &ModuleId> {
+        self.subscribers.get(publisher).into_iter().flatten()
+    }
+
+    /// Write the subscriptions to the given file.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let entries: Vec<Entry> = self
+            .subscribers
+            .iter()
+            .map(|(publisher, subscribers)| {
+                (*publisher, subscribers.iter().copied().collect())
+            })
+            .collect();
+
+        let bytes = rkyv::to_bytes::<_, 1024>(&entries)
+            .expect("Serializing the subscriptions should succeed");
+        std::fs::write(path, bytes).map_err(PersistenceError)
+    }
+
+    /// Read the subscriptions from the given file, returning no
+    /// subscriptions if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let mut subscriptions = Subscriptions::default();
+
+        if !path.exists() {
+            return Ok(subscriptions);
+        }
+
+        let contents = std::fs::read(path).map_err(PersistenceError)?;
+
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(&contents);
+
+        let archived = rkyv::check_archived_root::<Vec<Entry>>(&bytes[..])
+            .map_err(|_| Error::ValidationError)?;
+        let entries: Vec<Entry> =
+            archived.deserialize(&mut Infallible).expect("Infallible");
+
+        for (publisher, subscribers) in entries {
+            for subscriber in subscribers {
+                subscriptions.subscribe(subscriber, publisher);
+            }
+        }
+
+        Ok(subscriptions)
+    }
+}
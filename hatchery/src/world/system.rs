@@ -0,0 +1,64 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+
+use dallo::ModuleId;
+
+pub struct SystemModules {
+    map: BTreeMap<ModuleId, Box<dyn SystemModule>>,
+}
+
+impl Debug for SystemModules {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.map.keys()).finish()
+    }
+}
+
+impl SystemModules {
+    pub fn new() -> Self {
+        SystemModules {
+            map: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert<M>(&mut self, module_id: ModuleId, module: M)
+    where
+        M: 'static + SystemModule,
+    {
+        self.map.insert(module_id, Box::new(module));
+    }
+
+    pub fn contains(&self, module_id: &ModuleId) -> bool {
+        self.map.contains_key(module_id)
+    }
+
+    pub fn call(
+        &self,
+        module_id: &ModuleId,
+        name: &str,
+        buf: &mut [u8],
+        len: u32,
+    ) -> Option<u32> {
+        self.map
+            .get(module_id)
+            .and_then(|module| module(name, buf, len))
+    }
+}
+
+/// A module implemented on the host, with an id in the namespace reserved by
+/// [`ModuleId::reserved`].
+///
+/// Modules query it like any deployed module. The name of the method queried
+/// is passed as the first argument, followed by the buffer containing the
+/// argument and its length, exactly like a [`NativeQuery`]. The implementor
+/// should emplace the return in the same buffer and return its length, or
+/// return `None` if it has no such method.
+///
+/// [`NativeQuery`]: crate::NativeQuery
+pub trait SystemModule: Fn(&str, &mut [u8], u32) -> Option<u32> {}
+impl<F> SystemModule for F where F: Fn(&str, &mut [u8], u32) -> Option<u32> {}
@@ -0,0 +1,129 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem;
+
+use dallo::ModuleId;
+
+use crate::world::access::Accesses;
+
+/// Size of the pages module memories are split into in a [`Witness`].
+pub const WITNESS_PAGE_SIZE: usize = 4096;
+
+/// The memory pages of the modules taking part in a call, as they were
+/// before the call, allowing a verifier holding the state root to re-execute
+/// it without the rest of the state.
+///
+/// The pages of a module are the ones accessed during the call, including
+/// the ones the host accessed on its behalf, such as to copy arguments or to
+/// roll back a failed call. Pages not present were not accessed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Witness {
+    root: [u8; 32],
+    memories: BTreeMap<ModuleId, MemoryWitness>,
+}
+
+impl Witness {
+    /// Return the root of the state at the start of the call, which the
+    /// pages were taken from.
+    pub fn root(&self) -> &[u8; 32] {
+        &self.root
+    }
+
+    /// Return the pages of each module taking part in the call.
+    pub fn memories(&self) -> &BTreeMap<ModuleId, MemoryWitness> {
+        &self.memories
+    }
+}
+
+/// The pages of the memory of a single module in a [`Witness`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MemoryWitness {
+    len: usize,
+    pages: BTreeMap<usize, Vec<u8>>,
+    written: BTreeSet<usize>,
+}
+
+impl MemoryWitness {
+    fn new(accesses: Accesses) -> Self {
+        let len = accesses.len;
+        let (pages, written) = accesses.split(WITNESS_PAGE_SIZE);
+
+        Self {
+            len,
+            pages,
+            written,
+        }
+    }
+
+    /// Return the length of the memory before the call.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return whether the memory was empty before the call.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the pages accessed during the call, as they were before it,
+    /// keyed by their index.
+    pub fn pages(&self) -> &BTreeMap<usize, Vec<u8>> {
+        &self.pages
+    }
+
+    /// Return the indices of the pages written during the call.
+    pub fn written(&self) -> &BTreeSet<usize> {
+        &self.written
+    }
+}
+
+/// Collects the pages accessed in the memories of the modules entered during
+/// a call, when witnesses are enabled.
+#[derive(Debug, Default)]
+pub struct WitnessRecorder {
+    enabled: bool,
+    root: [u8; 32],
+    memories: BTreeMap<ModuleId, MemoryWitness>,
+}
+
+impl WitnessRecorder {
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.memories.clear();
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Start recording a call made on the state with the given root.
+    pub fn start(&mut self, root: [u8; 32]) {
+        self.root = root;
+        self.memories.clear();
+    }
+
+    /// Record the accesses made to the memory of a module during the call.
+    pub fn record(&mut self, module_id: ModuleId, accesses: Accesses) {
+        if self.enabled {
+            self.memories
+                .insert(module_id, MemoryWitness::new(accesses));
+        }
+    }
+
+    /// Produce the witness of the call.
+    pub fn finish(&mut self) -> Option<Witness> {
+        if !self.enabled {
+            return None;
+        }
+
+        Some(Witness {
+            root: self.root,
+            memories: mem::take(&mut self.memories),
+        })
+    }
+}
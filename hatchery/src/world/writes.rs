@@ -0,0 +1,139 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::mem;
+use std::ops::Range;
+
+use dallo::ModuleId;
+
+use crate::env::Env;
+use crate::error::Error;
+use crate::instance::{Instance, SavedMemory};
+
+/// Size of the pages writes to memory are counted in.
+const PAGE_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, Copy)]
+struct WriteLimit {
+    pages: usize,
+    points_per_page: u64,
+}
+
+/// Counts the memory pages written by the modules taking part in a
+/// transaction, when a write limit is set.
+///
+/// Pages are counted by comparing the memories of the modules at the end of
+/// the transaction with the ones they had when first entered, looking only
+/// at the regions making up their state. The memories are written back if
+/// the transaction exceeds the limit.
+#[derive(Debug, Default)]
+pub struct WriteTracker {
+    limit: Option<WriteLimit>,
+    memories: BTreeMap<ModuleId, SavedMemory>,
+}
+
+impl WriteTracker {
+    pub fn set_limit(&mut self, pages: usize, points_per_page: u64) {
+        self.limit = Some(WriteLimit {
+            pages,
+            points_per_page,
+        });
+    }
+
+    pub fn clear_limit(&mut self) {
+        self.limit = None;
+        self.memories.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.memories.clear();
+    }
+
+    /// Record the memory of a module, if it was not yet entered during the
+    /// transaction.
+    pub fn enter(&mut self, module_id: ModuleId, instance: &Instance) {
+        if self.limit.is_some() {
+            self.memories
+                .entry(module_id)
+                .or_insert_with(|| instance.save_memory());
+        }
+    }
+
+    /// Count the pages written during the transaction to `module_id`, which
+    /// spent `spent` out of `limit` points, returning the points spent
+    /// including the ones charged for the pages.
+    ///
+    /// If the transaction exceeds the limit, or can't pay for the pages, the
+    /// memories of the modules it entered are rolled back before failing.
+    pub fn finish(
+        &mut self,
+        module_id: ModuleId,
+        environments: &BTreeMap<ModuleId, Env>,
+        spent: u64,
+        limit: u64,
+    ) -> Result<u64, Error> {
+        let memories = mem::take(&mut self.memories);
+
+        let write_limit = match self.limit {
+            Some(write_limit) => write_limit,
+            None => return Ok(spent),
+        };
+
+        let pages: usize = memories
+            .iter()
+            .map(|(id, before)| {
+                let instance = environments[id].inner();
+                instance.with_memory(|after| {
+                    let regions = instance.state_regions(after.len());
+                    written_pages(before.memory(), after, &regions)
+                })
+            })
+            .sum();
+
+        let spent = spent.saturating_add(
+            (pages as u64).saturating_mul(write_limit.points_per_page),
+        );
+
+        let err = if pages > write_limit.pages {
+            Error::WriteLimit(module_id)
+        } else if spent > limit {
+            Error::OutOfPoints(module_id)
+        } else {
+            return Ok(spent);
+        };
+
+        for (id, memory) in memories {
+            environments[&id].inner_mut().restore_all_memory(memory);
+        }
+
+        Err(err)
+    }
+}
+
+/// Count the pages whose bytes within `regions` differ between two versions
+/// of a memory, taking bytes past the end of the first to have been zeroes.
+fn written_pages(
+    before: &[u8],
+    after: &[u8],
+    regions: &[Range<usize>],
+) -> usize {
+    (0..after.len())
+        .step_by(PAGE_SIZE)
+        .filter(|ofs| {
+            let end = after.len().min(ofs + PAGE_SIZE);
+            regions
+                .iter()
+                .map(|region| region.start.max(*ofs)..region.end.min(end))
+                .filter(|range| !range.is_empty())
+                .any(|range| {
+                    let grown = before.len().clamp(range.start, range.end);
+                    before[range.start..grown] != after[range.start..grown]
+                        || after[grown..range.end].iter().any(|b| *b != 0)
+                })
+        })
+        .count()
+}
@@ -42,7 +42,7 @@ pub fn box_set_store_restore_get() -> Result<(), Error> {
         first_world.storage_path().clone_into(&mut storage_path);
     }
 
-    let mut second_world = World::new(storage_path);
+    let mut second_world = World::new(storage_path)?;
 
     let second_id = second_world.deploy(module_bytecode!("box"))?;
 
@@ -78,3 +78,111 @@ pub fn world_persist_restore() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+pub fn world_storage_locked() -> Result<(), Error> {
+    let world = World::ephemeral()?;
+    let storage_path = world.storage_path().to_path_buf();
+
+    assert!(matches!(
+        World::new(&storage_path),
+        Err(Error::StorageLocked(_))
+    ));
+    assert!(matches!(
+        World::new_read_only(&storage_path),
+        Err(Error::StorageLocked(_))
+    ));
+
+    drop(world);
+
+    let first = World::new_read_only(&storage_path)?;
+    let _second = World::new_read_only(&storage_path)?;
+
+    assert!(matches!(first.persist(), Err(Error::StorageLocked(_))));
+    assert!(matches!(
+        World::new(&storage_path),
+        Err(Error::StorageLocked(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+pub fn world_release_module() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let box_id = world.deploy(module_bytecode!("box"))?;
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+
+    world.transact::<i16, ()>(box_id, "set", 17)?;
+    let first = world.persist()?;
+
+    world.release(counter_id)?;
+
+    let err = world
+        .query::<_, i64>(counter_id, "read_value", ())
+        .expect_err("released modules can't be queried");
+    assert!(matches!(err, Error::ModuleNotFound(id) if id == counter_id));
+    assert!(matches!(
+        world.release(counter_id),
+        Err(Error::ModuleNotFound(_))
+    ));
+
+    let value = world.query::<_, Option<i16>>(box_id, "get", ())?;
+    assert_eq!(*value, Some(17));
+
+    let second = world.persist()?;
+    assert_ne!(first, second);
+    assert_eq!(world.root(), second);
+
+    let modules = world.canonical_modules(second)?;
+    assert_eq!(modules.len(), 1);
+    assert_eq!(modules[0].0, box_id);
+
+    Ok(())
+}
+
+#[test]
+pub fn world_drop_releases_storage() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    world.deploy(module_bytecode!("box"))?;
+
+    let storage_path = world.storage_path().to_path_buf();
+    drop(world);
+
+    let _world = World::new(storage_path)?;
+
+    Ok(())
+}
+
+#[test]
+pub fn world_clone_outlives_original() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    let id = world.deploy(module_bytecode!("box"))?;
+    world.transact::<i16, ()>(id, "set", 0x11)?;
+
+    let clone = world.clone();
+    drop(world);
+
+    let value = clone.query::<_, Option<i16>>(id, "get", ())?;
+    assert_eq!(*value, Some(0x11));
+
+    Ok(())
+}
+
+#[test]
+pub fn ephemeral_world_in_scratch_dir() -> Result<(), Error> {
+    let scratch = World::ephemeral()?;
+    let scratch_dir = scratch.storage_path();
+
+    let mut world = World::ephemeral_in(scratch_dir)?;
+    world.deploy(module_bytecode!("box"))?;
+
+    let storage_path = world.storage_path().to_path_buf();
+    assert!(storage_path.starts_with(scratch_dir));
+    drop(world);
+
+    assert!(storage_path.exists());
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use hatchery::{module_bytecode, Error, Receipt, World};
+
+#[test]
+pub fn cached_query_invalidated_by_transact() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    world.set_query_cache(true);
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    let first: Receipt<i64> = world.query(id, "read_value", ())?;
+    let second: Receipt<i64> = world.query(id, "read_value", ())?;
+
+    assert_eq!(*first, 0xfc);
+    assert_eq!(first, second);
+
+    let _: Receipt<()> = world.transact(id, "increment", ())?;
+
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfd);
+
+    Ok(())
+}
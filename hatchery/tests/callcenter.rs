@@ -4,8 +4,8 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use dallo::{RawQuery, RawResult, RawTransaction};
-use hatchery::{module_bytecode, Error, Receipt, World};
+use dallo::{ModuleId, RawQuery, RawResult, RawTransaction};
+use hatchery::{module_bytecode, Error, ErrorCode, Receipt, World};
 
 #[test]
 pub fn world_center_counter_read() -> Result<(), Error> {
@@ -59,6 +59,29 @@ pub fn world_center_counter_direct() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+pub fn world_raw_calls() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+
+    let res = world.query_raw(counter_id, RawQuery::new("read_value", ()))?;
+    assert_eq!(res.cast::<i64>(), 0xfc);
+
+    world.transact_raw(counter_id, RawTransaction::new("increment", ()))?;
+
+    let res = world.query_raw(counter_id, RawQuery::new("read_value", ()))?;
+    assert_eq!(res.cast::<i64>(), 0xfd);
+
+    let unknown = ModuleId::from([0; 32]);
+    assert!(matches!(
+        world.query_raw(unknown, RawQuery::new("read_value", ())),
+        Err(Error::ModuleNotFound(module_id)) if module_id == unknown
+    ));
+
+    Ok(())
+}
+
 #[test]
 pub fn world_center_counter_delegated() -> Result<(), Error> {
     let mut world = World::ephemeral()?;
@@ -123,3 +146,216 @@ pub fn world_center_caller() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+pub fn world_center_counter_deferred() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    let rt = RawTransaction::new("increment", ());
+
+    let receipt: Receipt<()> =
+        world.transact(center_id, "defer_transaction", (counter_id, rt))?;
+
+    assert_eq!(receipt.deferred().len(), 1);
+    assert!(receipt.deferred()[0].spent() > 0);
+
+    let value: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(*value, 0xfd);
+
+    Ok(())
+}
+
+#[test]
+pub fn world_center_deferred_meta() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let everest_id = world.deploy(module_bytecode!("everest"))?;
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    let meta = b"paid by alice".to_vec();
+    let rt = RawTransaction::new("get_tx_meta", ());
+    let receipt: Receipt<()> = world.transact_with_meta(
+        center_id,
+        "defer_transaction",
+        (everest_id, rt.clone()),
+        meta.clone(),
+    )?;
+
+    assert_eq!(receipt.deferred().len(), 1);
+    let received: Vec<u8> = receipt.deferred()[0].cast();
+    assert_eq!(received, meta);
+
+    // raw transactions carry no metadata, whatever the previous one had
+    let receipt = world.transact_raw(everest_id, rt)?;
+    let received: Vec<u8> = receipt.cast();
+    assert!(received.is_empty());
+
+    Ok(())
+}
+
+#[test]
+pub fn world_center_deferred_failure() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    let rt = RawTransaction::new("increment_and_revert", ());
+    let receipt: Receipt<()> =
+        world.transact(center_id, "defer_transaction", (counter_id, rt))?;
+
+    assert!(receipt.deferred().is_empty());
+    assert_eq!(receipt.failures().len(), 1);
+
+    let failure = &receipt.failures()[0];
+    assert_eq!(failure.module(), counter_id);
+    assert_eq!(failure.method(), "increment_and_revert");
+    assert_eq!(failure.code(), ErrorCode::Revert);
+    assert!(failure.spent() > 0);
+
+    let value: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(*value, 0xfc);
+
+    // the transactions deferred after the failed one are still performed
+    let rt = RawTransaction::new("increment", ());
+    let receipt: Receipt<()> =
+        world.transact(center_id, "defer_transaction", (counter_id, rt))?;
+    assert_eq!(receipt.deferred().len(), 1);
+    assert!(receipt.failures().is_empty());
+
+    let value: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(*value, 0xfd);
+
+    Ok(())
+}
+
+#[test]
+pub fn world_center_defer_in_query() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    let rt = RawTransaction::new("increment", ());
+    let result =
+        world.query::<_, ()>(center_id, "defer_transaction", (counter_id, rt));
+    assert!(
+        matches!(result, Err(Error::DeferredInQuery(id)) if id == center_id)
+    );
+
+    let value: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(*value, 0xfc);
+
+    Ok(())
+}
+
+#[test]
+pub fn world_center_entry_module() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    let entry_module: Receipt<ModuleId> =
+        world.query(center_id, "return_entry_module", ())?;
+    assert_eq!(*entry_module, center_id);
+
+    let rq = RawQuery::new("return_entry_module", ());
+    let res = world.query::<_, RawResult>(
+        center_id,
+        "delegate_query",
+        (center_id, rq),
+    )?;
+    let entry_module: ModuleId = res.cast();
+    assert_eq!(entry_module, center_id);
+
+    Ok(())
+}
+
+#[test]
+pub fn world_center_typed_errors() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    let err = world
+        .query::<_, ()>(center_id, "revert_with", 7u32)
+        .expect_err("reverting should fail the call");
+    assert_eq!(err.code(), ErrorCode::Revert);
+    assert!(matches!(err, Error::Revert(ref err) if err.module() == center_id));
+
+    let ret: Receipt<Result<(), u32>> =
+        world.query(center_id, "try_revert", 7u32)?;
+    assert_eq!(*ret, Err(7));
+
+    let reverts = ret.reverts();
+    assert_eq!(reverts.len(), 1);
+    assert_eq!(reverts[0].module(), center_id);
+    assert_eq!(reverts[0].method(), "revert_with");
+    assert_eq!(reverts[0].decode::<u32>()?, 7);
+
+    let code: Receipt<u16> =
+        world.query(center_id, "try_missing", counter_id)?;
+    let code = ErrorCode::from_code(*code).expect("the code should be known");
+    assert_ne!(code, ErrorCode::Revert);
+
+    Ok(())
+}
+
+#[test]
+pub fn world_center_try_transact_rollback() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    // the counter reverts after incrementing, so the increment is undone
+    let ret: Receipt<Option<i64>> =
+        world.transact(center_id, "try_increment_and_revert", counter_id)?;
+    assert_eq!(*ret, Some(0xfd));
+
+    let value: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(*value, 0xfc);
+
+    Ok(())
+}
+
+#[test]
+pub fn world_center_panic() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    match world.query::<_, ()>(center_id, "panic_with", 7u32) {
+        Err(Error::Panic { module, message }) => {
+            assert_eq!(module, center_id);
+            assert!(message.contains("panicked with 7"));
+            assert!(message.contains("src/lib.rs"));
+        }
+        _ => panic!("expected the module to panic"),
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn world_center_selector() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    let value: Receipt<i64> =
+        world.query(center_id, "query_counter_selector", counter_id)?;
+    assert_eq!(*value, 0xfc);
+
+    let err = world
+        .query::<_, i64>(center_id, "query_counter_selector", center_id)
+        .expect_err("the callcenter has no read_value method");
+    assert_eq!(err.code(), ErrorCode::SelectorNotFound);
+
+    Ok(())
+}
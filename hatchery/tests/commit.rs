@@ -0,0 +1,595 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use dallo::ModuleId;
+use hatchery::{
+    module_bytecode, CommitId, Error, ModuleSnapshotId, Receipt,
+    SnapshotPolicy, World,
+};
+
+#[test]
+fn export_module_state() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let box_id = world.deploy(module_bytecode!("box"))?;
+    let vector_id = world.deploy(module_bytecode!("vector"))?;
+
+    let first = world.persist()?;
+
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    let second = world.persist()?;
+
+    assert_ne!(first, second);
+
+    for module_id in [counter_id, box_id, vector_id] {
+        let (memory, proof) = world.export_module_state(second, module_id)?;
+        assert_eq!(proof.module_id(), &module_id);
+        assert!(proof.verify(&second, &memory));
+        assert!(!proof.verify(&first, &memory));
+    }
+
+    let (old_memory, old_proof) =
+        world.export_module_state(first, counter_id)?;
+    let (new_memory, _) = world.export_module_state(second, counter_id)?;
+
+    assert_ne!(old_memory, new_memory);
+    assert!(old_proof.verify(&first, &old_memory));
+    assert!(!old_proof.verify(&first, &new_memory));
+
+    Ok(())
+}
+
+#[test]
+fn root_tracks_state() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let box_id = world.deploy(module_bytecode!("box"))?;
+
+    let root = world.root();
+    assert_eq!(root, world.persist()?);
+
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    world.transact::<i16, ()>(box_id, "set", 0x11)?;
+
+    let changed = world.root();
+    assert_ne!(root, changed);
+    assert_eq!(changed, world.persist()?);
+
+    world.restore()?;
+    assert_eq!(changed, world.root());
+
+    Ok(())
+}
+
+#[test]
+fn read_only_queries() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    let first = world.persist()?;
+    let read_only = world.read_only();
+
+    let value: Receipt<i64> = read_only.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfc);
+    assert_eq!(read_only.root(), first);
+
+    // the handle follows the world, including unpersisted changes
+    let _: Receipt<()> = world.transact(id, "increment", ())?;
+    let value: Receipt<i64> = read_only.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfd);
+    assert_eq!(read_only.root(), world.root());
+
+    Ok(())
+}
+
+#[test]
+fn pinned_commits() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    let storage_path = world.storage_path().to_path_buf();
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    let first = world.persist()?;
+    let _: Receipt<()> = world.transact(id, "increment", ())?;
+    let second = world.persist()?;
+
+    world.pin(first)?;
+    world.pin(second)?;
+    world.unpin(second)?;
+    assert_eq!(world.pinned(), vec![first]);
+
+    let unknown = CommitId::from([0; 32]);
+    assert!(matches!(
+        world.pin(unknown),
+        Err(Error::CommitNotFound(commit_id)) if commit_id == unknown
+    ));
+
+    drop(world);
+
+    // commits made before the world was reopened can still be pinned
+    let mut world = World::new(storage_path)?;
+    assert_eq!(world.pinned(), vec![first]);
+    world.pin(second)?;
+
+    let mut pinned = vec![first, second];
+    pinned.sort();
+    assert_eq!(world.pinned(), pinned);
+
+    Ok(())
+}
+
+#[test]
+fn commits_survive_reopen() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    let storage_path = world.storage_path().to_path_buf();
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+
+    let first = world.persist()?;
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    let second = world.persist()?;
+
+    let modules = world.commit_modules(first)?;
+    drop(world);
+
+    let world = World::new(storage_path)?;
+    assert_eq!(world.commit_modules(first)?, modules);
+
+    let (memory, proof) = world.export_module_state(second, counter_id)?;
+    assert!(proof.verify(&second, &memory));
+    assert!(!proof.verify(&first, &memory));
+
+    world.verify(first)?;
+    world.verify(second)?;
+
+    Ok(())
+}
+
+#[test]
+fn verify_commit() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let box_id = world.deploy(module_bytecode!("box"))?;
+
+    let first = world.persist()?;
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    let second = world.persist()?;
+
+    world.verify(first)?;
+    world.verify(second)?;
+
+    // corrupt the snapshot of the box, shared by both commits
+    let (mut memory, _) = world.export_module_state(second, box_id)?;
+    memory[0] ^= 0xff;
+
+    let memory_path = world.memory_path(&box_id);
+    let snapshot_prefix =
+        format!("{}_", memory_path.file_name().unwrap().to_str().unwrap());
+
+    for entry in std::fs::read_dir(world.storage_path()).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        if name.starts_with(&snapshot_prefix) {
+            std::fs::write(&path, &memory).unwrap();
+        }
+    }
+
+    assert!(matches!(
+        world.verify(second),
+        Err(Error::CorruptSnapshot { module, commit })
+            if module == box_id && commit == second
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn deploy_with_memory() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+    let _: Receipt<()> = world.transact(id, "increment", ())?;
+    let commit_id = world.persist()?;
+
+    let (memory, proof) = world.export_module_state(commit_id, id)?;
+
+    let mut migrated = World::ephemeral()?;
+
+    assert!(matches!(
+        migrated.deploy_with_memory(
+            module_bytecode!("counter"),
+            &memory,
+            [0; 32]
+        ),
+        Err(Error::MemoryMismatch(module_id)) if module_id == id
+    ));
+
+    let migrated_id = migrated.deploy_with_memory(
+        module_bytecode!("counter"),
+        &memory,
+        proof.memory_hash(),
+    )?;
+    assert_eq!(migrated_id, id);
+
+    let value: Receipt<i64> = migrated.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfd);
+    assert_eq!(migrated.persist()?, commit_id);
+
+    Ok(())
+}
+
+#[test]
+fn module_layout() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+    let commit_id = world.persist()?;
+
+    let layout = world.module_layout(id)?;
+    let (memory, _) = world.export_module_state(commit_id, id)?;
+
+    assert!(layout.arg_buf.end <= layout.data_end);
+    assert!(layout.data_end <= layout.heap_base);
+    assert_eq!(layout.stack_region, layout.data_end..layout.heap_base);
+    assert!(layout.heap_base <= memory.len());
+
+    Ok(())
+}
+
+#[test]
+fn snapshot_policy() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+    let layout = world.module_layout(id)?;
+
+    world.set_snapshot_policy(id, SnapshotPolicy::DataAndHeap)?;
+
+    let _: Receipt<()> = world.transact(id, "increment", ())?;
+    let commit_id = world.persist()?;
+
+    world.verify(commit_id)?;
+
+    let (memory, proof) = world.export_module_state(commit_id, id)?;
+    assert!(proof.verify(&commit_id, &memory));
+    assert!(memory[layout.stack_region.clone()].iter().all(|b| *b == 0));
+
+    // the same regions given explicitly make up the same state
+    let ranges = vec![
+        layout.stack_region.end..usize::MAX,
+        0..layout.stack_region.start,
+    ];
+    world.set_snapshot_policy(id, SnapshotPolicy::Ranges(ranges))?;
+    assert_eq!(world.root(), commit_id);
+
+    // restoring zeroes the stack region, so the whole memory hashes the same
+    world.restore()?;
+    world.set_snapshot_policy(id, SnapshotPolicy::Full)?;
+    assert_eq!(world.root(), commit_id);
+
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfd);
+
+    Ok(())
+}
+
+#[test]
+fn snapshot_policy_survives_reopen() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    let storage_path = world.storage_path().to_path_buf();
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+    world.set_snapshot_policy(id, SnapshotPolicy::DataAndHeap)?;
+
+    let _: Receipt<()> = world.transact(id, "increment", ())?;
+    let commit_id = world.persist()?;
+    drop(world);
+
+    // the policy is applied again when the module is deployed
+    let mut world = World::new(storage_path)?;
+    world.deploy(module_bytecode!("counter"))?;
+    assert_eq!(world.root(), commit_id);
+    world.verify(commit_id)?;
+
+    // and used to verify the module once it's gone
+    world.release(id)?;
+    world.verify(commit_id)?;
+
+    Ok(())
+}
+
+#[test]
+fn restore_modules() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let box_id = world.deploy(module_bytecode!("box"))?;
+
+    let commit_id = world.persist()?;
+
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    world.restore_modules(commit_id, &[counter_id])?;
+
+    assert_eq!(world.root(), commit_id);
+    let value: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(*value, 0xfc);
+
+    // the box no longer matches the commit, so the counter can't be restored
+    // on its own
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    world.transact::<i16, ()>(box_id, "set", 0x11)?;
+
+    assert!(matches!(
+        world.restore_modules(commit_id, &[counter_id]),
+        Err(Error::StateMismatch { module, .. }) if module == box_id
+    ));
+
+    world.restore_modules(commit_id, &[counter_id, box_id])?;
+    assert_eq!(world.root(), commit_id);
+
+    Ok(())
+}
+
+#[test]
+fn call_history() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let vector_id = world.deploy(module_bytecode!("vector"))?;
+
+    // transactions performed before the history is enabled aren't recorded
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+
+    world.set_call_history(true);
+
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    let _: Receipt<()> = world.transact(vector_id, "push", 3i16)?;
+    let first = world.persist()?;
+
+    // transactions that are restored aren't recorded either
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    world.restore()?;
+
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    let second = world.persist()?;
+
+    let history = world.call_history(counter_id)?;
+    assert_eq!(history.len(), 2);
+
+    assert_eq!(history[0].method(), "increment");
+    assert_eq!(history[0].commit(), first);
+    assert_eq!(history[1].commit(), second);
+    assert_eq!(history[0].tx_hash(), history[1].tx_hash());
+
+    let history = world.call_history(vector_id)?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].method(), "push");
+    assert_eq!(history[0].commit(), first);
+    assert_ne!(history[0].arg_hash(), &[0; 32]);
+
+    Ok(())
+}
+
+#[test]
+fn canonical_modules() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    world.deploy(module_bytecode!("vector"))?;
+    world.deploy(module_bytecode!("counter"))?;
+    world.deploy(module_bytecode!("box"))?;
+
+    let commit_id = world.persist()?;
+
+    let modules = world.canonical_modules(commit_id)?;
+    assert_eq!(modules.len(), 3);
+    assert!(modules.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+    assert_eq!(CommitId::compute(modules.iter().copied()), commit_id);
+    assert_eq!(CommitId::compute(modules.into_iter().rev()), commit_id);
+
+    Ok(())
+}
+
+#[test]
+fn commit_modules() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let box_id = world.deploy(module_bytecode!("box"))?;
+
+    let commit_id = world.persist()?;
+    let modules = world.commit_modules(commit_id)?;
+
+    assert_eq!(modules.len(), 2);
+    assert!(modules.contains_key(&counter_id));
+    assert!(modules.contains_key(&box_id));
+
+    let hashes = modules
+        .iter()
+        .map(|(id, snapshot)| (*id, (*snapshot).into()));
+    assert_eq!(CommitId::compute(hashes), commit_id);
+
+    let bytes = rkyv::to_bytes::<_, 256>(&modules).expect("serializes");
+    let archived: BTreeMap<ModuleId, ModuleSnapshotId> =
+        rkyv::from_bytes(&bytes).expect("valid archive");
+    assert_eq!(archived, modules);
+
+    Ok(())
+}
+
+#[test]
+fn world_stats() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let stats = world.stats()?;
+    assert_eq!(stats.modules(), 0);
+    assert_eq!(stats.commits(), 0);
+    assert_eq!(stats.points_spent(), 0);
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let box_id = world.deploy(module_bytecode!("box"))?;
+    world.persist()?;
+
+    let receipt: Receipt<()> = world.transact(box_id, "set", 0x11i16)?;
+    let mut spent = receipt.spent();
+    let receipt: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    spent += receipt.spent();
+    world.persist()?;
+
+    let stats = world.stats()?;
+    assert_eq!(stats.modules(), 2);
+    assert_eq!(stats.commits(), 2);
+    // the counter is unchanged between the commits
+    assert_eq!(stats.snapshots(), 3);
+    assert_eq!(stats.points_spent(), spent);
+    assert!(stats.memory_bytes() >= 2 * 0x10000);
+    assert!(stats.disk_usage() > 0);
+
+    Ok(())
+}
+
+#[test]
+fn commit_hooks() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let box_id = world.deploy(module_bytecode!("box"))?;
+    world.deploy(module_bytecode!("counter"))?;
+
+    let commits = Arc::new(Mutex::new(Vec::new()));
+    let recorded = commits.clone();
+    world.on_commit(move |info, modules| {
+        recorded
+            .lock()
+            .unwrap()
+            .push((info.clone(), modules.to_vec()));
+    });
+
+    world.set_height(7);
+    let first = world.persist()?;
+
+    let _: Receipt<()> = world.transact(box_id, "set", 0x11i16)?;
+    let second = world.persist()?;
+
+    let commits = commits.lock().unwrap();
+    assert_eq!(commits.len(), 2);
+
+    for ((info, modules), commit_id) in commits.iter().zip([first, second]) {
+        assert_eq!(info.id(), commit_id);
+        assert_eq!(info.height(), 7);
+        assert_eq!(*modules, world.canonical_modules(commit_id)?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn prune_sessions() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let commit = world.persist()?;
+
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    world.persist()?;
+
+    // a snapshot of a session that crashed before committing
+    let memory_path = world.memory_path(&counter_id);
+    let mut orphan = memory_path.clone().into_os_string();
+    orphan.push(format!("_{}", "AB".repeat(32)));
+    std::fs::copy(&memory_path, &orphan).expect("copy should succeed");
+
+    let reported = world.prune_sessions(true)?;
+    assert_eq!(reported, vec![PathBuf::from(&orphan)]);
+    assert!(Path::new(&orphan).exists());
+
+    assert_eq!(world.prune_sessions(false)?, reported);
+    assert!(!Path::new(&orphan).exists());
+    assert!(world.prune_sessions(true)?.is_empty());
+
+    world.verify(commit)?;
+
+    Ok(())
+}
+
+#[test]
+fn prune_sessions_after_reopen() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    let storage_path = world.storage_path().to_path_buf();
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+
+    // without a commit index nothing is known to be unreferenced
+    assert!(matches!(
+        world.prune_sessions(true),
+        Err(Error::PersistenceError(_))
+    ));
+
+    let first = world.persist()?;
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    let second = world.persist()?;
+    drop(world);
+
+    let mut world = World::new(storage_path)?;
+
+    let memory_path = world.memory_path(&counter_id);
+    let mut orphan = memory_path.clone().into_os_string();
+    orphan.push(format!("_{}", "AB".repeat(32)));
+    std::fs::copy(&memory_path, &orphan).expect("copy should succeed");
+
+    assert_eq!(world.prune_sessions(false)?, vec![PathBuf::from(&orphan)]);
+
+    world.verify(first)?;
+    world.verify(second)?;
+
+    Ok(())
+}
+
+#[test]
+fn migrate_module_state() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let first = world.persist()?;
+    let (memory, _) = world.export_module_state(first, counter_id)?;
+
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    world.persist()?;
+
+    // a failing migration leaves the memory untouched
+    let err = world
+        .migrate(counter_id, |_, new| {
+            new.memory_mut().fill(0);
+            Err(Error::ValidationError)
+        })
+        .expect_err("migration should fail");
+    assert!(matches!(err, Error::ValidationError));
+
+    let value: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(*value, 0xfd);
+
+    let migrated = world.migrate(counter_id, |old, new| {
+        assert_eq!(old.module_id(), counter_id);
+        assert_eq!(old.memory().len(), memory.len());
+        new.write(0, &memory);
+        Ok(())
+    })?;
+
+    assert_eq!(migrated, first);
+    assert_eq!(world.root(), first);
+
+    let value: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(*value, 0xfc);
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use hatchery::conformance::{self, Call, Mismatch, Vector};
+use hatchery::{Error, World};
+
+#[test]
+fn vectors_match() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    let root = world.root();
+
+    let mismatches = conformance::run(&mut world, conformance::VECTORS)?;
+    assert_eq!(mismatches, vec![]);
+
+    // the module of the vectors is gone after the run
+    assert_eq!(world.root(), root);
+
+    Ok(())
+}
+
+#[test]
+fn vectors_mismatch() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let vectors = [Vector {
+        name: "wrong",
+        calls: &[
+            Call {
+                method: "noop",
+                arg: 0,
+                spent: 2,
+            },
+            Call {
+                method: "count",
+                arg: 2,
+                spent: 20,
+            },
+        ],
+    }];
+
+    let mismatches = conformance::run(&mut world, &vectors)?;
+    assert_eq!(
+        mismatches,
+        vec![Mismatch {
+            vector: "wrong",
+            call: 1,
+            expected: 20,
+            spent: 26,
+        }]
+    );
+
+    Ok(())
+}
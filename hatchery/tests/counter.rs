@@ -4,7 +4,12 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use hatchery::{module_bytecode, Error, Receipt, World};
+use std::panic::{self, AssertUnwindSafe};
+
+use dallo::{ModuleId, RawTransaction};
+use hatchery::{
+    module_bytecode, Error, ErrorCode, MemoryAdvice, Receipt, World,
+};
 
 #[test]
 pub fn counter_trivial() -> Result<(), Error> {
@@ -37,3 +42,225 @@ pub fn counter_increment() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+pub fn invalid_return_data() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    // the bytes of an `i64` are not a valid archived `String`
+    match world.query::<_, String>(id, "read_value", ()) {
+        Err(Error::InvalidReturnData { module, method }) => {
+            assert_eq!(module, id);
+            assert_eq!(method, "read_value");
+        }
+        _ => panic!("expected the return data to be invalid"),
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn counter_read_unchecked() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    let value: Receipt<i64> =
+        unsafe { world.query_unchecked(id, "read_value", ())? };
+
+    assert_eq!(*value, 0xfc);
+
+    Ok(())
+}
+
+#[test]
+pub fn counter_execute() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+    world.persist()?;
+
+    let _: Receipt<()> = world.execute(id, "increment", ())?;
+
+    // the increment was persisted, so restoring keeps it
+    world.restore()?;
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfd);
+
+    let _: Receipt<()> = world.transact(id, "increment", ())?;
+
+    // a failure discards everything since the last persist
+    let result = world.execute::<_, ()>(id, "non_existent", ());
+    assert!(result.is_err());
+
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfd);
+
+    Ok(())
+}
+
+#[test]
+pub fn counter_session() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+    world.persist()?;
+
+    world.with_session(|world| {
+        world.transact::<_, ()>(id, "increment", ())?;
+        world.transact::<_, ()>(id, "increment", ())
+    })?;
+
+    let result = world.with_session(|world| {
+        world.transact::<_, ()>(id, "increment", ())?;
+        world.transact::<_, ()>(id, "non_existent", ())
+    });
+    assert!(result.is_err());
+
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfe);
+
+    let mut session_world = world.clone();
+    let result = panic::catch_unwind(AssertUnwindSafe(move || {
+        session_world.with_session::<_, ()>(|world| {
+            world.transact::<_, ()>(id, "increment", ())?;
+            panic!("the session should be restored");
+        })
+    }));
+    assert!(result.is_err());
+
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfe);
+
+    Ok(())
+}
+
+#[test]
+pub fn counter_batch() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    let increment = (id, RawTransaction::new("increment", ()));
+    let receipts =
+        world.transact_batch(&[increment.clone(), increment.clone()])?;
+    assert_eq!(receipts.len(), 2);
+
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfe);
+
+    // the failing transaction rolls back the one before it
+    let missing = (id, RawTransaction::new("no_such_method", ()));
+    world
+        .transact_batch(&[increment, missing])
+        .expect_err("the batch should fail");
+
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfe);
+
+    Ok(())
+}
+
+#[test]
+pub fn counter_warm_up() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    let timings = world.warm_up(&[id])?;
+    assert_eq!(timings.len(), 1);
+    assert_eq!(timings[0].0, id);
+
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfc);
+
+    let unknown = ModuleId::from([0; 32]);
+    match world.warm_up(&[id, unknown]) {
+        Err(Error::ModuleNotFound(module_id)) => assert_eq!(module_id, unknown),
+        _ => panic!("expected the module not to be found"),
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn counter_memory_advice() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    world.set_memory_advice(id, MemoryAdvice::WillNeed)?;
+    let _: Receipt<()> = world.transact(id, "increment", ())?;
+
+    // dropped pages are read back from the file backing the memory
+    world.set_memory_advice(id, MemoryAdvice::DontNeed)?;
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfd);
+
+    world.set_memory_advice(id, MemoryAdvice::Normal)?;
+
+    let unknown = ModuleId::from([0; 32]);
+    match world.set_memory_advice(unknown, MemoryAdvice::WillNeed) {
+        Err(Error::ModuleNotFound(module_id)) => assert_eq!(module_id, unknown),
+        _ => panic!("expected the module not to be found"),
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn counter_contract_ref() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    let mut counter = world.contract(id)?;
+    assert_eq!(counter.module_id(), id);
+
+    counter.prepare(&["increment", "read_value"])?;
+
+    let _: Receipt<()> = counter.transact("increment", ())?;
+    let value: Receipt<i64> = counter.query("read_value", ())?;
+    assert_eq!(*value, 0xfd);
+
+    match counter.prepare(&["no_such_method"]) {
+        Err(Error::MethodNotFound { module, method }) => {
+            assert_eq!(module, id);
+            assert_eq!(method, "no_such_method");
+        }
+        _ => panic!("expected the method not to be found"),
+    }
+
+    let unknown = ModuleId::from([0; 32]);
+    assert!(matches!(
+        world.contract(unknown),
+        Err(Error::ModuleNotFound(module_id)) if module_id == unknown
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn error_codes() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    let unknown = ModuleId::from([0; 32]);
+    let err = world
+        .query::<_, i64>(unknown, "read_value", ())
+        .expect_err("the module should not be found");
+    assert_eq!(err.code(), ErrorCode::ModuleNotFound);
+
+    let err = world
+        .query::<_, i64>(id, "no_such_method", ())
+        .expect_err("the method should not be found");
+
+    let code = err.code().as_u16();
+    assert_eq!(ErrorCode::from_code(code), Some(err.code()));
+    assert_eq!(ErrorCode::from_code(0), None);
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use hatchery::{module_bytecode, Error, Receipt, World};
+
+#[test]
+pub fn coverage() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let uncovered = world.deploy(module_bytecode!("box"))?;
+
+    world.set_coverage(true);
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    let report = world.coverage();
+    let functions = &report.modules()[&id];
+    assert!(functions.iter().all(|f| f.hits == 0));
+    assert!(!report.modules().contains_key(&uncovered));
+
+    let _: Receipt<()> = world.transact(id, "increment", ())?;
+    let _: Receipt<()> = world.transact(id, "increment", ())?;
+
+    let report = world.coverage();
+    let functions = &report.modules()[&id];
+    assert!(functions.iter().any(|f| f.hits == 2));
+
+    let lcov = report.to_lcov();
+    assert!(lcov.starts_with("TN:\nSF:"));
+    assert!(lcov.ends_with("end_of_record\n"));
+
+    Ok(())
+}
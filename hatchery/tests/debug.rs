@@ -19,3 +19,32 @@ pub fn debug() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+pub fn info() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("debugger"))?;
+
+    let res: Receipt<()> = world.query(id, "info", 3u32)?;
+
+    assert_eq!(res.debug(), &[String::from("Said 3 times")]);
+
+    Ok(())
+}
+
+#[test]
+pub fn debug_limit() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("debugger"))?;
+
+    world.set_debug_limit(1, 8);
+
+    let err = world
+        .query::<_, ()>(id, "debug", String::from("Hello world"))
+        .expect_err("debug output over the limit should fail");
+    assert!(matches!(err, Error::DebugLimit(mid) if mid == id));
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use hatchery::{
+    module_bytecode, Differential, DivergenceKind, Error, Receipt, World,
+};
+
+#[test]
+pub fn differential_agree() -> Result<(), Error> {
+    let mut diff = Differential::new(World::ephemeral()?, World::ephemeral()?);
+
+    let id = diff.deploy(module_bytecode!("counter"))?;
+
+    let _: Receipt<()> = diff.transact(id, "increment", ())?;
+    let value: Receipt<i64> = diff.query(id, "read_value", ())?;
+
+    assert_eq!(*value, 0xfd);
+    assert!(diff.divergences().is_empty());
+
+    Ok(())
+}
+
+#[test]
+pub fn differential_diverge() -> Result<(), Error> {
+    let primary = World::ephemeral()?;
+    let mut secondary = World::ephemeral()?;
+    secondary.set_height(42);
+
+    let mut diff = Differential::new(primary, secondary);
+
+    let id = diff.deploy(module_bytecode!("everest"))?;
+
+    let height: Receipt<u64> = diff.query(id, "get_height", ())?;
+
+    assert_eq!(*height, 0);
+    assert_eq!(diff.divergences().len(), 1);
+    assert_eq!(diff.divergences()[0].kind, DivergenceKind::Return);
+
+    Ok(())
+}
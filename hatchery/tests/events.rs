@@ -4,7 +4,10 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use hatchery::{module_bytecode, Error, Receipt, World};
+use std::sync::{Arc, Mutex};
+
+use dallo::{RawResult, RawTransaction};
+use hatchery::{module_bytecode, Error, ErrorCode, Receipt, World};
 
 #[test]
 pub fn world_center_events() -> Result<(), Error> {
@@ -29,3 +32,273 @@ pub fn world_center_events() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+pub fn events_record_their_frame() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let eventer_id = world.deploy(module_bytecode!("eventer"))?;
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    let receipt: Receipt<()> =
+        world.transact(eventer_id, "emit_events", 1u32)?;
+
+    let event = &receipt.events()[0];
+    assert_eq!(event.frame(), 0);
+    assert!(event.callers().is_empty());
+    assert_eq!(event.method(), "emit_events");
+
+    let rt = RawTransaction::new("emit_events", 1u32);
+    let receipt: Receipt<RawResult> =
+        world.transact(center_id, "delegate_transaction", (eventer_id, rt))?;
+
+    let event = &receipt.events()[0];
+    assert_eq!(event.module_id(), &eventer_id);
+    assert_eq!(event.frame(), 1);
+    assert_eq!(event.callers(), [center_id]);
+    assert_eq!(event.method(), "emit_events");
+
+    Ok(())
+}
+
+#[test]
+pub fn typed_events() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let eventer_id = world.deploy(module_bytecode!("eventer"))?;
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+
+    let receipt: Receipt<()> =
+        world.transact(eventer_id, "emit_events", 4u32)?;
+
+    let events = receipt
+        .events_of::<u32>(eventer_id)
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(events, vec![0, 1, 2, 3]);
+
+    assert_eq!(receipt.events_of::<u32>(counter_id).count(), 0);
+
+    let err = receipt
+        .events_of::<u64>(eventer_id)
+        .next()
+        .expect("there should be an event")
+        .expect_err("a u32 should not decode as a u64");
+    assert!(matches!(err, Error::ValidationError));
+
+    Ok(())
+}
+
+#[test]
+pub fn emit_limit() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let eventer_id = world.deploy(module_bytecode!("eventer"))?;
+
+    world.set_emit_limit(3, usize::MAX);
+
+    let err = world
+        .transact::<_, ()>(eventer_id, "emit_events", 5u32)
+        .expect_err("emitting over the limit should fail");
+    assert!(matches!(err, Error::EmitLimit(mid) if mid == eventer_id));
+
+    let receipt: Receipt<()> =
+        world.transact(eventer_id, "emit_events", 3u32)?;
+    assert_eq!(receipt.events().len(), 3);
+
+    Ok(())
+}
+
+#[test]
+pub fn observe_events() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let eventer_id = world.deploy(module_bytecode!("eventer"))?;
+
+    let observed = Arc::new(Mutex::new(Vec::new()));
+
+    let sink = observed.clone();
+    world.on_event(
+        |event| event.data() != 1u32.to_le_bytes(),
+        move |event| sink.lock().unwrap().push(event.data().to_vec()),
+    );
+
+    let _: Receipt<()> = world.transact(eventer_id, "emit_events", 3u32)?;
+
+    // events emitted by queries are not observed
+    let _: Receipt<()> = world.query(eventer_id, "emit_events", 3u32)?;
+
+    let observed = observed.lock().unwrap();
+    assert_eq!(
+        *observed,
+        vec![0u32.to_le_bytes().to_vec(), 2u32.to_le_bytes().to_vec()]
+    );
+
+    Ok(())
+}
+
+#[test]
+pub fn replay_events() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let eventer_id = world.deploy(module_bytecode!("eventer"))?;
+
+    let _: Receipt<()> = world.transact(eventer_id, "emit_events", 2u32)?;
+    let first = world.persist()?;
+
+    let _: Receipt<()> = world.transact(eventer_id, "emit_events", 3u32)?;
+    let _: Receipt<()> = world.transact(eventer_id, "emit_events", 1u32)?;
+    let second = world.persist()?;
+
+    let _: Receipt<()> = world.transact(eventer_id, "emit_events", 7u32)?;
+    world.restore()?;
+    let third = world.persist()?;
+
+    let commits = world.replay_events(..)?;
+    assert_eq!(commits.len(), 3);
+
+    assert_eq!(commits[0].0, first);
+    assert_eq!(commits[0].1.len(), 2);
+    assert_eq!(commits[1].0, second);
+    assert_eq!(commits[1].1.len(), 4);
+    assert_eq!(commits[2].0, third);
+    assert!(commits[2].1.is_empty());
+
+    let commits = world.replay_events(1..2)?;
+    assert_eq!(commits.len(), 1);
+    assert_eq!(commits[0].0, second);
+
+    Ok(())
+}
+
+#[test]
+pub fn events_bloom() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    let storage_path = world.storage_path().to_path_buf();
+
+    let eventer_id = world.deploy(module_bytecode!("eventer"))?;
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+
+    let _: Receipt<()> = world.transact(eventer_id, "emit_events", 2u32)?;
+    let first = world.persist()?;
+
+    let _: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    let second = world.persist()?;
+
+    assert!(world.maybe_has_events(first, eventer_id)?);
+    assert!(!world.maybe_has_events(first, counter_id)?);
+    assert!(!world.maybe_has_events(second, eventer_id)?);
+    assert!(!world.maybe_has_events(second, counter_id)?);
+
+    drop(world);
+
+    let world = World::new(storage_path)?;
+    assert!(world.maybe_has_events(first, eventer_id)?);
+    assert!(!world.maybe_has_events(first, counter_id)?);
+    assert!(!world.maybe_has_events(second, eventer_id)?);
+
+    Ok(())
+}
+
+#[test]
+pub fn receipt_combinators() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let eventer_id = world.deploy(module_bytecode!("eventer"))?;
+
+    let receipt: Receipt<()> =
+        world.transact(eventer_id, "emit_events", 3u32)?;
+
+    let mut count = 0;
+    for event in &receipt {
+        assert_eq!(event.module_id(), &eventer_id);
+        count += 1;
+    }
+    assert_eq!(count, 3);
+
+    let mapped = receipt.as_ref().map(|_| 42u8);
+    assert_eq!(*mapped, 42);
+    assert_eq!(mapped.events(), receipt.events());
+    assert_eq!(mapped.spent(), receipt.spent());
+
+    let spent = receipt.spent();
+    let (_, events, split_spent) = receipt.split();
+    assert_eq!(events.len(), 3);
+    assert_eq!(split_spent, spent);
+
+    assert_eq!(mapped.into_iter().collect::<Vec<_>>(), events);
+
+    Ok(())
+}
+
+#[test]
+pub fn subscribed_module() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let eventer_id = world.deploy(module_bytecode!("eventer"))?;
+    let listener_id = world.deploy(module_bytecode!("listener"))?;
+
+    let _: Receipt<()> =
+        world.transact(listener_id, "subscribe", eventer_id)?;
+
+    let receipt: Receipt<()> =
+        world.transact(eventer_id, "emit_events", 3u32)?;
+    assert_eq!(receipt.deferred().len(), 3);
+
+    let received: Receipt<u32> = world.query(listener_id, "received", ())?;
+    assert_eq!(*received, 3);
+
+    let _: Receipt<()> =
+        world.transact(listener_id, "unsubscribe", eventer_id)?;
+    let _: Receipt<()> = world.transact(eventer_id, "emit_events", 3u32)?;
+
+    let received: Receipt<u32> = world.query(listener_id, "received", ())?;
+    assert_eq!(*received, 3);
+
+    Ok(())
+}
+
+#[test]
+pub fn failing_subscriber() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let eventer_id = world.deploy(module_bytecode!("eventer"))?;
+    let listener_id = world.deploy(module_bytecode!("listener"))?;
+
+    // subscriptions made by a failed call are discarded
+    let result =
+        world.transact::<_, ()>(listener_id, "subscribe_and_fail", eventer_id);
+    assert!(matches!(result, Err(Error::Panic { .. })));
+
+    let receipt: Receipt<()> =
+        world.transact(eventer_id, "emit_events", 3u32)?;
+    assert!(receipt.deferred().is_empty());
+
+    let _: Receipt<()> =
+        world.transact(listener_id, "subscribe", eventer_id)?;
+    let _: Receipt<()> = world.transact(listener_id, "set_failing", true)?;
+
+    // the failures of the subscriber don't fail the transaction emitting
+    let receipt: Receipt<()> =
+        world.transact(eventer_id, "emit_events", 2u32)?;
+    assert_eq!(receipt.events().len(), 2);
+    assert!(receipt.deferred().is_empty());
+    assert_eq!(receipt.failures().len(), 2);
+    for failure in receipt.failures() {
+        assert_eq!(failure.module(), listener_id);
+        assert_eq!(failure.method(), "on_event");
+        assert_eq!(failure.code(), ErrorCode::Panic);
+    }
+
+    let received: Receipt<u32> = world.query(listener_id, "received", ())?;
+    assert_eq!(*received, 0);
+
+    let _: Receipt<()> = world.transact(listener_id, "set_failing", false)?;
+    let receipt: Receipt<()> =
+        world.transact(eventer_id, "emit_events", 1u32)?;
+    assert_eq!(receipt.deferred().len(), 1);
+
+    let received: Receipt<u32> = world.query(listener_id, "received", ())?;
+    assert_eq!(*received, 1);
+
+    Ok(())
+}
@@ -0,0 +1,131 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use hatchery::{Error, FloatPolicy, WasmFeatures, World};
+
+// A minimal module using `memory.fill`, from the bulk memory proposal.
+const BULK_MEMORY: &str = r#"
+(module
+  (memory (export "memory") 2)
+  (global (export "A") i32 (i32.const 1024))
+  (global (export "SELF_ID") i32 (i32.const 512))
+  (global (export "__heap_base") i32 (i32.const 65536))
+  (func (export "clear") (param i32) (result i32)
+    (memory.fill (i32.const 1024) (i32.const 0) (local.get 0))
+    (i32.const 0)))
+"#;
+
+#[test]
+fn bulk_memory_accepted_by_default() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    world.deploy(BULK_MEMORY.as_bytes())?;
+
+    Ok(())
+}
+
+#[test]
+fn bulk_memory_rejected() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    world.set_wasm_features(WasmFeatures {
+        bulk_memory: false,
+        ..WasmFeatures::default()
+    });
+
+    match world.deploy(BULK_MEMORY.as_bytes()) {
+        Err(Error::CompileError(_)) => {}
+        _ => panic!("expected the module to be rejected"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn deploy_receipt() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let features = WasmFeatures {
+        simd: false,
+        ..WasmFeatures::default()
+    };
+    world.set_wasm_features(features);
+
+    let receipt = world.deploy_with_receipt(BULK_MEMORY.as_bytes())?;
+    assert_eq!(receipt.validated_features(), features);
+    assert!(receipt.artifact_size().is_some_and(|size| size > 0));
+
+    assert_eq!(world.deploy(BULK_MEMORY.as_bytes())?, receipt.module_id());
+
+    Ok(())
+}
+
+// A minimal module performing floating point arithmetic.
+const FLOATS: &str = r#"
+(module
+  (memory (export "memory") 2)
+  (global (export "A") i32 (i32.const 1024))
+  (global (export "SELF_ID") i32 (i32.const 512))
+  (global (export "__heap_base") i32 (i32.const 65536))
+  (func (export "half") (param i32) (result i32)
+    (i32.trunc_f32_s
+      (f32.div (f32.convert_i32_s (local.get 0)) (f32.const 2)))))
+"#;
+
+#[test]
+fn floats_rejected() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let bytecode = wasmer::wat2wasm(FLOATS.as_bytes())
+        .expect("the module should be valid");
+
+    world.set_float_policy(FloatPolicy::Reject);
+
+    match world.deploy(&bytecode) {
+        Err(Error::CompileError(_)) => {}
+        _ => panic!("expected the module to be rejected"),
+    }
+
+    world.set_float_policy(FloatPolicy::Allow);
+    world.deploy(&bytecode)?;
+
+    Ok(())
+}
+
+// A minimal module performing vector floating point arithmetic, without any
+// scalar float operation.
+const VECTOR_FLOATS: &str = r#"
+(module
+  (memory (export "memory") 2)
+  (global (export "A") i32 (i32.const 1024))
+  (global (export "SELF_ID") i32 (i32.const 512))
+  (global (export "__heap_base") i32 (i32.const 65536))
+  (func (export "sum") (param i32) (result i32)
+    (i32x4.extract_lane 0
+      (f32x4.add
+        (v128.const i32x4 1 2 3 4)
+        (v128.const i32x4 5 6 7 8)))))
+"#;
+
+#[test]
+fn vector_floats_rejected() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let bytecode = wasmer::wat2wasm(VECTOR_FLOATS.as_bytes())
+        .expect("the module should be valid");
+
+    world.set_float_policy(FloatPolicy::Reject);
+
+    match world.deploy(&bytecode) {
+        Err(Error::CompileError(_)) => {}
+        _ => panic!("expected the module to be rejected"),
+    }
+
+    world.set_float_policy(FloatPolicy::Allow);
+    world.deploy(&bytecode)?;
+
+    Ok(())
+}
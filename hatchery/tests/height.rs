@@ -20,3 +20,92 @@ pub fn height() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+pub fn timestamp() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("everest"))?;
+
+    let timestamp: Receipt<u64> = world.query(id, "get_timestamp", ())?;
+    assert_eq!(*timestamp, 0);
+
+    world.set_timestamp(1_660_000_000);
+    let timestamp: Receipt<u64> = world.query(id, "get_timestamp", ())?;
+    assert_eq!(*timestamp, 1_660_000_000);
+
+    Ok(())
+}
+
+#[test]
+pub fn receipt_context() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("everest"))?;
+
+    world.set_height(7);
+    world.set_timestamp(1_660_000_000);
+
+    let receipt: Receipt<u64> = world.transact(id, "get_height", ())?;
+    assert_eq!(receipt.height(), 7);
+    assert_eq!(receipt.timestamp(), 1_660_000_000);
+
+    world.set_height(8);
+
+    let receipt: Receipt<u64> = world.query(id, "get_timestamp", ())?;
+    assert_eq!(receipt.height(), 8);
+    assert_eq!(receipt.timestamp(), 1_660_000_000);
+
+    Ok(())
+}
+
+#[test]
+pub fn code_hash() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let bytecode = module_bytecode!("everest");
+    let id = world.deploy(bytecode)?;
+
+    let hash: Receipt<[u8; 32]> = world.query(id, "get_code_hash", ())?;
+    assert_eq!(*hash, *blake3::hash(bytecode).as_bytes());
+
+    Ok(())
+}
+
+#[test]
+pub fn tx_meta() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("everest"))?;
+
+    let meta = b"paid by alice".to_vec();
+    let received: Receipt<Vec<u8>> =
+        world.transact_with_meta(id, "get_tx_meta", (), meta.clone())?;
+    assert_eq!(*received, meta);
+
+    let received: Receipt<Vec<u8>> = world.transact(id, "get_tx_meta", ())?;
+    assert!(received.is_empty());
+
+    Ok(())
+}
+
+#[test]
+pub fn commit_id() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("everest"))?;
+
+    let commit_id: Receipt<[u8; 32]> = world.query(id, "get_commit_id", ())?;
+    assert_eq!(*commit_id, [0; 32]);
+
+    let first = world.persist()?;
+    let commit_id: Receipt<[u8; 32]> = world.query(id, "get_commit_id", ())?;
+    assert_eq!(*commit_id, *first.as_bytes());
+
+    world.set_height(1);
+    let _: Receipt<u64> = world.transact(id, "get_height", ())?;
+    let commit_id: Receipt<[u8; 32]> = world.query(id, "get_commit_id", ())?;
+    assert_eq!(*commit_id, *first.as_bytes());
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::sync::{Arc, Mutex};
+
+use hatchery::{module_bytecode, Error, Receipt, World};
+
+#[test]
+pub fn pre_deploy_veto() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let max_size = module_bytecode!("counter").len();
+    world.on_pre_deploy(move |_, bytecode| match bytecode.len() > max_size {
+        true => Err(String::from("module too large")),
+        false => Ok(()),
+    });
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let _: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+
+    let large = [module_bytecode!("counter"), &[0u8][..]].concat();
+    match world.deploy(&large) {
+        Err(Error::DeployRejected(_, reason)) => {
+            assert_eq!(reason, "module too large")
+        }
+        _ => panic!("expected the deploy to be rejected"),
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn post_deploy() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let deployed = Arc::new(Mutex::new(vec![]));
+    let hook_deployed = deployed.clone();
+
+    world.on_post_deploy(move |id, _| {
+        hook_deployed.lock().unwrap().push(id);
+        Ok(())
+    });
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let box_id = world.deploy(module_bytecode!("box"))?;
+
+    assert_eq!(*deployed.lock().unwrap(), vec![counter_id, box_id]);
+
+    world.on_post_deploy(|_, _| Err(String::from("no more modules")));
+
+    let result = world.deploy(module_bytecode!("vector"));
+    assert!(matches!(result, Err(Error::DeployRejected(_, _))));
+
+    Ok(())
+}
@@ -4,6 +4,10 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use dallo::{ModuleId, RawQuery, RawResult, RawTransaction};
 use hatchery::{module_bytecode, Error, Receipt, World};
 
 fn hash(buf: &mut [u8], len: u32) -> u32 {
@@ -37,3 +41,122 @@ pub fn host_hash() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+pub fn host_call_stack() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("host"))?;
+
+    let stacks = Rc::new(RefCell::new(Vec::new()));
+    let handle = world.clone();
+    let recorded = stacks.clone();
+    world.register_native_query("hash", move |buf, len| {
+        recorded.borrow_mut().push(handle.current_call_stack());
+        hash(buf, len)
+    });
+
+    let _: Receipt<[u8; 32]> = world.query(id, "hash", 42)?;
+
+    assert_eq!(*stacks.borrow(), vec![vec![id]]);
+
+    Ok(())
+}
+
+#[test]
+pub fn system_module() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    let system_id = ModuleId::reserved(0);
+    world.register_system_module(system_id, |name, buf, len| match name {
+        "hash" => Some(hash(buf, len)),
+        _ => None,
+    })?;
+
+    let rq = RawQuery::new("hash", 42);
+    let res: Receipt<RawResult> =
+        world.query(center_id, "delegate_query", (system_id, rq))?;
+    let h: [u8; 32] = res.cast();
+    assert_eq!(hash_num(42), h);
+
+    let rq = RawQuery::new("unknown", 42);
+    let err = world
+        .query::<_, RawResult>(center_id, "delegate_query", (system_id, rq))
+        .expect_err("querying an unknown method should fail");
+    assert!(
+        matches!(err, Error::MethodNotFound { module, .. } if module == system_id)
+    );
+
+    let rq = RawQuery::new("hash", 42);
+    let unregistered_id = ModuleId::reserved(1);
+    let err = world
+        .query::<_, RawResult>(
+            center_id,
+            "delegate_query",
+            (unregistered_id, rq),
+        )
+        .expect_err("querying an unregistered module should fail");
+    assert!(matches!(err, Error::ModuleNotFound(id) if id == unregistered_id));
+
+    // system modules are stateless, so transacting with them is querying
+    let rt = RawTransaction::new("hash", 42);
+    let res: Receipt<RawResult> =
+        world.transact(center_id, "delegate_transaction", (system_id, rt))?;
+    let h: [u8; 32] = res.cast();
+    assert_eq!(hash_num(42), h);
+
+    let rt = RawTransaction::new("hash", 42);
+    let missing_id = ModuleId::from([0x42; 32]);
+    let err = world
+        .transact::<_, RawResult>(
+            center_id,
+            "delegate_transaction",
+            (missing_id, rt),
+        )
+        .expect_err("transacting with a missing module should fail");
+    assert!(matches!(err, Error::ModuleNotFound(id) if id == missing_id));
+
+    let err = world
+        .register_system_module(center_id, |_, _, _| None)
+        .expect_err("deployed module ids are not reserved");
+    assert!(matches!(err, Error::UnreservedModuleId(id) if id == center_id));
+
+    Ok(())
+}
+
+#[test]
+pub fn native_query_memoization() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("host"))?;
+
+    // answers differently every time it's called
+    let calls = Rc::new(RefCell::new(0));
+    let counted = calls.clone();
+    world.register_native_query("hash", move |buf, len| {
+        *counted.borrow_mut() += 1;
+        buf[0] = buf[0].wrapping_add(*counted.borrow());
+        hash(buf, len)
+    });
+
+    let (a, b) =
+        *world.query::<_, ([u8; 32], [u8; 32])>(id, "hash_twice", 42)?;
+    assert_ne!(a, b, "answers should differ without memoization");
+    assert_eq!(*calls.borrow(), 2);
+
+    world.set_native_query_memoization(true);
+
+    let (a, b) =
+        *world.query::<_, ([u8; 32], [u8; 32])>(id, "hash_twice", 42)?;
+    assert_eq!(a, b, "repeated answers should be memoized");
+    assert_eq!(*calls.borrow(), 3);
+
+    let (c, _) =
+        *world.query::<_, ([u8; 32], [u8; 32])>(id, "hash_twice", 42)?;
+    assert_ne!(a, c, "memoization should not outlive the query");
+    assert_eq!(*calls.borrow(), 4);
+
+    Ok(())
+}
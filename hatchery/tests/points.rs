@@ -4,7 +4,7 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use hatchery::{module_bytecode, Error, Receipt, World};
+use hatchery::{module_bytecode, Error, Receipt, Sponsor, SponsorMode, World};
 
 #[test]
 pub fn points_get_used() -> Result<(), Error> {
@@ -69,3 +69,201 @@ pub fn limit_and_spent() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+pub fn instructions_counted() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    let receipt_counter: Receipt<i64> =
+        world.query(counter_id, "read_value", ())?;
+    let receipt_center: Receipt<i64> =
+        world.query(center_id, "query_counter", counter_id)?;
+
+    assert!(receipt_counter.instructions() > 0);
+    assert!(receipt_counter.instructions() <= receipt_counter.spent());
+
+    // instructions executed by the callee are included
+    assert!(receipt_center.instructions() > receipt_counter.instructions());
+
+    Ok(())
+}
+
+#[test]
+pub fn tx_limit_and_spent() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    const LIMIT: u64 = 10000;
+
+    world.set_point_limit(LIMIT);
+    let spender_id = world.deploy(module_bytecode!("spender"))?;
+
+    let receipt: Receipt<(u64, u64, u64, u64)> =
+        world.query(spender_id, "get_tx_limit_and_spent", ())?;
+
+    let (tx_limit, tx_spent, frame_limit, tx_spent_before) = *receipt;
+
+    assert_eq!(tx_limit, LIMIT, "the callee sees the limit of the tx");
+    assert!(frame_limit < LIMIT, "the callee frame gets a share");
+    assert!(
+        tx_spent > tx_spent_before,
+        "points spent by the caller are included"
+    );
+    assert!(tx_spent < receipt.spent());
+
+    Ok(())
+}
+
+#[test]
+pub fn gas_multiplier() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+
+    let receipt: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    let spent = receipt.spent();
+    let instructions = receipt.instructions();
+
+    world.set_gas_multiplier(counter_id, 200)?;
+    let receipt: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(receipt.spent(), 2 * spent);
+    assert_eq!(receipt.instructions(), instructions);
+
+    world.set_gas_multiplier(counter_id, 50)?;
+    let receipt: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(receipt.spent(), spent - spent / 2);
+    assert_eq!(receipt.instructions(), instructions);
+
+    // limits that don't divide evenly lose no points to rounding
+    world.set_gas_multiplier(counter_id, 300)?;
+    world.set_point_limit(1_000_001);
+    let receipt: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(receipt.spent(), 3 * spent);
+
+    Ok(())
+}
+
+#[test]
+pub fn gas_multiplier_survives_reopen() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    let storage_path = world.storage_path().to_path_buf();
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+
+    let receipt: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    let spent = receipt.spent();
+
+    world.set_gas_multiplier(counter_id, 200)?;
+    world.persist()?;
+    drop(world);
+
+    let mut world = World::new(storage_path)?;
+    world.deploy(module_bytecode!("counter"))?;
+
+    let receipt: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(receipt.spent(), 2 * spent);
+
+    Ok(())
+}
+
+#[test]
+pub fn default_point_limit() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+
+    world.set_default_point_limit(5000);
+    let receipt: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(receipt.limit(), 5000);
+
+    world.set_point_limit(6000);
+    assert_eq!(world.point_limit(), 6000);
+    let receipt: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    assert_eq!(receipt.limit(), 6000);
+
+    world.clear_point_limit();
+    assert_eq!(world.point_limit(), 5000);
+    let receipt: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(receipt.limit(), 5000);
+
+    Ok(())
+}
+
+#[test]
+pub fn query_point_limit() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+
+    world.set_point_limit(6000);
+    world.set_query_point_limit(0);
+    assert_eq!(world.query_point_limit(), 0);
+
+    let receipt: Receipt<()> = world.transact(counter_id, "increment", ())?;
+    assert_eq!(receipt.limit(), 6000);
+
+    let err = world
+        .query::<(), i64>(counter_id, "read_value", ())
+        .expect_err("should error with no points");
+    assert!(matches!(err, Error::OutOfPoints(mid) if mid == counter_id));
+
+    world.clear_query_point_limit();
+    let receipt: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(receipt.limit(), 6000);
+
+    Ok(())
+}
+
+#[test]
+pub fn sponsored_transactions() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    let mut sponsor = Sponsor::new(*b"sponsor", 1_000_000);
+    let receipt: Receipt<()> =
+        world.transact_sponsored(id, "increment", (), &mut sponsor)?;
+
+    assert_eq!(receipt.sponsor(), Some(&b"sponsor"[..]));
+    assert_eq!(receipt.sponsored(), receipt.spent());
+    assert_eq!(receipt.charged(), 0);
+    assert_eq!(sponsor.budget(), 1_000_000 - receipt.spent());
+
+    // the sponsor pays first, and the transaction the rest
+    let mut sponsor =
+        Sponsor::new(*b"sponsor", 1).with_mode(SponsorMode::InAddition);
+    let receipt: Receipt<()> =
+        world.transact_sponsored(id, "increment", (), &mut sponsor)?;
+
+    assert_eq!(receipt.sponsored(), 1);
+    assert_eq!(receipt.charged(), receipt.spent() - 1);
+    assert_eq!(sponsor.budget(), 0);
+
+    // a sponsor without budget can't pay for a transaction by itself
+    let mut sponsor = Sponsor::new(*b"sponsor", 0);
+    let err = world
+        .transact_sponsored::<_, ()>(id, "increment", (), &mut sponsor)
+        .expect_err("the sponsor should be out of budget");
+    assert!(matches!(err, Error::OutOfPoints(mid) if mid == id));
+
+    // a failed transaction still charges the sponsor for what it spent
+    let mut sponsor = Sponsor::new(*b"sponsor", 1_000_000);
+    let err = world
+        .transact_sponsored::<_, ()>(
+            id,
+            "increment_and_revert",
+            (),
+            &mut sponsor,
+        )
+        .expect_err("the transaction should revert");
+    assert!(matches!(err, Error::Revert(_)));
+    assert!(sponsor.budget() < 1_000_000);
+
+    let receipt: Receipt<()> = world.transact(id, "increment", ())?;
+    assert_eq!(receipt.sponsor(), None);
+    assert_eq!(receipt.charged(), receipt.spent());
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use hatchery::{module_bytecode, Error, Receipt, SnapshotPolicy, World};
+
+#[test]
+pub fn pure_writes_are_rejected() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("puritan"))?;
+
+    let err = world
+        .query::<_, i64>(id, "sneaky_write", 42i64)
+        .expect_err("pure method should not be able to write state");
+    assert!(matches!(err, Error::PureViolation(mid) if mid == id));
+
+    Ok(())
+}
+
+#[test]
+pub fn pure_allocations_are_freed() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("puritan"))?;
+    world.set_snapshot_policy(id, SnapshotPolicy::DataAndHeap)?;
+
+    let before = world.persist()?;
+
+    let sum: Receipt<i64> = world.query(id, "sum_to", 100i64)?;
+    assert_eq!(*sum, 5050);
+
+    let after = world.persist()?;
+    assert_eq!(before, after, "pure query should leave the state as is");
+
+    Ok(())
+}
+
+#[test]
+pub fn pure_cannot_transact() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("puritan"))?;
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+
+    let err = world
+        .transact::<_, ()>(id, "sneaky_transact", counter_id)
+        .expect_err("pure method should not be able to transact");
+    assert!(matches!(err, Error::PureViolation(mid) if mid == id));
+
+    let value: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(*value, 0xfc);
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use hatchery::{module_bytecode, Error, Receipt, World};
+
+#[test]
+pub fn random_is_replayable() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("dice"))?;
+
+    let first: Receipt<([u8; 32], [u8; 32])> =
+        world.query(id, "roll_twice", ())?;
+    let second: Receipt<([u8; 32], [u8; 32])> =
+        world.query(id, "roll_twice", ())?;
+
+    assert_ne!(first.0, first.1, "each draw should be different");
+    assert_eq!(*first, *second, "the same call should draw the same bytes");
+
+    let bytes: Receipt<Vec<u8>> = world.query(id, "random_bytes", 48u32)?;
+    assert_eq!(bytes.len(), 48);
+
+    Ok(())
+}
+
+#[test]
+pub fn random_depends_on_context() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("dice"))?;
+
+    let first: Receipt<([u8; 32], [u8; 32])> =
+        world.query(id, "roll_twice", ())?;
+    world.set_height(1);
+    let second: Receipt<([u8; 32], [u8; 32])> =
+        world.query(id, "roll_twice", ())?;
+    assert_ne!(*first, *second, "another height should draw other bytes");
+
+    let alice: Receipt<([u8; 32], [u8; 32])> =
+        world.transact_with_meta(id, "roll_twice", (), b"alice".to_vec())?;
+    let bob: Receipt<([u8; 32], [u8; 32])> =
+        world.transact_with_meta(id, "roll_twice", (), b"bob".to_vec())?;
+    assert_ne!(*alice, *bob, "another sender should draw other bytes");
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dallo::RawTransaction;
+use hatchery::{module_bytecode, Error, Receipt, World};
+
+#[test]
+fn scheduled_at_height() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    world.schedule(10, id, RawTransaction::new("increment", ()));
+
+    assert!(world.run_due(9).is_empty());
+
+    let results = world.run_due(10);
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_ok());
+
+    assert!(world.run_due(11).is_empty());
+
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfd);
+
+    Ok(())
+}
+
+#[test]
+fn schedule_persists() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    world.schedule(1, id, RawTransaction::new("increment", ()));
+    world.schedule(2, id, RawTransaction::new("increment", ()));
+    world.persist()?;
+
+    assert_eq!(world.run_due(2).len(), 2);
+
+    world.restore()?;
+
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfc);
+
+    assert_eq!(world.run_due(1).len(), 1);
+    assert_eq!(world.run_due(2).len(), 1);
+
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfe);
+
+    Ok(())
+}
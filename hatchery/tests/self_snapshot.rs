@@ -24,9 +24,14 @@ fn self_snapshot() -> Result<(), Error> {
 
     assert_eq!(*world.query::<_, i32>(id, "crossover", ())?, 10);
 
-    let result = world.transact::<_, i32>(id, "update_and_panic", 11);
-
-    assert!(result.is_err());
+    match world.transact::<_, i32>(id, "update_and_panic", 11) {
+        Err(Error::Panic { module, message }) => {
+            assert_eq!(module, id);
+            assert!(message.contains("OH NOES"));
+            assert!(message.contains("src/lib.rs"));
+        }
+        _ => panic!("expected the module to panic"),
+    }
 
     // panic reverted the change!
 
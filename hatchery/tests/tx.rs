@@ -0,0 +1,97 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "tx")]
+
+use dallo::{ModuleId, RawTransaction};
+use hatchery::{
+    module_bytecode, Error, Receipt, SignedTransaction, TxPayload, World,
+};
+
+// A stand-in signature scheme, where signing is hashing the message keyed by
+// the signer.
+fn sign(signer: &[u8], msg: &[u8]) -> Vec<u8> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(signer);
+    hasher.update(msg);
+    hasher.finalize().as_bytes().to_vec()
+}
+
+fn verify(signer: &[u8], msg: &[u8], signature: &[u8]) -> bool {
+    sign(signer, msg) == signature
+}
+
+fn signed(module_id: ModuleId, nonce: u64) -> SignedTransaction {
+    let payload = TxPayload {
+        module_id,
+        raw: RawTransaction::new("increment", ()),
+        nonce,
+        limit: 4096,
+    };
+
+    let signer = b"alice".to_vec();
+    let signature = sign(&signer, &payload.to_signable_bytes());
+
+    SignedTransaction {
+        payload,
+        signer,
+        signature,
+    }
+}
+
+#[test]
+fn execute_signed() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+    world.set_signature_verifier(verify);
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    world.execute_signed(&signed(id, 0))?;
+    assert_eq!(world.nonce(b"alice"), 1);
+
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfd);
+
+    // replaying the same transaction fails
+    match world.execute_signed(&signed(id, 0)) {
+        Err(Error::InvalidNonce(1)) => {}
+        _ => panic!("expected an invalid nonce"),
+    }
+
+    let mut tx = signed(id, 1);
+    tx.payload.limit += 1;
+
+    match world.execute_signed(&tx) {
+        Err(Error::InvalidSignature) => {}
+        _ => panic!("expected an invalid signature"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn transact_with_nonce() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    let _: Receipt<()> =
+        world.transact_with_nonce(b"bob", 0, id, "increment", ())?;
+    let _: Receipt<()> =
+        world.transact_with_nonce(b"bob", 1, id, "increment", ())?;
+
+    // replaying a nonce is rejected
+    match world.transact_with_nonce::<_, ()>(b"bob", 1, id, "increment", ()) {
+        Err(Error::InvalidNonce(expected)) => assert_eq!(expected, 2),
+        _ => panic!("expected the nonce to be rejected"),
+    }
+    assert_eq!(world.nonce(b"alice"), 0);
+
+    let value: Receipt<i64> = world.query(id, "read_value", ())?;
+    assert_eq!(*value, 0xfe);
+
+    Ok(())
+}
@@ -4,6 +4,7 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use dallo::RawTransaction;
 use hatchery::{module_bytecode, Error, Receipt, World};
 
 #[test]
@@ -30,3 +31,333 @@ pub fn vector_push_pop() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+pub fn vector_push_chunks() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+
+    // serializing this many nested vectors needs more than the fixed scratch
+    // space
+    let chunks: Vec<Vec<i16>> = (0..64).map(|i| vec![i; 4]).collect();
+
+    let returned: Receipt<Vec<Vec<i16>>> =
+        world.transact(id, "push_chunks", chunks.clone())?;
+    assert_eq!(*returned, chunks);
+
+    let popped: Receipt<Option<i16>> = world.transact(id, "pop", ())?;
+    assert_eq!(*popped, Some(63));
+
+    Ok(())
+}
+
+#[test]
+pub fn argument_too_large() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+
+    let chunks: Vec<Vec<i16>> = vec![vec![0; dallo::ARGBUF_LEN]];
+
+    match world.transact::<_, Vec<Vec<i16>>>(id, "push_chunks", chunks) {
+        Err(Error::ArgumentTooLarge {
+            required,
+            available,
+        }) => {
+            assert!(required > available);
+            assert_eq!(available, dallo::ARGBUF_LEN);
+        }
+        _ => panic!("expected the argument to be too large"),
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn vector_heap_report() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+
+    for i in 0..16 {
+        world.transact::<_, ()>(id, "push", i as i16)?;
+    }
+
+    let report = world.heap_report();
+
+    assert!(!report.growth().is_empty());
+    for growth in report.growth() {
+        assert_eq!(growth.module_id, id);
+        assert_eq!(growth.method, "push");
+    }
+
+    let grown: usize = report.growth().iter().map(|g| g.bytes).sum();
+    assert!(report.high_water_marks()[&id] >= grown);
+
+    Ok(())
+}
+
+#[test]
+pub fn vector_batch_heap_report() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+    world.transact::<_, ()>(id, "push", 0i16)?;
+
+    let before = world.heap_report();
+
+    // the growth of a batch that fails is rolled back with it
+    let mut batch: Vec<_> = (0..16)
+        .map(|i| (id, RawTransaction::new("push", i as i16)))
+        .collect();
+    batch.push((id, RawTransaction::new("no_such_method", ())));
+    world
+        .transact_batch(&batch)
+        .expect_err("the batch should fail");
+
+    assert_eq!(world.heap_report(), before);
+
+    let popped: Receipt<Option<i16>> = world.transact(id, "pop", ())?;
+    assert_eq!(*popped, Some(0));
+    let popped: Receipt<Option<i16>> = world.transact(id, "pop", ())?;
+    assert_eq!(*popped, None);
+
+    Ok(())
+}
+
+#[test]
+pub fn vector_heap_stats() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+
+    let before: Receipt<(u64, u64)> = world.query(id, "heap_stats", ())?;
+
+    for i in 0..16 {
+        world.transact::<_, ()>(id, "push", i as i16)?;
+    }
+
+    let after: Receipt<(u64, u64)> = world.query(id, "heap_stats", ())?;
+
+    let (used_before, limit) = *before;
+    let (used_after, _) = *after;
+
+    assert!(used_after > used_before);
+    assert!(used_after <= limit);
+
+    Ok(())
+}
+
+#[test]
+pub fn vector_memory_pressure() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+
+    let pressure: Receipt<bool> = world.query(id, "memory_pressure", ())?;
+    assert!(!*pressure);
+
+    world.set_soft_memory_limit(100);
+    let pressure: Receipt<bool> = world.query(id, "memory_pressure", ())?;
+    assert!(!*pressure);
+
+    world.set_soft_memory_limit(1);
+    world.transact::<_, ()>(id, "reserve", 1u32 << 16)?;
+    let pressure: Receipt<bool> = world.query(id, "memory_pressure", ())?;
+    assert!(*pressure);
+
+    world.clear_soft_memory_limit();
+    let pressure: Receipt<bool> = world.query(id, "memory_pressure", ())?;
+    assert!(!*pressure);
+
+    Ok(())
+}
+
+#[test]
+pub fn vector_arena_sum() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+
+    for i in 0..16 {
+        world.transact::<_, ()>(id, "push", i as i16)?;
+    }
+
+    let before: Receipt<(u64, u64)> = world.query(id, "heap_stats", ())?;
+
+    for _ in 0..4 {
+        let sum: Receipt<i64> = world.query(id, "arena_sum", 100u32)?;
+        assert_eq!(*sum, 12000);
+    }
+
+    // the arena is separate from the heap
+    let after: Receipt<(u64, u64)> = world.query(id, "heap_stats", ())?;
+    assert_eq!(before.0, after.0);
+
+    Ok(())
+}
+
+#[test]
+pub fn vector_memory_pages() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+
+    let pages: Receipt<(u32, u32)> = world.query(id, "memory_pages", ())?;
+    let (pages, limit) = *pages;
+
+    assert!(pages > 0);
+    assert!(pages <= limit);
+
+    Ok(())
+}
+
+#[test]
+pub fn vector_return_too_large() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+
+    for i in 0..16 {
+        world.transact::<_, ()>(id, "push", i as i16)?;
+    }
+
+    let replicated: Receipt<Vec<i16>> = world.query(id, "replicate", 2)?;
+    assert_eq!(replicated.len(), 32);
+    assert!(replicated.ret_len() >= 32 * 2);
+    assert!(replicated.ret_len() <= dallo::ARGBUF_LEN);
+
+    let times = (dallo::ARGBUF_LEN / 32) as u32;
+    match world.query::<_, Vec<i16>>(id, "replicate", times) {
+        Err(Error::ReturnTooLarge { module, method }) => {
+            assert_eq!(module, id);
+            assert_eq!(method, "replicate");
+        }
+        _ => panic!("expected the return to be too large"),
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn vector_abi_checked() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+
+    world.transact::<i16, ()>(id, "push", 7)?;
+    world.transact::<_, Vec<Vec<i16>>>(
+        id,
+        "push_chunks",
+        vec![vec![1i16, 2]],
+    )?;
+
+    match world.transact::<u64, ()>(id, "push", 7) {
+        Err(Error::ArgumentMismatch {
+            module,
+            method,
+            expected,
+            found,
+        }) => {
+            assert_eq!(module, id);
+            assert_eq!(method, "push");
+            assert_eq!(expected, format!("{:016x}", dallo::type_hash::<i16>()));
+            assert_eq!(found, format!("{:016x}", dallo::type_hash::<u64>()));
+        }
+        _ => panic!("expected an argument mismatch"),
+    }
+
+    match world.query::<_, ()>(id, "shove", ()) {
+        Err(Error::MethodNotFound { module, method }) => {
+            assert_eq!(module, id);
+            assert_eq!(method, "shove");
+        }
+        _ => panic!("expected the method not to be found"),
+    }
+
+    let popped: Receipt<Option<i16>> = world.transact(id, "pop", ())?;
+    assert_eq!(*popped, Some(2));
+
+    // types are compared by structure, so any 32 bit integer will do
+    world.query::<i32, Vec<i16>>(id, "replicate", 1)?;
+
+    Ok(())
+}
+
+#[test]
+pub fn vector_revert_rendered() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+
+    let err = match world.transact::<_, i16>(id, "pop_or_revert", ()) {
+        Err(Error::Revert(err)) => err,
+        _ => panic!("expected the call to revert"),
+    };
+    assert_eq!(err.module(), id);
+    assert_eq!(err.method(), "pop_or_revert");
+    assert_eq!(err.type_name(), None);
+    assert_eq!(err.decode::<String>()?, "the vector is empty");
+
+    world.register_error_type::<String>();
+
+    let err = match world.transact::<_, i16>(id, "pop_or_revert", ()) {
+        Err(Error::Revert(err)) => err,
+        _ => panic!("expected the call to revert"),
+    };
+    assert_eq!(err.type_name(), Some(std::any::type_name::<String>()));
+    assert_eq!(err.rendering(), Some("\"the vector is empty\""));
+
+    Ok(())
+}
+
+#[test]
+pub fn vector_write_limit() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+
+    world.transact::<_, ()>(id, "push", 1i16)?;
+    let unlimited: Receipt<()> = world.transact(id, "push", 2i16)?;
+
+    world.set_write_limit(usize::MAX, 100);
+    let charged: Receipt<()> = world.transact(id, "push", 3i16)?;
+    assert!(charged.spent() >= unlimited.spent() + 100);
+
+    world.set_write_limit(0, 100);
+    match world.transact::<_, ()>(id, "push", 4i16) {
+        Err(Error::WriteLimit(module_id)) => assert_eq!(module_id, id),
+        _ => panic!("expected the write limit to be exceeded"),
+    }
+
+    world.clear_write_limit();
+    world.transact::<_, ()>(id, "push", 5i16)?;
+
+    let popped: Receipt<Option<i16>> = world.transact(id, "pop", ())?;
+    assert_eq!(*popped, Some(5));
+    // the push exceeding the limit was rolled back
+    let popped: Receipt<Option<i16>> = world.transact(id, "pop", ())?;
+    assert_eq!(*popped, Some(3));
+
+    Ok(())
+}
+
+#[test]
+pub fn vector_out_of_memory() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("vector"))?;
+
+    const HUGE: u32 = 1 << 29;
+
+    let reserved: Receipt<bool> = world.transact(id, "try_reserve", HUGE)?;
+    assert!(!*reserved);
+
+    match world.transact::<_, ()>(id, "reserve", HUGE) {
+        Err(Error::GuestOutOfMemory(module_id)) => assert_eq!(module_id, id),
+        _ => panic!("expected the module to run out of memory"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dallo::{ModuleId, RawTransaction};
+use hatchery::{module_bytecode, wire, CommitId, Error, ErrorCode, World};
+
+/// A commit info encoded by the first version of the encoding, which every
+/// later release must keep decoding the same.
+fn commit_info_v1() -> Vec<u8> {
+    let mut bytes = vec![1, 2];
+    bytes.extend_from_slice(&[7; 32]);
+    bytes.extend_from_slice(&42u64.to_le_bytes());
+    bytes.extend_from_slice(&1_700_000_000u64.to_le_bytes());
+    bytes
+}
+
+/// An event encoded by the first version of the encoding, emitted by
+/// `[3; 32]` called by `[4; 32]`.
+fn event_v1() -> Vec<u8> {
+    let mut archived = vec![];
+
+    // the callers and the data, followed by padding for the event
+    archived.extend_from_slice(&[4; 32]);
+    archived.extend_from_slice(&[5, 6, 0, 0]);
+
+    archived.extend_from_slice(&[3; 32]);
+    push_vec(&mut archived, 0, 1);
+    archived.extend_from_slice(b"ping\0\0\0\x04");
+    push_vec(&mut archived, 32, 2);
+
+    let mut bytes = vec![1, 1];
+    bytes.extend_from_slice(&archived);
+    bytes
+}
+
+/// A receipt encoded by the first version of the encoding, with a debug
+/// message and a failed deferred call.
+fn receipt_v1() -> Vec<u8> {
+    let mut archived = vec![];
+
+    // the return, followed by padding for the events
+    archived.extend_from_slice(&[1, 2, 3, 0]);
+    archived.extend_from_slice(b"hi\0\0\0\0\0\x02");
+    // padding for the deferred calls
+    archived.extend_from_slice(&[0; 4]);
+
+    // the failure
+    archived.extend_from_slice(&[9; 32]);
+    archived.extend_from_slice(b"tick\0\0\0\x04");
+    archived.extend_from_slice(b"boom\0\0\0\x04");
+    archived.extend_from_slice(&8u16.to_le_bytes());
+    archived.extend_from_slice(&[0; 6]);
+    archived.extend_from_slice(&7u64.to_le_bytes());
+
+    // the call, without a sponsor
+    push_vec(&mut archived, 0, 3);
+    push_vec(&mut archived, 4, 0);
+    push_vec(&mut archived, 4, 1);
+    push_vec(&mut archived, 12, 0);
+    archived.extend_from_slice(&[0; 16]);
+    for n in [100u64, 1000, 50, 3, 42, 1_700_000_000, 0] {
+        archived.extend_from_slice(&n.to_le_bytes());
+    }
+
+    push_vec(&mut archived, 16, 0);
+    push_vec(&mut archived, 16, 1);
+
+    let mut bytes = vec![1, 0];
+    bytes.extend_from_slice(&archived);
+    bytes
+}
+
+/// Push an archived vector of `len` elements starting at `pos`, pointing to
+/// them relative to itself.
+fn push_vec(archived: &mut Vec<u8>, pos: usize, len: u32) {
+    let offset = pos as i32 - archived.len() as i32;
+    archived.extend_from_slice(&offset.to_le_bytes());
+    archived.extend_from_slice(&len.to_le_bytes());
+}
+
+#[test]
+fn receipt_round_trip() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let eventer_id = world.deploy(module_bytecode!("eventer"))?;
+
+    let receipt = world
+        .transact_raw(eventer_id, RawTransaction::new("emit_events", 3u32))?;
+    assert_eq!(receipt.events().len(), 3);
+
+    let bytes = wire::encode_receipt(&receipt);
+    assert_eq!(bytes[0], wire::WIRE_VERSION);
+    assert_eq!(wire::decode_receipt(&bytes)?, receipt);
+
+    for event in receipt.events() {
+        let bytes = wire::encode_event(event);
+        assert_eq!(&wire::decode_event(&bytes)?, event);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn commit_info_compatibility() -> Result<(), Error> {
+    let bytes = commit_info_v1();
+
+    let info = wire::decode_commit_info(&bytes)?;
+    assert_eq!(info.id(), CommitId::from([7; 32]));
+    assert_eq!(info.height(), 42);
+    assert_eq!(info.timestamp(), 1_700_000_000);
+
+    assert_eq!(wire::encode_commit_info(&info), bytes);
+
+    Ok(())
+}
+
+#[test]
+fn event_compatibility() -> Result<(), Error> {
+    let bytes = event_v1();
+
+    let event = wire::decode_event(&bytes)?;
+    assert_eq!(*event.module_id(), ModuleId::from([3; 32]));
+    assert_eq!(event.callers(), [ModuleId::from([4; 32])]);
+    assert_eq!(event.method(), "ping");
+    assert_eq!(event.data(), [5, 6]);
+
+    assert_eq!(wire::encode_event(&event), bytes);
+
+    Ok(())
+}
+
+#[test]
+fn receipt_compatibility() -> Result<(), Error> {
+    let bytes = receipt_v1();
+
+    let receipt = wire::decode_receipt(&bytes)?;
+    assert_eq!(receipt.ret().as_bytes(), [1, 2, 3]);
+    assert!(receipt.events().is_empty());
+    assert_eq!(receipt.debug(), [String::from("hi")]);
+    assert!(receipt.reverts().is_empty());
+    assert_eq!(receipt.sponsor(), None);
+    assert_eq!(receipt.spent(), 100);
+    assert_eq!(receipt.limit(), 1000);
+    assert_eq!(receipt.instructions(), 50);
+    assert_eq!(receipt.ret_len(), 3);
+    assert_eq!(receipt.height(), 42);
+    assert_eq!(receipt.timestamp(), 1_700_000_000);
+    assert!(receipt.deferred().is_empty());
+
+    assert_eq!(receipt.failures().len(), 1);
+    let failure = &receipt.failures()[0];
+    assert_eq!(failure.module(), ModuleId::from([9; 32]));
+    assert_eq!(failure.method(), "tick");
+    assert_eq!(failure.code(), ErrorCode::OutOfPoints);
+    assert_eq!(failure.message(), "boom");
+    assert_eq!(failure.spent(), 7);
+
+    assert_eq!(wire::encode_receipt(&receipt), bytes);
+
+    Ok(())
+}
+
+#[test]
+fn wire_rejects_unknown_values() {
+    let mut bytes = commit_info_v1();
+
+    assert!(matches!(
+        wire::decode_event(&bytes),
+        Err(Error::ValidationError)
+    ));
+    assert!(matches!(
+        wire::decode_receipt(&[]),
+        Err(Error::ValidationError)
+    ));
+
+    bytes[0] = wire::WIRE_VERSION + 1;
+    assert!(matches!(
+        wire::decode_commit_info(&bytes),
+        Err(Error::UnsupportedWireVersion(version)) if version == wire::WIRE_VERSION + 1
+    ));
+}
@@ -0,0 +1,77 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use hatchery::{module_bytecode, Error, Receipt, World, WITNESS_PAGE_SIZE};
+
+#[test]
+fn no_witness_by_default() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    let receipt: Receipt<()> = world.transact(id, "increment", ())?;
+    assert!(receipt.witness().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn witness_records_written_pages() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let counter_id = world.deploy(module_bytecode!("counter"))?;
+    let center_id = world.deploy(module_bytecode!("callcenter"))?;
+
+    world.set_witness(true);
+    world.persist()?;
+
+    let receipt: Receipt<()> =
+        world.transact(center_id, "increment_counter", counter_id)?;
+
+    let witness = receipt.witness().expect("witnesses are enabled");
+    let memories = witness.memories();
+
+    assert_eq!(memories.len(), 2);
+    assert!(!memories[&counter_id].written().is_empty());
+    assert!(!memories[&counter_id].pages().is_empty());
+
+    let counter = &memories[&counter_id];
+    assert!(
+        counter.pages().len() < counter.len() / WITNESS_PAGE_SIZE,
+        "only the pages accessed should be in the witness"
+    );
+    for page in counter.written() {
+        assert!(counter.pages().contains_key(page));
+    }
+
+    let receipt: Receipt<i64> = world.query(counter_id, "read_value", ())?;
+    assert_eq!(*receipt, 0xfd);
+
+    let witness = receipt.witness().expect("witnesses are enabled");
+    assert_eq!(witness.memories().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn witness_root_is_state_at_call_start() -> Result<(), Error> {
+    let mut world = World::ephemeral()?;
+
+    let id = world.deploy(module_bytecode!("counter"))?;
+
+    world.set_witness(true);
+    let persisted = world.persist()?;
+
+    world.transact::<_, ()>(id, "increment", ())?;
+    let root = world.root();
+    assert_ne!(root, persisted);
+
+    let receipt: Receipt<()> = world.transact(id, "increment", ())?;
+    let witness = receipt.witness().expect("witnesses are enabled");
+    assert_eq!(witness.root(), root.as_bytes());
+
+    Ok(())
+}
@@ -9,8 +9,8 @@
 #![no_main]
 
 use dallo::{
-    wrap_query, wrap_transaction, HostAlloc, ModuleId, RawQuery, RawResult,
-    RawTransaction, State,
+    wrap_query, wrap_transaction, CallError, HostAlloc, ModuleId, RawQuery,
+    RawResult, RawTransaction, State,
 };
 
 #[global_allocator]
@@ -24,11 +24,17 @@ static SELF_ID: ModuleId = ModuleId::uninitialized();
 
 static mut STATE: State<Callcenter> = State::new(Callcenter);
 
+const READ_VALUE: u32 = dallo::selector("read_value");
+
 impl Callcenter {
     pub fn query_counter(&self, counter_id: ModuleId) -> i64 {
         dallo::query(counter_id, "read_value", ())
     }
 
+    pub fn query_counter_selector(&self, counter_id: ModuleId) -> i64 {
+        dallo::query_selector(counter_id, READ_VALUE, ())
+    }
+
     pub fn increment_counter(self: &mut State<Self>, counter_id: ModuleId) {
         dallo::emit(counter_id);
         self.transact(counter_id, "increment", ())
@@ -39,7 +45,8 @@ impl Callcenter {
         module_id: ModuleId,
         raw: RawQuery,
     ) -> RawResult {
-        dallo::query_raw(module_id, raw)
+        dallo::proxy::forward_query(module_id, &raw)
+            .expect("the query should be well formed")
     }
 
     pub fn query_passthrough(&mut self, raw: RawQuery) -> RawQuery {
@@ -51,13 +58,64 @@ impl Callcenter {
         module_id: ModuleId,
         raw: RawTransaction,
     ) -> RawResult {
-        self.transact_raw(module_id, raw)
+        dallo::proxy::forward_transaction(module_id, &raw)
+            .expect("the transaction should be well formed")
+    }
+
+    pub fn defer_transaction(
+        &mut self,
+        module_id: ModuleId,
+        raw: RawTransaction,
+    ) {
+        dallo::defer(module_id, raw)
+    }
+
+    pub fn revert_with(&self, err: u32) {
+        dallo::revert(err)
+    }
+
+    pub fn try_revert(&self, err: u32) -> Result<(), u32> {
+        let self_id = dallo::self_id();
+        match dallo::try_query::<_, (), u32>(self_id, "revert_with", err) {
+            Ok(()) => Ok(()),
+            Err(CallError::Reverted(err)) => Err(err),
+            Err(CallError::Failed(code)) => panic!("failed with {}", code),
+        }
+    }
+
+    pub fn try_increment_and_revert(
+        self: &mut State<Self>,
+        counter_id: ModuleId,
+    ) -> Option<i64> {
+        match self.try_transact::<_, (), i64>(
+            counter_id,
+            "increment_and_revert",
+            (),
+        ) {
+            Err(CallError::Reverted(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn panic_with(&self, code: u32) {
+        panic!("panicked with {}", code)
+    }
+
+    pub fn try_missing(&self, module_id: ModuleId) -> u16 {
+        match dallo::try_query::<_, (), ()>(module_id, "missing", ()) {
+            Err(CallError::Failed(code)) => code,
+            _ => 0,
+        }
     }
 
     pub fn calling_self(&self, id: ModuleId) -> bool {
         dallo::self_id() == id
     }
 
+    pub fn return_entry_module(&self) -> ModuleId {
+        dallo::entry_module()
+    }
+
     pub fn call_self(&self) -> bool {
         let self_id = dallo::self_id();
         let caller = dallo::caller();
@@ -74,11 +132,45 @@ unsafe fn query_counter(arg_len: u32) -> u32 {
     wrap_query(arg_len, |counter_id| STATE.query_counter(counter_id))
 }
 
+#[no_mangle]
+unsafe fn query_counter_selector(arg_len: u32) -> u32 {
+    wrap_query(arg_len, |counter_id| {
+        STATE.query_counter_selector(counter_id)
+    })
+}
+
 #[no_mangle]
 unsafe fn increment_counter(arg_len: u32) -> u32 {
     wrap_transaction(arg_len, |counter_id| STATE.increment_counter(counter_id))
 }
 
+#[no_mangle]
+unsafe fn revert_with(arg_len: u32) -> u32 {
+    wrap_query(arg_len, |err| STATE.revert_with(err))
+}
+
+#[no_mangle]
+unsafe fn try_revert(arg_len: u32) -> u32 {
+    wrap_query(arg_len, |err| STATE.try_revert(err))
+}
+
+#[no_mangle]
+unsafe fn try_increment_and_revert(arg_len: u32) -> u32 {
+    wrap_transaction(arg_len, |counter_id| {
+        STATE.try_increment_and_revert(counter_id)
+    })
+}
+
+#[no_mangle]
+unsafe fn panic_with(arg_len: u32) -> u32 {
+    wrap_query(arg_len, |code| STATE.panic_with(code))
+}
+
+#[no_mangle]
+unsafe fn try_missing(arg_len: u32) -> u32 {
+    wrap_query(arg_len, |module_id| STATE.try_missing(module_id))
+}
+
 #[no_mangle]
 unsafe fn calling_self(arg_len: u32) -> u32 {
     wrap_query(arg_len, |self_id| STATE.calling_self(self_id))
@@ -89,6 +181,11 @@ unsafe fn call_self(arg_len: u32) -> u32 {
     wrap_query(arg_len, |_: ()| STATE.call_self())
 }
 
+#[no_mangle]
+unsafe fn return_entry_module(arg_len: u32) -> u32 {
+    wrap_query(arg_len, |_: ()| STATE.return_entry_module())
+}
+
 #[no_mangle]
 unsafe fn delegate_query(arg_len: u32) -> u32 {
     wrap_query(arg_len, |(mod_id, rq): (ModuleId, RawQuery)| {
@@ -107,3 +204,10 @@ unsafe fn delegate_transaction(arg_len: u32) -> u32 {
         STATE.delegate_transaction(mod_id, rt)
     })
 }
+
+#[no_mangle]
+unsafe fn defer_transaction(arg_len: u32) -> u32 {
+    wrap_transaction(arg_len, |(mod_id, rt): (ModuleId, RawTransaction)| {
+        STATE.defer_transaction(mod_id, rt)
+    })
+}
@@ -32,6 +32,11 @@ impl Counter {
         let value = self.value + 1;
         self.value = value;
     }
+
+    pub fn increment_and_revert(&mut self) {
+        self.increment();
+        dallo::revert(self.value)
+    }
 }
 
 #[no_mangle]
@@ -43,3 +48,8 @@ unsafe fn read_value(arg_len: u32) -> u32 {
 unsafe fn increment(arg_len: u32) -> u32 {
     dallo::wrap_transaction(arg_len, |_: ()| STATE.increment())
 }
+
+#[no_mangle]
+unsafe fn increment_and_revert(arg_len: u32) -> u32 {
+    dallo::wrap_transaction(arg_len, |_: ()| STATE.increment_and_revert())
+}
@@ -28,6 +28,10 @@ impl Debug {
         dallo::debug!("What a string! {}", string);
     }
 
+    pub fn info(&self, times: u32) {
+        dallo::info!("Said {} times", times);
+    }
+
     pub fn panic(&self) {
         panic!("It's never too late to panic");
     }
@@ -37,3 +41,8 @@ impl Debug {
 unsafe fn debug(arg_len: u32) -> u32 {
     dallo::wrap_query(arg_len, |s: alloc::string::String| STATE.debug(s))
 }
+
+#[no_mangle]
+unsafe fn info(arg_len: u32) -> u32 {
+    dallo::wrap_query(arg_len, |times| STATE.info(times))
+}
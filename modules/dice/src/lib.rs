@@ -0,0 +1,50 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use dallo::{HostAlloc, ModuleId, State};
+
+#[global_allocator]
+static ALLOCATOR: HostAlloc = HostAlloc;
+
+pub struct Dice;
+
+#[no_mangle]
+static SELF_ID: ModuleId = ModuleId::uninitialized();
+
+static mut STATE: State<Dice> = State::new(Dice);
+
+impl Dice {
+    pub fn roll_twice(&self) -> ([u8; 32], [u8; 32]) {
+        let mut first = [0u8; 32];
+        let mut second = [0u8; 32];
+
+        dallo::random(b"dice", &mut first);
+        dallo::random(b"dice", &mut second);
+
+        (first, second)
+    }
+
+    pub fn random_bytes(&self, n: u32) -> Vec<u8> {
+        dallo::random_bytes(n as usize)
+    }
+}
+
+#[no_mangle]
+unsafe fn roll_twice(arg_len: u32) -> u32 {
+    dallo::wrap_query(arg_len, |_: ()| STATE.roll_twice())
+}
+
+#[no_mangle]
+unsafe fn random_bytes(arg_len: u32) -> u32 {
+    dallo::wrap_query(arg_len, |n| STATE.random_bytes(n))
+}
@@ -7,6 +7,7 @@
 #![feature(arbitrary_self_types)]
 #![no_std]
 #![no_main]
+extern crate alloc;
 
 #[global_allocator]
 static ALLOCATOR: dallo::HostAlloc = dallo::HostAlloc;
@@ -14,6 +15,7 @@ static ALLOCATOR: dallo::HostAlloc = dallo::HostAlloc;
 #[derive(Default)]
 pub struct Height;
 
+use alloc::vec::Vec;
 use dallo::{ModuleId, State};
 
 #[no_mangle]
@@ -25,9 +27,45 @@ impl Height {
     pub fn get_height(&self) -> u64 {
         dallo::height()
     }
+
+    pub fn get_timestamp(&self) -> u64 {
+        dallo::timestamp()
+    }
+
+    pub fn get_tx_meta(&self) -> Vec<u8> {
+        dallo::tx_meta()
+    }
+
+    pub fn get_code_hash(&self) -> [u8; 32] {
+        dallo::code_hash_self()
+    }
+
+    pub fn get_commit_id(&self) -> [u8; 32] {
+        dallo::commit_id()
+    }
 }
 
 #[no_mangle]
 unsafe fn get_height(a: u32) -> u32 {
     dallo::wrap_query(a, |_: ()| STATE.get_height())
 }
+
+#[no_mangle]
+unsafe fn get_timestamp(a: u32) -> u32 {
+    dallo::wrap_query(a, |_: ()| STATE.get_timestamp())
+}
+
+#[no_mangle]
+unsafe fn get_tx_meta(a: u32) -> u32 {
+    dallo::wrap_transaction(a, |_: ()| STATE.get_tx_meta())
+}
+
+#[no_mangle]
+unsafe fn get_code_hash(a: u32) -> u32 {
+    dallo::wrap_query(a, |_: ()| STATE.get_code_hash())
+}
+
+#[no_mangle]
+unsafe fn get_commit_id(a: u32) -> u32 {
+    dallo::wrap_query(a, |_: ()| STATE.get_commit_id())
+}
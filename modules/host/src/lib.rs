@@ -24,9 +24,18 @@ impl Hoster {
     pub fn hash(&self, num: i32) -> [u8; 32] {
         dallo::native_query("hash", num)
     }
+
+    pub fn hash_twice(&self, num: i32) -> ([u8; 32], [u8; 32]) {
+        (self.hash(num), self.hash(num))
+    }
 }
 
 #[no_mangle]
 unsafe fn hash(arg_len: u32) -> u32 {
     dallo::wrap_query(arg_len, |num| STATE.hash(num))
 }
+
+#[no_mangle]
+unsafe fn hash_twice(arg_len: u32) -> u32 {
+    dallo::wrap_query(arg_len, |num| STATE.hash_twice(num))
+}
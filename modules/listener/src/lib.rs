@@ -0,0 +1,89 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![feature(arbitrary_self_types)]
+#![no_std]
+#![no_main]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use dallo::{ModuleId, State};
+
+#[global_allocator]
+static ALLOCATOR: dallo::HostAlloc = dallo::HostAlloc;
+
+#[no_mangle]
+static SELF_ID: ModuleId = ModuleId::uninitialized();
+
+pub struct Listener {
+    received: u32,
+    failing: bool,
+}
+
+static mut STATE: State<Listener> = State::new(Listener {
+    received: 0,
+    failing: false,
+});
+
+impl Listener {
+    pub fn subscribe(&mut self, module_id: ModuleId) {
+        dallo::subscribe(module_id);
+    }
+
+    pub fn unsubscribe(&mut self, module_id: ModuleId) {
+        dallo::unsubscribe(module_id);
+    }
+
+    pub fn subscribe_and_fail(&mut self, module_id: ModuleId) {
+        dallo::subscribe(module_id);
+        panic!("failing after subscribing");
+    }
+
+    pub fn set_failing(&mut self, failing: bool) {
+        self.failing = failing;
+    }
+
+    pub fn on_event(&mut self, _module_id: ModuleId, _data: Vec<u8>) {
+        self.received += 1;
+        if self.failing {
+            panic!("failing to handle an event");
+        }
+    }
+
+    pub fn received(&self) -> u32 {
+        self.received
+    }
+}
+
+#[no_mangle]
+unsafe fn subscribe(arg_len: u32) -> u32 {
+    dallo::wrap_transaction(arg_len, |id| STATE.subscribe(id))
+}
+
+#[no_mangle]
+unsafe fn unsubscribe(arg_len: u32) -> u32 {
+    dallo::wrap_transaction(arg_len, |id| STATE.unsubscribe(id))
+}
+
+#[no_mangle]
+unsafe fn subscribe_and_fail(arg_len: u32) -> u32 {
+    dallo::wrap_transaction(arg_len, |id| STATE.subscribe_and_fail(id))
+}
+
+#[no_mangle]
+unsafe fn set_failing(arg_len: u32) -> u32 {
+    dallo::wrap_transaction(arg_len, |failing| STATE.set_failing(failing))
+}
+
+#[no_mangle]
+unsafe fn on_event(arg_len: u32) -> u32 {
+    dallo::wrap_transaction(arg_len, |(id, data)| STATE.on_event(id, data))
+}
+
+#[no_mangle]
+unsafe fn received(arg_len: u32) -> u32 {
+    dallo::wrap_query(arg_len, |_: ()| STATE.received())
+}
@@ -0,0 +1,71 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![feature(arbitrary_self_types)]
+#![no_std]
+#![no_main]
+
+#[global_allocator]
+static ALLOCATOR: dallo::HostAlloc = dallo::HostAlloc;
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use dallo::{ModuleId, State};
+
+pub struct Puritan {
+    value: i64,
+}
+
+#[no_mangle]
+static SELF_ID: ModuleId = ModuleId::uninitialized();
+
+static mut STATE: State<Puritan> = State::new(Puritan { value: 0 });
+
+dallo::pure!("read_value", "sum_to", "sneaky_write", "sneaky_transact");
+
+impl Puritan {
+    pub fn read_value(&self) -> i64 {
+        self.value
+    }
+
+    pub fn sum_to(&self, n: i64) -> i64 {
+        let values: Vec<i64> = (0..=n).map(|i| self.value + i).collect();
+        values.iter().sum()
+    }
+
+    pub fn sneaky_write(&mut self, value: i64) -> i64 {
+        self.value = value;
+        self.value
+    }
+
+    pub fn sneaky_transact(self: &mut State<Self>, counter_id: ModuleId) {
+        self.transact(counter_id, "increment", ())
+    }
+}
+
+#[no_mangle]
+unsafe fn read_value(arg_len: u32) -> u32 {
+    dallo::wrap_query(arg_len, |_: ()| STATE.read_value())
+}
+
+#[no_mangle]
+unsafe fn sum_to(arg_len: u32) -> u32 {
+    dallo::wrap_query(arg_len, |n| STATE.sum_to(n))
+}
+
+#[no_mangle]
+unsafe fn sneaky_write(arg_len: u32) -> u32 {
+    dallo::wrap_query(arg_len, |value| STATE.sneaky_write(value))
+}
+
+#[no_mangle]
+unsafe fn sneaky_transact(arg_len: u32) -> u32 {
+    dallo::wrap_transaction(arg_len, |counter_id| {
+        STATE.sneaky_transact(counter_id)
+    })
+}
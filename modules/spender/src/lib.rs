@@ -44,9 +44,34 @@ impl Spender {
             false => (limit, spent_before, 0, 0, 0),
         }
     }
+
+    pub fn get_tx_limit_and_spent(&self) -> (u64, u64, u64, u64) {
+        let self_id = dallo::self_id();
+
+        match dallo::caller().is_uninitialized() {
+            true => {
+                let tx_spent_before = dallo::tx_spent();
+                let (tx_limit, tx_spent, frame_limit, _): (u64, u64, u64, u64) =
+                    dallo::query(self_id, "get_tx_limit_and_spent", ());
+
+                (tx_limit, tx_spent, frame_limit, tx_spent_before)
+            }
+            false => (
+                dallo::tx_limit(),
+                dallo::tx_spent(),
+                dallo::frame_limit(),
+                0,
+            ),
+        }
+    }
 }
 
 #[no_mangle]
 unsafe fn get_limit_and_spent(a: u32) -> u32 {
     dallo::wrap_query(a, |_: ()| STATE.get_limit_and_spent())
 }
+
+#[no_mangle]
+unsafe fn get_tx_limit_and_spent(a: u32) -> u32 {
+    dallo::wrap_query(a, |_: ()| STATE.get_tx_limit_and_spent())
+}
@@ -13,6 +13,7 @@ use dallo::{HostAlloc, ModuleId, State};
 #[global_allocator]
 static ALLOCATOR: HostAlloc = HostAlloc;
 
+use alloc::string::String;
 use alloc::vec::Vec;
 
 pub struct Vector {
@@ -24,6 +25,20 @@ static SELF_ID: ModuleId = ModuleId::uninitialized();
 
 static mut STATE: State<Vector> = State::new(Vector { a: Vec::new() });
 
+dallo::abi!(
+    "push": i16,
+    "pop": (),
+    "pop_or_revert": () => String,
+    "reserve": u32,
+    "try_reserve": u32,
+    "heap_stats": (),
+    "memory_pages": (),
+    "memory_pressure": (),
+    "replicate": u32,
+    "push_chunks": Vec<Vec<i16>>,
+    "arena_sum": u32,
+);
+
 impl Vector {
     pub fn push(&mut self, x: i16) {
         self.a.push(x);
@@ -32,6 +47,58 @@ impl Vector {
     pub fn pop(&mut self) -> Option<i16> {
         self.a.pop()
     }
+
+    pub fn pop_or_revert(&mut self) -> i16 {
+        match self.a.pop() {
+            Some(x) => x,
+            None => dallo::revert(String::from("the vector is empty")),
+        }
+    }
+
+    pub fn reserve(&mut self, additional: u32) {
+        self.a.reserve(additional as usize)
+    }
+
+    pub fn try_reserve(&mut self, additional: u32) -> bool {
+        self.a.try_reserve(additional as usize).is_ok()
+    }
+
+    pub fn heap_stats(&self) -> (u64, u64) {
+        dallo::heap_stats()
+    }
+
+    pub fn memory_pages(&self) -> (u32, u32) {
+        (dallo::memory_pages(), dallo::memory_limit())
+    }
+
+    pub fn memory_pressure(&self) -> bool {
+        dallo::memory_pressure()
+    }
+
+    pub fn replicate(&self, times: u32) -> Vec<i16> {
+        let mut replicated = Vec::with_capacity(self.a.len() * times as usize);
+        for _ in 0..times {
+            replicated.extend(&self.a);
+        }
+        replicated
+    }
+
+    pub fn arena_sum(&self, times: u32) -> i64 {
+        dallo::arena::scope(|arena| {
+            let mut replicated = arena.vec();
+            for _ in 0..times {
+                replicated.extend(self.a.iter().map(|x| *x as i64));
+            }
+            replicated.iter().sum()
+        })
+    }
+
+    pub fn push_chunks(&mut self, chunks: Vec<Vec<i16>>) -> Vec<Vec<i16>> {
+        for chunk in &chunks {
+            self.a.extend(chunk);
+        }
+        chunks
+    }
 }
 
 #[no_mangle]
@@ -43,3 +110,48 @@ unsafe fn push(arg_len: u32) -> u32 {
 unsafe fn pop(arg_len: u32) -> u32 {
     dallo::wrap_transaction(arg_len, |_arg: ()| STATE.pop())
 }
+
+#[no_mangle]
+unsafe fn pop_or_revert(arg_len: u32) -> u32 {
+    dallo::wrap_transaction(arg_len, |_arg: ()| STATE.pop_or_revert())
+}
+
+#[no_mangle]
+unsafe fn reserve(arg_len: u32) -> u32 {
+    dallo::wrap_transaction(arg_len, |arg| STATE.reserve(arg))
+}
+
+#[no_mangle]
+unsafe fn try_reserve(arg_len: u32) -> u32 {
+    dallo::wrap_transaction(arg_len, |arg| STATE.try_reserve(arg))
+}
+
+#[no_mangle]
+unsafe fn heap_stats(arg_len: u32) -> u32 {
+    dallo::wrap_query(arg_len, |_arg: ()| STATE.heap_stats())
+}
+
+#[no_mangle]
+unsafe fn memory_pages(arg_len: u32) -> u32 {
+    dallo::wrap_query(arg_len, |_arg: ()| STATE.memory_pages())
+}
+
+#[no_mangle]
+unsafe fn memory_pressure(arg_len: u32) -> u32 {
+    dallo::wrap_query(arg_len, |_arg: ()| STATE.memory_pressure())
+}
+
+#[no_mangle]
+unsafe fn push_chunks(arg_len: u32) -> u32 {
+    dallo::wrap_transaction(arg_len, |arg| STATE.push_chunks(arg))
+}
+
+#[no_mangle]
+unsafe fn replicate(arg_len: u32) -> u32 {
+    dallo::wrap_query(arg_len, |arg| STATE.replicate(arg))
+}
+
+#[no_mangle]
+unsafe fn arena_sum(arg_len: u32) -> u32 {
+    dallo::wrap_query(arg_len, |arg| STATE.arena_sum(arg))
+}